@@ -1 +1,60 @@
-fn main() { tauri_build::build() }
+// Commandes de l'application déclarées à l'ACL Tauri: chacune reçoit une permission `allow-<commande>`.
+// La fenêtre principale les reçoit toutes (permission `default`, cf. permissions/default.toml);
+// les pages FFE distantes (login / probe) n'ont droit qu'à `set_probe`.
+const COMMANDS: &[&str] = &[
+  "list_targets",
+  "add_target",
+  "delete_target",
+  "get_target_history",
+  "start_watcher",
+  "stop_watcher",
+  "get_mute_state",
+  "set_quiet_hours",
+  "snooze_target",
+  "unsnooze_target",
+  "get_smtp_settings",
+  "set_smtp_settings",
+  "list_email_recipients",
+  "add_email_recipient",
+  "delete_email_recipient",
+  "send_test_email",
+  "send_digest_now",
+  "list_push_channels",
+  "add_push_channel",
+  "delete_push_channel",
+  "test_push_channel",
+  "open_url",
+  "store_epreuves",
+  "list_epreuves",
+  "watch_contest",
+  "list_engages",
+  "search_contests",
+  "get_contest_detail",
+  "list_contest_details",
+  "delete_contest_detail",
+  "store_debug",
+  "store_links",
+  "set_login_debug",
+  "get_login_debug",
+  "set_probe",
+  "save_ffe_session",
+  "set_session_passphrase",
+  "unlock_ffe_session",
+  "is_ffe_connected",
+  "check_ffe_connected",
+  "ffe_login_with_credentials",
+  "ffe_logout",
+  "list_ffe_accounts",
+  "add_ffe_account",
+  "delete_ffe_account",
+  "get_session_health",
+  "get_http_settings",
+  "set_http_settings",
+];
+
+fn main() {
+  tauri_build::try_build(
+    tauri_build::Attributes::new().app_manifest(tauri_build::AppManifest::new().commands(COMMANDS)),
+  )
+  .expect("tauri build")
+}
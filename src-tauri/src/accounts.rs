@@ -0,0 +1,124 @@
+// Comptes FFE nommés (compte club, licence coach, cavaliers...).
+// Chaque compte a son propre cookie jar, sa session persistée et ses WebViews de login/probe.
+// Le compte "default" garde les labels historiques (`ffe-login`, `ffe-probe`) et le store
+// de cookies par défaut de la WebView; les autres ont un dossier de données WebView isolé.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::cookie_jar::CookieJar;
+
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+static JARS: OnceLock<Mutex<HashMap<String, Arc<CookieJar>>>> = OnceLock::new();
+
+/// Nom de compte normalisé (None / vide => compte par défaut).
+pub fn normalize(account: Option<&str>) -> String {
+  match account.map(|a| a.trim().to_lowercase()) {
+    Some(a) if !a.is_empty() => a,
+    _ => DEFAULT_ACCOUNT.to_string(),
+  }
+}
+
+/// Les noms servent aussi de suffixe de fichier / label de fenêtre: on les limite à [a-z0-9_-].
+pub fn validate_name(name: &str) -> Result<String, String> {
+  let n = name.trim().to_lowercase();
+  if n.is_empty() || n.len() > 32 {
+    return Err("Nom de compte requis (32 caractères max).".into());
+  }
+  if !n.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_') {
+    return Err("Nom de compte: lettres, chiffres, '-' et '_' uniquement.".into());
+  }
+  if is_reserved_windows_name(&n) {
+    return Err(format!("Nom de compte réservé par le système: {n}."));
+  }
+  Ok(n)
+}
+
+/// Noms de périphériques Windows, inutilisables comme dossier de données WebView.
+fn is_reserved_windows_name(n: &str) -> bool {
+  matches!(n, "con" | "prn" | "aux" | "nul")
+    || ["com", "lpt"].iter().any(|p| n.strip_prefix(p).is_some_and(|d| d.len() == 1 && matches!(d.as_bytes()[0], b'1'..=b'9')))
+}
+
+pub fn jar(account: &str) -> Arc<CookieJar> {
+  let cell = JARS.get_or_init(|| Mutex::new(HashMap::new()));
+  cell.lock().unwrap().entry(account.to_string()).or_insert_with(|| Arc::new(CookieJar::new())).clone()
+}
+
+pub fn all_jars() -> Vec<(String, Arc<CookieJar>)> {
+  JARS
+    .get()
+    .map(|m| m.lock().unwrap().iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+    .unwrap_or_default()
+}
+
+pub fn forget_jar(account: &str) {
+  if let Some(m) = JARS.get() {
+    m.lock().unwrap().remove(account);
+  }
+}
+
+pub fn login_label(account: &str) -> String {
+  if account == DEFAULT_ACCOUNT { "ffe-login".into() } else { format!("ffe-login-{account}") }
+}
+
+pub fn probe_label(account: &str) -> String {
+  if account == DEFAULT_ACCOUNT { "ffe-probe".into() } else { format!("ffe-probe-{account}") }
+}
+
+/// Dossier de données WebView propre au compte (None => store par défaut).
+pub fn webview_data_dir(app_data_dir: &Path, account: &str) -> Option<PathBuf> {
+  if account == DEFAULT_ACCOUNT {
+    None
+  } else {
+    Some(app_data_dir.join("webviews").join(account))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn normalize_defaults_and_case() {
+    assert_eq!(normalize(None), DEFAULT_ACCOUNT);
+    assert_eq!(normalize(Some("")), DEFAULT_ACCOUNT);
+    assert_eq!(normalize(Some("   ")), DEFAULT_ACCOUNT);
+    assert_eq!(normalize(Some(" Club ")), "club");
+    assert_eq!(normalize(Some("DEFAULT")), DEFAULT_ACCOUNT);
+  }
+
+  #[test]
+  fn validate_name_rules() {
+    assert_eq!(validate_name(" Club_Coach-2 "), Ok("club_coach-2".into()));
+    assert_eq!(validate_name(&"a".repeat(32)), Ok("a".repeat(32)));
+    assert_eq!(validate_name("default"), Ok(DEFAULT_ACCOUNT.into()));
+    assert_eq!(validate_name("com0"), Ok("com0".into()));
+    assert_eq!(validate_name("console"), Ok("console".into()));
+
+    for empty in ["", "   ", &"a".repeat(33)] {
+      assert!(validate_name(empty).unwrap_err().contains("requis"), "{empty:?}");
+    }
+    // Séparateurs, traversée de dossier et caractères interdits dans un nom de dossier
+    for bad in [".", "..", "../club", "a/b", "a\\b", "c:", "a*", "a?", "a\"b", "a<b", "a>b", "a|b", "a b", "a.b", "écurie", "a\0b"] {
+      assert!(validate_name(bad).unwrap_err().contains("uniquement"), "{bad:?}");
+    }
+    for reserved in ["con", "NUL", "aux", "prn", "com1", "LPT9"] {
+      assert!(validate_name(reserved).unwrap_err().contains("réservé"), "{reserved:?}");
+    }
+  }
+
+  #[test]
+  fn labels_and_webview_dir() {
+    let base = Path::new("/data");
+    assert_eq!(webview_data_dir(base, DEFAULT_ACCOUNT), None);
+    assert_eq!((login_label(DEFAULT_ACCOUNT), probe_label(DEFAULT_ACCOUNT)), ("ffe-login".into(), "ffe-probe".into()));
+
+    let name = validate_name("Club").unwrap();
+    let dir = webview_data_dir(base, &name).unwrap();
+    assert_eq!(dir, base.join("webviews").join("club"));
+    assert_eq!(dir.parent(), Some(base.join("webviews").as_path()));
+    assert_eq!((login_label(&name), probe_label(&name)), ("ffe-login-club".into(), "ffe-probe-club".into()));
+  }
+}
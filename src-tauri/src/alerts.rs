@@ -0,0 +1,400 @@
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use chrono::NaiveTime;
+use rusqlite::Connection;
+use crate::{
+  db, mailer, push,
+  models::{Alert, AlertKind, MuteState, QueuedSummary, QuietHours, Severity},
+};
+
+pub const QUIET_HOURS_KEY: &str = "quiet_hours";
+
+pub fn load_quiet_hours(conn: &Connection) -> QuietHours {
+  db::get_setting(conn, QUIET_HOURS_KEY).ok().flatten().unwrap_or_default()
+}
+
+pub fn parse_hhmm(s: &str) -> Option<NaiveTime> {
+  NaiveTime::parse_from_str(s.trim(), "%H:%M").ok()
+}
+
+/// true si `now` tombe dans la plage [from, to[ (la plage peut passer minuit: 23:00 → 07:00).
+pub fn in_quiet_hours(q: &QuietHours, now: NaiveTime) -> bool {
+  if !q.enabled {
+    return false;
+  }
+  let (Some(from), Some(to)) = (parse_hhmm(&q.from), parse_hhmm(&q.to)) else {
+    return false;
+  };
+  if from <= to {
+    now >= from && now < to
+  } else {
+    now >= from || now < to
+  }
+}
+
+/// Heure locale de l'instant `now` (timestamp unix), comparée aux quiet hours.
+fn local_time(now: i64) -> NaiveTime {
+  chrono::DateTime::from_timestamp(now, 0)
+    .map(|t| t.with_timezone(&chrono::Local).time())
+    .unwrap_or_default()
+}
+
+fn is_muted(conn: &Connection, q: &QuietHours, target_id: i64, now: i64) -> bool {
+  let local = local_time(now);
+  let snoozed = db::snoozed_until(conn, target_id).ok().flatten().is_some_and(|until| until > now);
+  snoozed || in_quiet_hours(q, local)
+}
+
+/// Met l'alerte en file si la cible est en pause (quiet hours / snooze),
+/// sauf pour les types "critiques" configurés (ex: Opened). Renvoie true si l'alerte est différée.
+pub fn defer_if_muted(conn: &Connection, alert: &Alert, now: i64) -> rusqlite::Result<bool> {
+  let q = load_quiet_hours(conn);
+  if q.override_kinds.contains(&alert.kind) || !is_muted(conn, &q, alert.target_id, now) {
+    return Ok(false);
+  }
+  db::queue_alert(conn, alert, now)?;
+  Ok(true)
+}
+
+pub fn mute_state(conn: &Connection, now: i64) -> rusqlite::Result<MuteState> {
+  let quiet_hours = load_quiet_hours(conn);
+  let quiet_now = in_quiet_hours(&quiet_hours, local_time(now));
+  Ok(MuteState {
+    quiet_hours,
+    quiet_now,
+    snoozes: db::active_snoozes(conn, now)?,
+    queued: db::count_queued_alerts(conn)?,
+  })
+}
+
+/// Envoi "hors UI" d'une alerte: push + email. L'échec de l'un n'empêche pas l'autre;
+/// les erreurs sont renvoyées ensemble.
+pub async fn deliver_remote(db_path: std::path::PathBuf, alert: Alert) -> anyhow::Result<()> {
+  let mut errors = Vec::new();
+  let pushed = match push::client() {
+    Ok(client) => push::send_alert(&client, db_path.clone(), alert.clone()).await,
+    Err(e) => Err(e),
+  };
+  if let Err(e) = pushed {
+    errors.push(format!("push: {e:#}"));
+  }
+  if let Err(e) = mailer::send_alert(db_path, alert).await {
+    errors.push(format!("email: {e:#}"));
+  }
+  if !errors.is_empty() {
+    anyhow::bail!("alerte non envoyée ({})", errors.join(" ; "));
+  }
+  Ok(())
+}
+
+fn summarise(queued: Vec<(i64, Alert)>) -> Vec<QueuedSummary> {
+  let mut by_target: BTreeMap<i64, QueuedSummary> = BTreeMap::new();
+  for (ts, a) in queued {
+    let s = by_target.entry(a.target_id).or_insert_with(|| QueuedSummary {
+      target_id: a.target_id,
+      label: a.label.clone(),
+      url: a.url.clone(),
+      cavalier: a.cavalier.clone(),
+      opened: 0,
+      slot_freed: 0,
+      session_expired: 0,
+      contest_changed: 0,
+      engagement_lost: 0,
+      first_ts: ts,
+      last_ts: ts,
+    });
+    match a.kind {
+      AlertKind::Opened => s.opened += 1,
+      AlertKind::SlotFreed => s.slot_freed += 1,
+      AlertKind::SessionExpired => s.session_expired += 1,
+      AlertKind::ContestChanged => s.contest_changed += 1,
+      AlertKind::EngagementLost => s.engagement_lost += 1,
+    }
+    s.first_ts = s.first_ts.min(ts);
+    s.last_ts = s.last_ts.max(ts);
+  }
+  by_target.into_values().collect()
+}
+
+fn summary_text(s: &QueuedSummary) -> String {
+  let mut parts = Vec::new();
+  if s.opened > 0 { parts.push(format!("ouverture ×{}", s.opened)); }
+  if s.slot_freed > 0 { parts.push(format!("place libérée ×{}", s.slot_freed)); }
+  if s.session_expired > 0 { parts.push("session FFE expirée".to_string()); }
+  if s.contest_changed > 0 { parts.push(format!("programme modifié ×{}", s.contest_changed)); }
+  if s.engagement_lost > 0 { parts.push("engagement disparu".to_string()); }
+  let last = chrono::DateTime::from_timestamp(s.last_ts, 0)
+    .map(|t| t.with_timezone(&chrono::Local).format("%H:%M").to_string())
+    .unwrap_or_default();
+  format!("Pendant la pause : {} (dernière à {}) — {}", parts.join(", "), last, s.url)
+}
+
+async fn deliver_summary(db_path: &std::path::Path, s: &QueuedSummary) -> anyhow::Result<()> {
+  let (channels, smtp, to) = {
+    let conn = Connection::open(db_path)?;
+    (
+      db::push_channels_for(&conn, s.target_id, s.cavalier.as_deref()).unwrap_or_default(),
+      mailer::load_settings(&conn),
+      s.cavalier.as_deref().map(|c| db::recipients_for_rider(&conn, c).unwrap_or_default()).unwrap_or_default(),
+    )
+  };
+
+  let title = format!("Résumé des alertes — {}", s.label);
+  let msg = push::PushMessage {
+    title: title.clone(),
+    message: summary_text(s),
+    severity: Severity::High,
+    click_url: Some(s.url.clone()),
+  };
+  let mut errors = Vec::new();
+  if !channels.is_empty() {
+    let client = push::client()?;
+    for ch in &channels {
+      if let Err(e) = push::send(&client, ch, &msg).await {
+        errors.push(format!("push #{}: {e:#}", ch.id));
+      }
+    }
+  }
+  if smtp.enabled {
+    if let Err(e) = mailer::send(&smtp, &to, &format!("[FFE] {title}"), summary_text(s)).await {
+      errors.push(format!("email: {e:#}"));
+    }
+  }
+  if !errors.is_empty() {
+    anyhow::bail!("résumé non envoyé pour {} ({})", s.label, errors.join(" ; "));
+  }
+  Ok(())
+}
+
+/// Vide la file des cibles qui ne sont plus en pause et renvoie les résumés, avec les erreurs
+/// d'envoi (un résumé en échec n'empêche pas les autres).
+pub async fn flush_queue(db_path: &std::path::Path, now: i64) -> anyhow::Result<(Vec<QueuedSummary>, Vec<String>)> {
+  let queued = {
+    let mut conn = Connection::open(db_path)?;
+    let q = load_quiet_hours(&conn);
+    let ready: Vec<i64> = db::queued_target_ids(&conn)?
+      .into_iter()
+      .filter(|id| !is_muted(&conn, &q, *id, now))
+      .collect();
+    if ready.is_empty() {
+      return Ok((Vec::new(), Vec::new()));
+    }
+    db::take_queued_alerts(&mut conn, &ready)?
+  };
+
+  let summaries = summarise(queued);
+  let mut errors = Vec::new();
+  for s in &summaries {
+    if let Err(e) = deliver_summary(db_path, s).await {
+      errors.push(format!("{e:#}"));
+    }
+  }
+  Ok((summaries, errors))
+}
+
+/// Boucle de fin de pause: toutes les minutes, résume les alertes en file dont la pause est terminée.
+/// Les erreurs (file illisible, envoi impossible) sont remontées par `on_error`.
+pub async fn flush_loop(
+  db_path: std::path::PathBuf,
+  on_summary: Arc<dyn Fn(Vec<QueuedSummary>) + Send + Sync>,
+  on_error: Arc<dyn Fn(String) + Send + Sync>,
+) {
+  loop {
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    match flush_queue(&db_path, chrono::Utc::now().timestamp()).await {
+      Ok((summaries, errors)) => {
+        if !summaries.is_empty() {
+          on_summary(summaries);
+        }
+        for e in errors {
+          on_error(e);
+        }
+      }
+      Err(e) => on_error(format!("file d'alertes non vidée: {e:#}")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use chrono::{NaiveDate, TimeZone};
+
+  fn hm(h: u32, m: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(h, m, 0).unwrap()
+  }
+
+  fn quiet(from: &str, to: &str) -> QuietHours {
+    QuietHours { enabled: true, from: from.into(), to: to.into(), override_kinds: vec![AlertKind::Opened] }
+  }
+
+  /// Timestamp d'une heure locale du 18/10/2026.
+  fn local_ts(h: u32, m: u32) -> i64 {
+    let dt = NaiveDate::from_ymd_opt(2026, 10, 18).unwrap().and_time(hm(h, m));
+    chrono::Local.from_local_datetime(&dt).earliest().unwrap().timestamp()
+  }
+
+  fn alert(target_id: i64, kind: AlertKind) -> Alert {
+    Alert {
+      target_id,
+      kind,
+      label: format!("Cible {target_id}"),
+      url: format!("https://ffecompet.ffe.com/concours/20263508{target_id}"),
+      cavalier: Some("Marie Dupont".into()),
+      cheval: None,
+      account: None,
+      detail: None,
+    }
+  }
+
+  /// Base avec les cibles 1, 2 et 3.
+  fn init(conn: &Connection) {
+    db::init_db(conn).unwrap();
+    for id in 1..=3 {
+      let a = alert(id, AlertKind::Opened);
+      db::add_target(conn, crate::models::AddTargetPayload {
+        label: a.label,
+        url: a.url,
+        cheval: None,
+        cavalier: a.cavalier,
+        interval_normal_sec: None,
+        interval_hot_sec: None,
+        hot_from: None,
+        hot_to: None,
+        account: None,
+        kind: crate::models::WatchKind::Status,
+        check_engaged: false,
+      })
+      .unwrap();
+    }
+  }
+
+  fn memory_db() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    init(&conn);
+    conn
+  }
+
+  #[test]
+  fn quiet_hours_same_day() {
+    let q = quiet("12:00", "14:00");
+    assert!(!in_quiet_hours(&q, hm(11, 59)));
+    assert!(in_quiet_hours(&q, hm(12, 0)));
+    assert!(in_quiet_hours(&q, hm(13, 59)));
+    // `to` exclu
+    assert!(!in_quiet_hours(&q, hm(14, 0)));
+  }
+
+  #[test]
+  fn quiet_hours_wrap_midnight() {
+    let q = quiet("23:00", "07:00");
+    assert!(!in_quiet_hours(&q, hm(22, 59)));
+    assert!(in_quiet_hours(&q, hm(23, 0)));
+    assert!(in_quiet_hours(&q, hm(0, 0)));
+    assert!(in_quiet_hours(&q, hm(6, 59)));
+    assert!(!in_quiet_hours(&q, hm(7, 0)));
+    assert!(!in_quiet_hours(&q, hm(12, 0)));
+  }
+
+  #[test]
+  fn quiet_hours_disabled_or_invalid() {
+    assert!(!in_quiet_hours(&QuietHours { enabled: false, ..quiet("00:00", "23:59") }, hm(12, 0)));
+    assert!(!in_quiet_hours(&quiet("25:00", "07:00"), hm(2, 0)));
+    assert!(!in_quiet_hours(&quiet("23:00", ""), hm(23, 30)));
+    // from == to: plage vide
+    assert!(!in_quiet_hours(&quiet("08:00", "08:00"), hm(8, 0)));
+    assert_eq!(parse_hhmm(" 7:05 "), Some(hm(7, 5)));
+  }
+
+  #[test]
+  fn local_time_from_timestamp() {
+    assert_eq!(local_time(local_ts(23, 30)), hm(23, 30));
+    assert_eq!(local_time(local_ts(6, 59)), hm(6, 59));
+  }
+
+  #[test]
+  fn defer_during_quiet_hours_unless_overridden() {
+    let conn = memory_db();
+    db::set_setting(&conn, QUIET_HOURS_KEY, &quiet("23:00", "07:00")).unwrap();
+
+    let night = local_ts(23, 30);
+    assert!(defer_if_muted(&conn, &alert(1, AlertKind::SlotFreed), night).unwrap());
+    assert!(defer_if_muted(&conn, &alert(1, AlertKind::ContestChanged), night).unwrap());
+    // Type critique configuré: délivré malgré la pause
+    assert!(!defer_if_muted(&conn, &alert(1, AlertKind::Opened), night).unwrap());
+    // Fin de la plage
+    assert!(!defer_if_muted(&conn, &alert(1, AlertKind::SlotFreed), local_ts(7, 0)).unwrap());
+    assert_eq!(db::count_queued_alerts(&conn).unwrap(), 2);
+
+    let state = mute_state(&conn, night).unwrap();
+    assert!(state.quiet_now);
+    assert_eq!(state.queued, 2);
+    assert!(!mute_state(&conn, local_ts(12, 0)).unwrap().quiet_now);
+  }
+
+  #[test]
+  fn defer_while_snoozed() {
+    let conn = memory_db();
+    let noon = local_ts(12, 0);
+    db::snooze_target(&conn, 2, noon + 3600).unwrap();
+
+    assert!(defer_if_muted(&conn, &alert(2, AlertKind::SlotFreed), noon).unwrap());
+    assert!(!defer_if_muted(&conn, &alert(2, AlertKind::Opened), noon).unwrap());
+    // Autre cible, ou snooze expiré
+    assert!(!defer_if_muted(&conn, &alert(3, AlertKind::SlotFreed), noon).unwrap());
+    assert!(!defer_if_muted(&conn, &alert(2, AlertKind::SlotFreed), noon + 3600).unwrap());
+    assert!(!is_muted(&conn, &load_quiet_hours(&conn), 2, noon + 3600));
+  }
+
+  #[test]
+  fn queued_summary() {
+    let t0 = local_ts(23, 10);
+    let queued = vec![
+      (t0, alert(1, AlertKind::SlotFreed)),
+      (t0 + 600, alert(2, AlertKind::ContestChanged)),
+      (t0 + 1200, alert(1, AlertKind::SlotFreed)),
+      (t0 + 60, alert(1, AlertKind::SessionExpired)),
+      (t0 + 120, alert(1, AlertKind::Opened)),
+      (t0 + 1800, alert(1, AlertKind::EngagementLost)),
+    ];
+    let summaries = summarise(queued);
+    assert_eq!(summaries.len(), 2);
+
+    let s = &summaries[0];
+    assert_eq!((s.target_id, s.label.as_str()), (1, "Cible 1"));
+    assert_eq!((s.opened, s.slot_freed, s.session_expired, s.contest_changed, s.engagement_lost), (1, 2, 1, 0, 1));
+    assert_eq!((s.first_ts, s.last_ts), (t0, t0 + 1800));
+    assert_eq!(
+      summary_text(s),
+      "Pendant la pause : ouverture ×1, place libérée ×2, session FFE expirée, engagement disparu (dernière à 23:40) — https://ffecompet.ffe.com/concours/202635081"
+    );
+
+    let s = &summaries[1];
+    assert_eq!((s.target_id, s.contest_changed, s.first_ts, s.last_ts), (2, 1, t0 + 600, t0 + 600));
+    assert_eq!(summary_text(s), "Pendant la pause : programme modifié ×1 (dernière à 23:20) — https://ffecompet.ffe.com/concours/202635082");
+  }
+
+  #[tokio::test]
+  async fn flush_takes_only_unmuted_targets() {
+    let path = std::env::temp_dir().join(format!("ffe_alerts_flush_{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let noon = local_ts(12, 0);
+    {
+      let conn = Connection::open(&path).unwrap();
+      init(&conn);
+      db::queue_alert(&conn, &alert(1, AlertKind::SlotFreed), noon - 60).unwrap();
+      db::queue_alert(&conn, &alert(2, AlertKind::SlotFreed), noon - 60).unwrap();
+      // Cible 2 toujours en pause
+      db::snooze_target(&conn, 2, noon + 3600).unwrap();
+    }
+
+    // Ni canal push ni email configurés: rien à envoyer, pas d'erreur
+    let (summaries, errors) = flush_queue(&path, noon).await.unwrap();
+    assert_eq!(summaries.iter().map(|s| s.target_id).collect::<Vec<_>>(), vec![1]);
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(db::count_queued_alerts(&Connection::open(&path).unwrap()).unwrap(), 1);
+
+    let (summaries, _) = flush_queue(&path, noon + 3600).await.unwrap();
+    assert_eq!(summaries.iter().map(|s| s.target_id).collect::<Vec<_>>(), vec![2]);
+    let _ = std::fs::remove_file(&path);
+  }
+}
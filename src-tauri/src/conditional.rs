@@ -0,0 +1,154 @@
+// Requêtes conditionnelles du watcher: ETag / Last-Modified mémorisés par cible, et empreinte
+// de la partie utile du HTML (sans scripts, styles, commentaires ni champs cachés type jeton CSRF)
+// pour ignorer les réponses 200 dont le contenu n'a pas bougé.
+
+use std::sync::OnceLock;
+use regex::Regex;
+use reqwest::header::{HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use crate::embedded::extract_json_states;
+
+/// Validateurs HTTP + empreinte du dernier contenu vu pour une URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CachedPage {
+  pub etag: Option<String>,
+  pub last_modified: Option<String>,
+  pub body_hash: Option<String>,
+}
+
+impl CachedPage {
+  /// Ajoute If-None-Match / If-Modified-Since à la requête.
+  pub fn apply(&self, mut req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    if let Some(etag) = self.etag.as_deref() {
+      req = req.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(lm) = self.last_modified.as_deref() {
+      req = req.header(IF_MODIFIED_SINCE, lm);
+    }
+    req
+  }
+
+  /// Validateurs renvoyés par le serveur (le hash est complété après lecture du corps).
+  pub fn from_response(resp: &reqwest::Response) -> Self {
+    let header = |name: HeaderName| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    Self { etag: header(ETAG), last_modified: header(LAST_MODIFIED), body_hash: None }
+  }
+}
+
+fn noise_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r#"(?is)<script\b.*?</script>|<style\b.*?</style>|<noscript\b.*?</noscript>|<!--.*?-->|<input\b[^>]*type=["']?hidden[^>]*>"#).unwrap()
+  })
+}
+
+/// Partie du document qui porte l'information (contenu de `<main>`, sinon de `<body>`), nettoyée.
+pub fn relevant_region(html: &str) -> String {
+  let lower = html.to_ascii_lowercase();
+  let slice = ["<main", "<body"]
+    .iter()
+    .find_map(|tag| {
+      let start = lower.find(tag)?;
+      let close = format!("</{}", &tag[1..]);
+      let end = lower[start..].find(&close).map(|e| start + e).unwrap_or(html.len());
+      Some(&html[start..end])
+    })
+    .unwrap_or(html);
+
+  let cleaned = noise_re().replace_all(slice, " ");
+  cleaned.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Empreinte stable (FNV-1a 64 bits) de la partie utile du HTML. L'état JSON embarqué est
+/// inclus: sur une page hydratée, c'est lui qui change (les scripts sont retirés de la région).
+pub fn fingerprint(html: &str) -> String {
+  let states = extract_json_states(html).iter().map(|v| v.to_string()).collect::<String>();
+  hash_bytes(relevant_region(html).bytes().chain(states.bytes()))
+}
+
+/// FNV-1a 64 bits en hexadécimal.
+pub fn hash_bytes(bytes: impl IntoIterator<Item = u8>) -> String {
+  let mut h: u64 = 0xcbf29ce484222325;
+  for b in bytes {
+    h ^= b as u64;
+    h = h.wrapping_mul(0x100000001b3);
+  }
+  format!("{h:016x}")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn region_prefers_main_and_strips_noise() {
+    let html = r#"<html><head><title>T</title><script>var x=1;</script></head>
+<body><nav>Menu</nav><main class="c">
+  <h1>Épreuve   3</h1><!-- généré à 12:03:04 -->
+  <style>.a{}</style><input type="hidden" name="_csrf" value="abc123">
+  <noscript>JS requis</noscript><script>track()</script>
+  <p>Engagements   ouverts</p>
+</main><footer>Pied</footer></body></html>"#;
+    assert_eq!(relevant_region(html), r#"<main class="c"> <h1>Épreuve 3</h1> <p>Engagements ouverts</p>"#);
+  }
+
+  #[test]
+  fn region_falls_back_to_body_then_document() {
+    let body = "<html><head><title>T</title></head><body><p>A</p><script>x()</script></body></html>";
+    assert_eq!(relevant_region(body), "<body><p>A</p>");
+    assert_eq!(relevant_region("<p>brut</p>  <!-- c -->"), "<p>brut</p>");
+    // Balise non fermée: jusqu'à la fin du document
+    assert_eq!(relevant_region("<main><p>A</p>"), "<main><p>A</p>");
+  }
+
+  #[test]
+  fn fingerprint_ignores_noise() {
+    let a = r#"<body><p>Clôture le 12/04</p><input type="hidden" value="tok1"><script>var t=1</script></body>"#;
+    let b = r#"<body><p>Clôture   le 12/04</p><input type='hidden' value="tok2"><script>var t=2</script></body>"#;
+    assert_eq!(fingerprint(a), fingerprint(b));
+    assert_ne!(fingerprint(a), fingerprint("<body><p>Clôture le 13/04</p></body>"));
+    assert_eq!(fingerprint(a).len(), 16);
+  }
+
+  #[test]
+  fn fingerprint_includes_embedded_state() {
+    let page = |open: bool| {
+      format!(r#"<body><div id="root"></div><script id="__NEXT_DATA__" type="application/json">{{"epreuve":{{"engagementsOuverts":{open}}}}}</script></body>"#)
+    };
+    assert_eq!(relevant_region(&page(true)), relevant_region(&page(false)));
+    assert_ne!(fingerprint(&page(true)), fingerprint(&page(false)));
+  }
+
+  #[test]
+  fn hash_is_fnv1a() {
+    assert_eq!(hash_bytes(*b""), "cbf29ce484222325");
+    assert_eq!(hash_bytes(*b"a"), "af63dc4c8601ec8c");
+  }
+
+  #[test]
+  fn from_response_reads_validators() {
+    let resp: reqwest::Response = http::Response::builder()
+      .header("ETag", "\"abc\"")
+      .header("Last-Modified", "Wed, 01 Apr 2026 08:00:00 GMT")
+      .body("x")
+      .unwrap()
+      .into();
+    let page = CachedPage::from_response(&resp);
+    assert_eq!(page.etag.as_deref(), Some("\"abc\""));
+    assert_eq!(page.last_modified.as_deref(), Some("Wed, 01 Apr 2026 08:00:00 GMT"));
+    assert_eq!(page.body_hash, None);
+
+    let bare: reqwest::Response = http::Response::builder().body("x").unwrap().into();
+    assert_eq!(CachedPage::from_response(&bare), CachedPage::default());
+  }
+
+  #[test]
+  fn apply_sets_conditional_headers() {
+    let page = CachedPage { etag: Some("\"abc\"".into()), last_modified: Some("Wed, 01 Apr 2026 08:00:00 GMT".into()), body_hash: None };
+    let req = page.apply(reqwest::Client::new().get("https://ffecompet.ffe.com/")).build().unwrap();
+    assert_eq!(req.headers()[IF_NONE_MATCH], "\"abc\"");
+    assert_eq!(req.headers()[IF_MODIFIED_SINCE], "Wed, 01 Apr 2026 08:00:00 GMT");
+
+    let req = CachedPage::default().apply(reqwest::Client::new().get("https://ffecompet.ffe.com/")).build().unwrap();
+    assert!(req.headers().is_empty());
+  }
+}
@@ -0,0 +1,145 @@
+// Cache SQLite des épreuves par concours (stale-while-revalidate): la liste enregistrée est
+// renvoyée tout de suite, et relue en tâche de fond une fois sa durée de validité dépassée.
+// L'empreinte du contenu brut évite de comparer les listes quand la page n'a pas bougé.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use rusqlite::Connection;
+use crate::{db, epreuves::{self, EpreuveDetail}};
+
+/// Durée de validité d'une liste d'épreuves (secondes).
+pub const DEFAULT_TTL_SEC: i64 = 300;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedContest {
+  pub epreuves: Vec<EpreuveDetail>,
+  /// Empreinte du contenu brut (page ou réponse JSON) d'où viennent les épreuves
+  pub payload_hash: String,
+  pub fetched_at: i64,
+  pub ttl_sec: i64,
+}
+
+impl CachedContest {
+  pub fn is_fresh(&self, now: i64) -> bool {
+    now - self.fetched_at < self.ttl_sec
+  }
+}
+
+/// Concours en cours de relecture (une seule tâche de fond par concours).
+fn in_flight() -> &'static Mutex<HashSet<String>> {
+  static SET: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+  SET.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Réservation d'une relecture, libérée au drop.
+pub struct RefreshGuard(String);
+
+impl Drop for RefreshGuard {
+  fn drop(&mut self) {
+    in_flight().lock().unwrap_or_else(PoisonError::into_inner).remove(&self.0);
+  }
+}
+
+/// None si une relecture du concours est déjà en cours.
+pub fn begin_refresh(contest_id: &str) -> Option<RefreshGuard> {
+  // Un panic pendant une relecture ne doit pas bloquer les suivantes: l'ensemble reste cohérent
+  in_flight()
+    .lock()
+    .unwrap_or_else(PoisonError::into_inner)
+    .insert(contest_id.to_string())
+    .then(|| RefreshGuard(contest_id.to_string()))
+}
+
+pub fn load(db_path: &Path, contest_id: &str) -> Result<Option<CachedContest>, String> {
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::get_contest_cache(&conn, contest_id).map_err(|e| e.to_string())
+}
+
+/// La liste relue diffère de celle enregistrée (pas de cache = changement). Même empreinte:
+/// contenu identique; empreinte différente mais mêmes épreuves: bruit de la page (jeton, date).
+fn is_changed(previous: Option<&CachedContest>, payload_hash: &str, epreuves: &[EpreuveDetail]) -> bool {
+  previous.is_none_or(|p| p.payload_hash != payload_hash && p.epreuves != epreuves)
+}
+
+/// Relit le concours et met le cache à jour. Renvoie la nouvelle liste et `true`
+/// si elle diffère de celle qui était enregistrée.
+pub async fn refresh(db_path: &Path, client: &reqwest::Client, contest_id: &str) -> Result<(Vec<EpreuveDetail>, bool), String> {
+  let (epreuves, payload_hash) = epreuves::fetch(client, contest_id).await?;
+  let now = chrono::Utc::now().timestamp();
+
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  let previous = db::get_contest_cache(&conn, contest_id).map_err(|e| e.to_string())?;
+  let changed = is_changed(previous.as_ref(), &payload_hash, &epreuves);
+  let ttl_sec = previous.map_or(DEFAULT_TTL_SEC, |p| p.ttl_sec);
+  db::set_contest_cache(&conn, contest_id, &CachedContest { epreuves: epreuves.clone(), payload_hash, fetched_at: now, ttl_sec })
+    .map_err(|e| e.to_string())?;
+  Ok((epreuves, changed))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TABLE_HTML: &str = include_str!("../tests/fixtures/concours_table.html");
+
+  fn cached(epreuves: Vec<EpreuveDetail>, payload_hash: &str, fetched_at: i64) -> CachedContest {
+    CachedContest { epreuves, payload_hash: payload_hash.into(), fetched_at, ttl_sec: DEFAULT_TTL_SEC }
+  }
+
+  fn list() -> Vec<EpreuveDetail> {
+    let list = epreuves::from_text("202635080", &epreuves::html_to_text(TABLE_HTML));
+    assert!(!list.is_empty());
+    list
+  }
+
+  #[test]
+  fn freshness() {
+    let c = cached(Vec::new(), "h", 1_000);
+    assert!(c.is_fresh(1_000));
+    assert!(c.is_fresh(1_000 + DEFAULT_TTL_SEC - 1));
+    assert!(!c.is_fresh(1_000 + DEFAULT_TTL_SEC));
+    assert!(!c.is_fresh(1_000 + 10 * DEFAULT_TTL_SEC));
+    assert!(!CachedContest { ttl_sec: 0, ..c }.is_fresh(1_000));
+  }
+
+  #[test]
+  fn changed_decision() {
+    let old = list();
+    let previous = cached(old.clone(), "aaaa", 0);
+
+    // Premier chargement
+    assert!(is_changed(None, "aaaa", &old));
+    // Même empreinte: inchangé (la liste n'est pas comparée)
+    assert!(!is_changed(Some(&previous), "aaaa", &old));
+    // Empreinte différente, mêmes épreuves (jeton, horodatage dans la page)
+    assert!(!is_changed(Some(&previous), "bbbb", &old));
+
+    let mut new = old.clone();
+    new[0].max = Some(new[0].max.unwrap_or(0) + 10);
+    assert!(is_changed(Some(&previous), "bbbb", &new));
+    assert!(is_changed(Some(&previous), "bbbb", &old[1..]));
+  }
+
+  #[test]
+  fn refresh_guard() {
+    let id = "202699999";
+    let guard = begin_refresh(id).expect("première réservation");
+    assert!(begin_refresh(id).is_none());
+    drop(guard);
+    assert!(begin_refresh(id).is_some());
+  }
+
+  #[test]
+  fn guard_survives_poisoned_lock() {
+    let _ = std::thread::spawn(|| {
+      let _g = in_flight().lock().unwrap();
+      panic!("empoisonne le verrou");
+    })
+    .join();
+    assert!(in_flight().is_poisoned());
+    let guard = begin_refresh("202699998").expect("réservation malgré le verrou empoisonné");
+    drop(guard);
+    assert!(begin_refresh("202699998").is_some());
+  }
+}
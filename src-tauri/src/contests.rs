@@ -0,0 +1,1002 @@
+// Recherche de concours sur ffecompet (formulaire de recherche: période, département / région,
+// discipline, niveau, organisateur). Les résultats sont lus dans l'état JSON embarqué si la page
+// est hydratée, sinon dans la liste HTML (une ligne / carte par concours). Les critères vérifiables
+// sur le résumé (dates, département, discipline, organisateur) sont ré-appliqués localement.
+//
+// Fiche d'un concours (`/concours/{id}`): lieu, dates, organisateur, tarifs, ouverture / clôture
+// des engagements et contact, lus dans l'état JSON puis complétés par les paires "Clé : valeur"
+// du texte de la page.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+use std::time::Duration;
+use regex::Regex;
+use serde_json::{Map, Value};
+use crate::{
+  embedded::{self, get_str},
+  epreuves::{self, Discipline},
+  http,
+  models::{ContestContact, ContestDetail, ContestFee, ContestSearch, ContestSearchResult, ContestSummary},
+  text,
+};
+
+const SEARCH_URL: &str = "https://ffecompet.ffe.com/concours";
+/// Durée de validité d'une fiche enregistrée (tarifs / dates de clôture peuvent être corrigés)
+pub const DETAIL_TTL_SEC: i64 = 6 * 3600;
+const DEFAULT_PAGES: u32 = 3;
+const MAX_PAGES: u32 = 10;
+
+fn clean(v: &Option<String>) -> Option<String> {
+  v.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+}
+
+/// Vérifie et normalise les critères (dates ISO, département en majuscules, code discipline).
+pub fn validate(s: &ContestSearch) -> Result<ContestSearch, String> {
+  let date = |v: &Option<String>, what: &str| -> Result<Option<String>, String> {
+    match clean(v) {
+      None => Ok(None),
+      // Reformatée: les dates sont ensuite comparées comme chaînes ("2026-4-1" -> "2026-04-01")
+      Some(d) => chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d")
+        .map(|d| Some(d.format("%Y-%m-%d").to_string()))
+        .map_err(|_| format!("Date {what} invalide (AAAA-MM-JJ).")),
+    }
+  };
+  let date_from = date(&s.date_from, "de début")?;
+  let date_to = date(&s.date_to, "de fin")?;
+  if let (Some(from), Some(to)) = (&date_from, &date_to) {
+    if from > to {
+      return Err("La date de début doit précéder la date de fin.".into());
+    }
+  }
+
+  let departement = clean(&s.departement).map(|d| d.to_uppercase());
+  if let Some(d) = &departement {
+    let ok = (d.len() == 2 && d.chars().all(|c| c.is_ascii_digit())) || d == "2A" || d == "2B"
+      || (d.len() == 3 && d.starts_with("97") && d.chars().all(|c| c.is_ascii_digit()));
+    if !ok {
+      return Err("Département invalide (ex: 41, 2A, 974).".into());
+    }
+  }
+
+  let discipline = match clean(&s.discipline) {
+    None => None,
+    Some(d) => Some(Discipline::parse(&d).ok_or_else(|| format!("Discipline inconnue: {d}"))?.code().to_string()),
+  };
+
+  Ok(ContestSearch {
+    date_from,
+    date_to,
+    departement,
+    region: clean(&s.region),
+    discipline,
+    level: clean(&s.level),
+    organizer: clean(&s.organizer),
+    max_pages: Some(s.max_pages.unwrap_or(DEFAULT_PAGES).clamp(1, MAX_PAGES)),
+  })
+}
+
+/// URL de la page de résultats (paramètres du formulaire de recherche ffecompet).
+pub fn search_url(s: &ContestSearch, page: u32) -> String {
+  let mut params: Vec<(&str, String)> = Vec::new();
+  let fields = [
+    ("dateDebut", &s.date_from),
+    ("dateFin", &s.date_to),
+    ("departement", &s.departement),
+    ("region", &s.region),
+    ("discipline", &s.discipline),
+    ("niveau", &s.level),
+    ("organisateur", &s.organizer),
+  ];
+  for (key, value) in fields {
+    if let Some(v) = value {
+      params.push((key, v.clone()));
+    }
+  }
+  if page > 1 {
+    params.push(("page", page.to_string()));
+  }
+  reqwest::Url::parse_with_params(SEARCH_URL, &params)
+    .map(|u| u.to_string())
+    .unwrap_or_else(|_| SEARCH_URL.to_string())
+}
+
+fn date_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"\b(\d{1,2})/(\d{1,2})/(\d{4})\b|\b(\d{4})-(\d{2})-(\d{2})").unwrap())
+}
+
+/// Dates "JJ/MM/AAAA" ou ISO trouvées dans un texte, au format "AAAA-MM-JJ".
+pub fn dates_in(s: &str) -> Vec<String> {
+  date_re()
+    .captures_iter(s)
+    .filter_map(|c| {
+      let (y, m, d) = match c.get(3) {
+        Some(y) => (y.as_str(), &c[2], &c[1]),
+        None => (&c[4], &c[5], &c[6]),
+      };
+      let date = chrono::NaiveDate::from_ymd_opt(y.parse().ok()?, m.parse().ok()?, d.parse().ok()?)?;
+      Some(date.format("%Y-%m-%d").to_string())
+    })
+    .collect()
+}
+
+fn dep_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  // "Lamotte-Beuvron (41)" ou "41600 Lamotte-Beuvron"
+  RE.get_or_init(|| Regex::new(r"(?i)([^\n(]*?)\s*\((\d{2,3}|2[ab])\)|\b(\d{5})\s+([^\n,;]+)").unwrap())
+}
+
+/// (ville, département) lus dans un texte de résultat.
+fn place_in(s: &str) -> Option<(Option<String>, String)> {
+  let c = dep_re().captures(s)?;
+  let (city, dep) = match c.get(2) {
+    Some(dep) => (c.get(1), dep.as_str().to_uppercase()),
+    None => {
+      let cp = &c[3];
+      (c.get(4), if cp.starts_with("97") { cp[..3].to_string() } else { cp[..2].to_string() })
+    }
+  };
+  let city = city
+    .map(|m| {
+      // "Lieu : Lamotte-Beuvron", "Centre équestre, Route de ..., Paris"
+      let t = m.as_str().trim();
+      let t = t.split_once(':').map_or(t, |(_, v)| v);
+      t.rsplit(',').next().unwrap_or(t).trim().to_string()
+    })
+    .filter(|c| !c.is_empty());
+  Some((city, dep))
+}
+
+fn disciplines_in(s: &str) -> Vec<String> {
+  let mut out: Vec<String> = Vec::new();
+  for d in Discipline::ALL {
+    let named = s.lines().any(|l| Discipline::parse(l) == Some(d));
+    if named || s.split(|c: char| !c.is_alphanumeric()).any(|t| t == d.code()) {
+      out.push(d.code().to_string());
+    }
+  }
+  out
+}
+
+fn organizer_in(s: &str) -> Option<String> {
+  s.lines().find_map(|l| {
+    let (key, value) = l.split_once(':')?;
+    let key = text::normalize(key);
+    let is_org = key.ends_with("organisateur") || key.starts_with("organise par") || key == "club";
+    let value = value.trim();
+    (is_org && !value.is_empty()).then(|| value.to_string())
+  })
+}
+
+/// Complète `a` avec les champs connus de `b` (même concours vu deux fois).
+fn merge(a: &mut ContestSummary, b: ContestSummary) {
+  if a.name.starts_with("Concours ") && !b.name.starts_with("Concours ") {
+    a.name = b.name;
+  }
+  a.start_date = a.start_date.take().or(b.start_date);
+  a.end_date = a.end_date.take().or(b.end_date);
+  a.city = a.city.take().or(b.city);
+  a.departement = a.departement.take().or(b.departement);
+  a.organizer = a.organizer.take().or(b.organizer);
+  for d in b.disciplines {
+    if !a.disciplines.contains(&d) {
+      a.disciplines.push(d);
+    }
+  }
+}
+
+fn push_unique(out: &mut Vec<ContestSummary>, c: ContestSummary) {
+  match out.iter_mut().find(|x| x.id == c.id) {
+    Some(existing) => merge(existing, c),
+    None => out.push(c),
+  }
+}
+
+// ---------- État JSON embarqué ----------
+
+fn contest_id(obj: &Map<String, Value>) -> Option<String> {
+  ["numConcours", "numeroConcours", "concoursId", "numero", "id"].iter().find_map(|k| {
+    let v = match obj.get(*k)? {
+      Value::Number(n) => n.to_string(),
+      Value::String(s) => s.trim().to_string(),
+      _ => return None,
+    };
+    (v.len() == 9 && v.chars().all(|c| c.is_ascii_digit())).then_some(v)
+  })
+}
+
+const ORGANIZER_KEYS: &[&str] = &["organisateur", "club", "structure"];
+
+/// Champ texte direct, ou champ `inner` d'un sous-objet (`"club": {"nom": ...}`).
+fn nested_str(obj: &Map<String, Value>, keys: &[&str], inner: &[&str]) -> Option<String> {
+  get_str(obj, keys).or_else(|| keys.iter().find_map(|k| obj.get(*k)?.as_object().and_then(|o| get_str(o, inner))))
+}
+
+fn walk_json(v: &Value, out: &mut Vec<ContestSummary>) {
+  match v {
+    Value::Object(obj) => {
+      if let (Some(id), Some(name)) = (contest_id(obj), get_str(obj, &["nom", "libelle", "intitule", "name"])) {
+        let date = |keys: &[&str]| get_str(obj, keys).and_then(|d| dates_in(&d).into_iter().next());
+        let disciplines = match obj.get("disciplines") {
+          Some(Value::Array(arr)) => arr.iter().filter_map(Value::as_str).filter_map(Discipline::parse).map(|d| d.code().to_string()).collect(),
+          _ => get_str(obj, &["discipline", "codeDiscipline"]).and_then(|d| Discipline::parse(&d)).map(|d| vec![d.code().to_string()]).unwrap_or_default(),
+        };
+        let organizer = nested_str(obj, ORGANIZER_KEYS, &["nom", "libelle", "name"]);
+        push_unique(out, ContestSummary {
+          url: epreuves::contest_url(&id),
+          id,
+          name,
+          start_date: date(&["dateDebut", "debut", "startDate", "date_debut", "date"]),
+          end_date: date(&["dateFin", "fin", "endDate", "date_fin"]),
+          city: get_str(obj, &["lieu", "ville", "commune", "city"]),
+          departement: get_str(obj, &["departement", "codeDepartement", "dept"]).map(|d| d.to_uppercase()),
+          disciplines,
+          organizer,
+        });
+      }
+      for vv in obj.values() {
+        walk_json(vv, out);
+      }
+    }
+    Value::Array(arr) => {
+      for it in arr {
+        walk_json(it, out);
+      }
+    }
+    _ => {}
+  }
+}
+
+// ---------- Liste HTML ----------
+
+fn link_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r#"(?is)<a\b[^>]*href\s*=\s*["'][^"']*/concours/(\d{9})\b[^"']*["'][^>]*>(.*?)</a>"#).unwrap()
+  })
+}
+
+fn row_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?i)<(?:tr|li|article)\b").unwrap())
+}
+
+/// Textes de lien génériques (bouton "Voir", "Engager", ...) qui ne sont pas un nom de concours.
+fn is_generic_link(s: &str) -> bool {
+  let n = text::normalize(s);
+  n.is_empty() || ["voir", "detail", "details", "engager", "consulter", "+", "plus"].contains(&n.as_str())
+}
+
+fn summary_from_block(id: &str, anchor: &str, block: &str) -> ContestSummary {
+  let text = epreuves::html_to_text(block);
+  let anchor = epreuves::html_to_text(anchor).split_whitespace().collect::<Vec<_>>().join(" ");
+  let name = if is_generic_link(&anchor) {
+    format!("Concours {id}")
+  } else {
+    anchor
+  };
+  let dates = dates_in(&text);
+  let place = place_in(&text);
+  ContestSummary {
+    id: id.to_string(),
+    name,
+    start_date: dates.first().cloned(),
+    end_date: dates.get(1).or(dates.first()).cloned(),
+    city: place.as_ref().and_then(|p| p.0.clone()),
+    departement: place.map(|p| p.1),
+    disciplines: disciplines_in(&text),
+    organizer: organizer_in(&text),
+    url: epreuves::contest_url(id),
+  }
+}
+
+/// Résultats d'une page de recherche + présence d'une page suivante.
+pub fn parse_search_page(html: &str, page: u32) -> (Vec<ContestSummary>, bool) {
+  let has_next = html.contains("rel=\"next\"")
+    || html.contains("rel='next'")
+    || html.contains(&format!("page={}", page + 1));
+
+  let mut out = Vec::new();
+  for v in embedded::extract_json_states(html) {
+    walk_json(&v, &mut out);
+  }
+  if !out.is_empty() {
+    return (out, has_next);
+  }
+
+  // Une ligne de tableau / un élément de liste par concours; à défaut, découpe lien par lien
+  let mut starts: Vec<usize> = row_re().find_iter(html).map(|m| m.start()).collect();
+  starts.insert(0, 0);
+  starts.push(html.len());
+  for w in starts.windows(2) {
+    let segment = &html[w[0]..w[1]];
+    let links: Vec<_> = link_re().captures_iter(segment).collect();
+    let distinct: HashSet<&str> = links.iter().map(|c| c.get(1).unwrap().as_str()).collect();
+    if distinct.len() <= 1 {
+      if let Some(c) = links.first() {
+        // Nom = premier lien non générique de la ligne
+        let anchor = links.iter().map(|c| c.get(2).unwrap().as_str()).find(|a| !is_generic_link(&epreuves::html_to_text(a))).unwrap_or("");
+        push_unique(&mut out, summary_from_block(&c[1], anchor, segment));
+      }
+      continue;
+    }
+    for (i, c) in links.iter().enumerate() {
+      let start = c.get(0).unwrap().start();
+      let end = links.get(i + 1).map(|n| n.get(0).unwrap().start()).unwrap_or(segment.len());
+      push_unique(&mut out, summary_from_block(&c[1], &c[2], &segment[start..end]));
+    }
+  }
+  (out, has_next)
+}
+
+/// Critères vérifiables sur le résumé; un champ inconnu ne fait pas rejeter le concours.
+pub fn matches(s: &ContestSearch, c: &ContestSummary) -> bool {
+  let start = c.start_date.as_deref();
+  let end = c.end_date.as_deref().or(start);
+  let after_from = s.date_from.as_deref().is_none_or(|from| end.is_none_or(|e| e >= from));
+  let before_to = s.date_to.as_deref().is_none_or(|to| start.is_none_or(|st| st <= to));
+  let dep_ok = s.departement.as_deref().is_none_or(|d| c.departement.as_deref().is_none_or(|cd| cd == d));
+  let disc_ok = s.discipline.as_deref().is_none_or(|d| c.disciplines.is_empty() || c.disciplines.iter().any(|cd| cd == d));
+  let org_ok = s.organizer.as_deref().is_none_or(|o| {
+    c.organizer.as_deref().is_none_or(|co| text::normalize(co).contains(&text::normalize(o)))
+  });
+  after_from && before_to && dep_ok && disc_ok && org_ok
+}
+
+/// Parcourt les pages de résultats (jusqu'à `max_pages`).
+pub async fn search(client: &reqwest::Client, search: &ContestSearch) -> Result<ContestSearchResult, String> {
+  let s = validate(search)?;
+  let max_pages = s.max_pages.unwrap_or(DEFAULT_PAGES);
+
+  let mut contests: Vec<ContestSummary> = Vec::new();
+  let mut seen: HashSet<String> = HashSet::new();
+  let mut pages = 0;
+  let mut has_more = false;
+
+  for page in 1..=max_pages {
+    if page > 1 {
+      // Pas de rafale sur ffecompet
+      tokio::time::sleep(Duration::from_millis(400)).await;
+    }
+    let url = search_url(&s, page);
+    let resp = client.get(&url).send().await.map_err(|e| format!("HTTP error: {e}"))?;
+    let status = resp.status();
+    let body = http::read_text(resp).await.map_err(|e| format!("HTTP read error: {e:#}"))?;
+    if !status.is_success() {
+      if page == 1 {
+        return Err(format!("HTTP {status} sur {url}"));
+      }
+      break;
+    }
+    pages = page;
+
+    let (found, has_next) = parse_search_page(&body, page);
+    let before = seen.len();
+    for c in found {
+      if seen.insert(c.id.clone()) && matches(&s, &c) {
+        contests.push(c);
+      }
+    }
+    // Page vide ou déjà vue (paramètre de pagination ignoré): fin
+    has_more = has_next && seen.len() > before;
+    if !has_more {
+      break;
+    }
+  }
+
+  // Par date de début (dates inconnues en dernier)
+  contests.sort_by(|a, b| {
+    (a.start_date.is_none(), a.start_date.as_deref(), &a.id).cmp(&(b.start_date.is_none(), b.start_date.as_deref(), &b.id))
+  });
+  Ok(ContestSearchResult { contests, pages, has_more })
+}
+
+// ---------- Fiche concours ----------
+
+fn time_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  // "08h00", "8h", "08:00", "T08:00:00"
+  RE.get_or_init(|| Regex::new(r"(?i)(?:\b|T)([01]?\d|2[0-3])\s*[h:]\s*([0-5]\d)?(?:\b|$)").unwrap())
+}
+
+/// Date (et heure si présente) d'un texte: "YYYY-MM-DD" ou "YYYY-MM-DDTHH:MM".
+pub fn datetime_in(s: &str) -> Option<String> {
+  let date = dates_in(s).into_iter().next()?;
+  // Heure cherchée après la date ("01/03/2026 à 8h00", "2026-03-01T08:00:00")
+  let m = date_re().find(s)?;
+  match time_re().captures(&s[m.end()..]) {
+    Some(c) => {
+      let h: u32 = c[1].parse().ok()?;
+      let min: u32 = c.get(2).map_or(Some(0), |m| m.as_str().parse().ok())?;
+      Some(format!("{date}T{h:02}:{min:02}"))
+    }
+    None => Some(date),
+  }
+}
+
+fn amount_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?i)(\d{1,4}(?:[.,]\d{1,2})?)\s*(?:€|eur\b|euros?\b)").unwrap())
+}
+
+fn email_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").unwrap())
+}
+
+fn phone_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?:\+33\s?|\b0)[1-9](?:[\s.-]?\d{2}){4}\b").unwrap())
+}
+
+/// Mots-clés d'une ligne de tarif (les dotations ne sont pas des frais).
+const FEE_WORDS: &[&str] = &["engagement", "tarif", "frais", "box", "droit", "inscription", "supplement", "paddock"];
+
+fn h1_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?is)<h1\b[^>]*>(.*?)</h1>").unwrap())
+}
+
+fn title_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?is)<title\b[^>]*>(.*?)</title>").unwrap())
+}
+
+/// Paires (clé normalisée, valeur) du texte: "Lieu : X" ou "Lieu" / "X" sur deux cellules.
+fn fields(text: &str) -> Vec<(String, String)> {
+  let lines: Vec<&str> = text.lines().map(str::trim).filter(|l| !l.is_empty()).collect();
+  let mut out = Vec::new();
+  for (i, line) in lines.iter().enumerate() {
+    let next = lines.get(i + 1).copied().unwrap_or("");
+    match line.split_once(':') {
+      Some((k, v)) if !k.trim().is_empty() && k.chars().count() <= 60 => {
+        let v = v.trim();
+        out.push((text::normalize(k), if v.is_empty() { next } else { v }.to_string()));
+      }
+      _ if !next.is_empty() && line.chars().count() <= 40 => {
+        let k = text::normalize(line);
+        if KEYS.iter().any(|keys| keys.iter().any(|p| key_is(&k, p))) {
+          out.push((k, next.to_string()));
+        }
+      }
+      _ => {}
+    }
+  }
+  out
+}
+
+const VENUE_KEYS: &[&str] = &["lieu", "adresse", "lieu du concours", "adresse du concours"];
+const DATE_KEYS: &[&str] = &["date", "dates", "date du concours", "dates du concours", "periode"];
+const ORGANIZER_FIELD_KEYS: &[&str] = &["organisateur", "club organisateur", "structure organisatrice", "organise par", "club"];
+const OPENING_KEYS: &[&str] = &["ouverture des engagements", "date d'ouverture", "ouverture", "debut des engagements"];
+const CLOSING_KEYS: &[&str] = &[
+  "cloture des engagements",
+  "date de cloture",
+  "cloture",
+  "fermeture des engagements",
+  "date limite d'engagement",
+  "date limite",
+];
+const CONTACT_KEYS: &[&str] = &["contact", "responsable", "contact organisateur"];
+const KEYS: &[&[&str]] = &[VENUE_KEYS, DATE_KEYS, ORGANIZER_FIELD_KEYS, OPENING_KEYS, CLOSING_KEYS, CONTACT_KEYS];
+
+/// Clé exacte, éventuellement suivie d'une précision ("Clôture des engagements (SO)").
+fn key_is(key: &str, wanted: &str) -> bool {
+  key == wanted || key.strip_prefix(wanted).is_some_and(|rest| rest.starts_with(" ("))
+}
+
+fn field<'a>(fields: &'a [(String, String)], keys: &[&str]) -> Option<&'a str> {
+  fields.iter().find(|(k, _)| keys.iter().any(|w| key_is(k, w))).map(|(_, v)| v.as_str())
+}
+
+fn parse_amount(s: &str) -> Option<f64> {
+  s.replace(',', ".").parse().ok()
+}
+
+fn push_fee(fees: &mut Vec<ContestFee>, label: String, amount_eur: f64) {
+  if !fees.iter().any(|f| f.label == label && f.amount_eur == amount_eur) {
+    fees.push(ContestFee { label, amount_eur });
+  }
+}
+
+fn fees_in(text: &str) -> Vec<ContestFee> {
+  let is_fee = |n: &str| !n.contains("dotation") && FEE_WORDS.iter().any(|w| n.contains(w));
+  let mut fees = Vec::new();
+  let mut prev = "";
+  for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+    let key = std::mem::replace(&mut prev, line);
+    // Montant seul dans sa cellule: le libellé est la cellule précédente ("Engagement" / "25 €")
+    let own = amount_re().replace_all(line, "").trim_matches(|c: char| c.is_whitespace() || "-–—:".contains(c)).to_string();
+    let label = match line.split_once(':') {
+      Some((k, _)) => k.trim().to_string(),
+      None if own.is_empty() && !key.contains(':') && !amount_re().is_match(key) => key.to_string(),
+      None => own,
+    };
+    if !is_fee(&text::normalize(line)) && !is_fee(&text::normalize(&label)) {
+      continue;
+    }
+    let label = if label.is_empty() { "Engagement".to_string() } else { label };
+    for c in amount_re().captures_iter(line) {
+      if let Some(amount) = parse_amount(&c[1]) {
+        push_fee(&mut fees, label.clone(), amount);
+      }
+    }
+  }
+  fees
+}
+
+fn contact_in(fields: &[(String, String)], text: &str) -> ContestContact {
+  let value = field(fields, CONTACT_KEYS);
+  // Coordonnées de la rubrique contact en priorité, sinon première trouvée hors FFE
+  let email = value
+    .and_then(|v| email_re().find(v))
+    .or_else(|| email_re().find_iter(text).find(|m| !m.as_str().to_lowercase().ends_with("ffe.com")))
+    .map(|m| m.as_str().to_string());
+  let phone = value
+    .and_then(|v| phone_re().find(v))
+    .or_else(|| {
+      fields
+        .iter()
+        .filter(|(k, _)| k.starts_with("tel") || k.starts_with("portable"))
+        .find_map(|(_, v)| phone_re().find(v))
+    })
+    .map(|m| m.as_str().to_string());
+  let name = value
+    .map(|v| phone_re().replace_all(&email_re().replace_all(v, ""), "").to_string())
+    .map(|v| v.trim_matches(|c: char| c.is_whitespace() || "-–—,;/".contains(c)).to_string())
+    .filter(|v| !v.is_empty());
+  ContestContact { name, email, phone }
+}
+
+fn name_in(html: &str) -> Option<String> {
+  let clean = |s: &str| epreuves::html_to_text(s).split_whitespace().collect::<Vec<_>>().join(" ");
+  h1_re()
+    .captures(html)
+    .map(|c| clean(&c[1]))
+    .filter(|s| !s.is_empty())
+    .or_else(|| {
+      let title = clean(&title_re().captures(html)?[1]);
+      // "Nom du concours - FFE Compet"
+      let name = title.rsplit_once(" - ").map_or(title.as_str(), |(n, _)| n).trim().to_string();
+      (!name.is_empty()).then_some(name)
+    })
+}
+
+fn detail_from_text(id: &str, html: &str) -> ContestDetail {
+  let text = epreuves::html_to_text(html);
+  let fields = fields(&text);
+
+  let venue = field(&fields, VENUE_KEYS).map(str::to_string);
+  let place = venue.as_deref().and_then(place_in);
+  let dates = field(&fields, DATE_KEYS)
+    .map(dates_in)
+    .filter(|d| !d.is_empty())
+    .or_else(|| {
+      // "Du 12/03/2026 au 15/03/2026"
+      text.lines().map(text::normalize).filter(|l| l.starts_with("du ")).map(|l| dates_in(&l)).find(|d| !d.is_empty())
+    })
+    .unwrap_or_default();
+
+  ContestDetail {
+    id: id.to_string(),
+    name: name_in(html).unwrap_or_else(|| format!("Concours {id}")),
+    city: place.as_ref().and_then(|p| p.0.clone()),
+    departement: place.map(|p| p.1),
+    venue,
+    start_date: dates.first().cloned(),
+    end_date: dates.last().cloned(),
+    organizer: field(&fields, ORGANIZER_FIELD_KEYS).map(str::to_string),
+    fees: fees_in(&text),
+    opening_date: field(&fields, OPENING_KEYS).and_then(datetime_in),
+    closing_date: field(&fields, CLOSING_KEYS).and_then(datetime_in),
+    contact: contact_in(&fields, &text),
+    url: epreuves::contest_url(id),
+    fetched_at: 0,
+  }
+}
+
+fn find_contest<'a>(v: &'a Value, id: &str) -> Option<&'a Map<String, Value>> {
+  match v {
+    Value::Object(obj) => {
+      if contest_id(obj).as_deref() == Some(id) {
+        return Some(obj);
+      }
+      obj.values().find_map(|vv| find_contest(vv, id))
+    }
+    Value::Array(arr) => arr.iter().find_map(|it| find_contest(it, id)),
+    _ => None,
+  }
+}
+
+fn fees_from_json(obj: &Map<String, Value>) -> Vec<ContestFee> {
+  let amount = |o: &Map<String, Value>, keys: &[&str]| {
+    keys.iter().find_map(|k| match o.get(*k)? {
+      Value::Number(n) => n.as_f64(),
+      Value::String(s) => amount_re().captures(s).and_then(|c| parse_amount(&c[1])).or_else(|| parse_amount(s.trim())),
+      _ => None,
+    })
+  };
+  let mut fees = Vec::new();
+  for k in ["tarifs", "frais", "fees"] {
+    if let Some(Value::Array(arr)) = obj.get(k) {
+      for o in arr.iter().filter_map(Value::as_object) {
+        if let Some(a) = amount(o, &["montant", "prix", "tarif", "amount"]) {
+          push_fee(&mut fees, get_str(o, &["libelle", "nom", "label"]).unwrap_or_else(|| "Engagement".into()), a);
+        }
+      }
+    }
+  }
+  if let Some(a) = amount(obj, &["montantEngagement", "prixEngagement", "tarifEngagement", "fraisEngagement"]) {
+    push_fee(&mut fees, "Engagement".into(), a);
+  }
+  fees
+}
+
+fn detail_from_json(id: &str, obj: &Map<String, Value>) -> ContestDetail {
+  let date = |keys: &[&str]| get_str(obj, keys).and_then(|d| dates_in(&d).into_iter().next());
+  let datetime = |keys: &[&str]| get_str(obj, keys).and_then(|d| datetime_in(&d));
+  let venue = nested_str(obj, &["lieu", "adresse", "site"], &["nom", "adresse", "libelle"]);
+  let city = get_str(obj, &["ville", "commune", "city"])
+    .or_else(|| ["lieu", "adresse", "site"].iter().find_map(|k| obj.get(*k)?.as_object().and_then(|o| get_str(o, &["ville", "commune"]))));
+  let place = venue.as_deref().and_then(place_in);
+  // Rubrique "contact" dédiée, sinon coordonnées à plat sur le concours
+  let contact = obj.get("contact").and_then(Value::as_object);
+  let contact_obj = contact.unwrap_or(obj);
+  ContestDetail {
+    id: id.to_string(),
+    name: get_str(obj, &["nom", "libelle", "intitule", "name"]).unwrap_or_else(|| format!("Concours {id}")),
+    city: city.or_else(|| place.as_ref().and_then(|p| p.0.clone())),
+    departement: get_str(obj, &["departement", "codeDepartement", "dept"])
+      .map(|d| d.to_uppercase())
+      .or_else(|| place.map(|p| p.1)),
+    venue,
+    start_date: date(&["dateDebut", "debut", "startDate", "date_debut"]),
+    end_date: date(&["dateFin", "fin", "endDate", "date_fin"]),
+    organizer: nested_str(obj, ORGANIZER_KEYS, &["nom", "libelle", "name"]),
+    fees: fees_from_json(obj),
+    opening_date: datetime(&["dateOuvertureEngagements", "ouvertureEngagements", "dateOuverture", "debutEngagements"]),
+    closing_date: datetime(&["dateClotureEngagements", "clotureEngagements", "dateCloture", "finEngagements", "dateLimiteEngagement"]),
+    contact: ContestContact {
+      name: contact.and_then(|c| get_str(c, &["nom", "responsable", "name"])).or_else(|| get_str(obj, &["responsable"])),
+      email: get_str(contact_obj, &["email", "mail", "courriel"]),
+      phone: get_str(contact_obj, &["telephone", "tel", "portable", "phone"]),
+    },
+    url: epreuves::contest_url(id),
+    fetched_at: 0,
+  }
+}
+
+/// Fiche du concours: état JSON embarqué, complété par le texte de la page.
+pub fn parse_detail(id: &str, html: &str) -> ContestDetail {
+  let from_text = detail_from_text(id, html);
+  let states = embedded::extract_json_states(html);
+  let Some(mut d) = states.iter().find_map(|v| find_contest(v, id)).map(|obj| detail_from_json(id, obj)) else {
+    return from_text;
+  };
+  if d.name.starts_with("Concours ") {
+    d.name = from_text.name;
+  }
+  d.venue = d.venue.or(from_text.venue);
+  d.city = d.city.or(from_text.city);
+  d.departement = d.departement.or(from_text.departement);
+  d.start_date = d.start_date.or(from_text.start_date);
+  d.end_date = d.end_date.or(from_text.end_date);
+  d.organizer = d.organizer.or(from_text.organizer);
+  if d.fees.is_empty() {
+    d.fees = from_text.fees;
+  }
+  d.opening_date = d.opening_date.or(from_text.opening_date);
+  d.closing_date = d.closing_date.or(from_text.closing_date);
+  d.contact.name = d.contact.name.or(from_text.contact.name);
+  d.contact.email = d.contact.email.or(from_text.contact.email);
+  d.contact.phone = d.contact.phone.or(from_text.contact.phone);
+  d
+}
+
+/// Télécharge et lit la fiche du concours.
+pub async fn fetch_detail(client: &reqwest::Client, contest_id: &str) -> Result<ContestDetail, String> {
+  let id = contest_id.trim();
+  if id.len() != 9 || !id.chars().all(|c| c.is_ascii_digit()) {
+    return Err("N° concours invalide (9 chiffres).".into());
+  }
+  let url = epreuves::contest_url(id);
+  let resp = client.get(&url).send().await.map_err(|e| format!("HTTP error: {e}"))?;
+  let status = resp.status();
+  let body = http::read_text(resp).await.map_err(|e| format!("HTTP read error: {e:#}"))?;
+  if !status.is_success() {
+    return Err(format!("HTTP {status} sur {url}"));
+  }
+  let mut detail = parse_detail(id, &body);
+  detail.fetched_at = chrono::Utc::now().timestamp();
+  Ok(detail)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TABLE_HTML: &str = include_str!("../tests/fixtures/recherche_table.html");
+  const CARDS_HTML: &str = include_str!("../tests/fixtures/recherche_cartes.html");
+  const NEXT_DATA_HTML: &str = include_str!("../tests/fixtures/recherche_next_data.html");
+  const DETAIL_TABLE_HTML: &str = include_str!("../tests/fixtures/fiche_concours_table.html");
+  const DETAIL_LINES_HTML: &str = include_str!("../tests/fixtures/fiche_concours_lignes.html");
+  const DETAIL_NEXT_DATA_HTML: &str = include_str!("../tests/fixtures/fiche_concours_next_data.html");
+
+  fn summary(id: &str) -> ContestSummary {
+    ContestSummary {
+      id: id.into(),
+      name: format!("Concours {id}"),
+      start_date: None,
+      end_date: None,
+      city: None,
+      departement: None,
+      disciplines: Vec::new(),
+      organizer: None,
+      url: epreuves::contest_url(id),
+    }
+  }
+
+  fn opt(s: &str) -> Option<String> {
+    Some(s.to_string())
+  }
+
+  #[test]
+  fn search_table_rows() {
+    let (found, has_next) = parse_search_page(TABLE_HTML, 1);
+    assert!(has_next);
+    assert_eq!(found.len(), 3);
+
+    let c = &found[0];
+    assert_eq!(c.id, "202641001");
+    assert_eq!(c.name, "CSO Amateur & Pro de Printemps");
+    assert_eq!(c.start_date.as_deref(), Some("2026-04-12"));
+    assert_eq!(c.end_date.as_deref(), Some("2026-04-14"));
+    assert_eq!(c.city.as_deref(), Some("Lamotte-Beuvron"));
+    assert_eq!(c.departement.as_deref(), Some("41"));
+    assert_eq!(c.disciplines, vec!["SO"]);
+    assert_eq!(c.organizer.as_deref(), Some("Parc Equestre Fédéral"));
+    assert_eq!(c.url, "https://ffecompet.ffe.com/concours/202641001");
+
+    let c = &found[1];
+    assert_eq!((c.id.as_str(), c.name.as_str()), ("202675002", "Dressage Club"));
+    assert_eq!((c.start_date.as_deref(), c.end_date.as_deref()), (Some("2026-04-20"), Some("2026-04-20")));
+    assert_eq!((c.city.as_deref(), c.departement.as_deref()), (Some("Paris"), Some("75")));
+    assert_eq!(c.disciplines, vec!["DR"]);
+    assert_eq!(c.organizer, None);
+
+    let c = &found[2];
+    assert_eq!((c.city.as_deref(), c.departement.as_deref()), (Some("Ajaccio"), Some("2A")));
+    assert_eq!(c.disciplines, vec!["CC"]);
+    assert_eq!(c.organizer.as_deref(), Some("Poney Club d'Ajaccio"));
+  }
+
+  #[test]
+  fn search_cards_and_voir_links() {
+    let (found, has_next) = parse_search_page(CARDS_HTML, 2);
+    assert!(has_next);
+    assert_eq!(found.len(), 2);
+
+    let c = &found[0];
+    assert_eq!((c.id.as_str(), c.name.as_str()), ("202697404", "Hunter de la Réunion"));
+    assert_eq!((c.start_date.as_deref(), c.end_date.as_deref()), (Some("2026-06-06"), Some("2026-06-07")));
+    assert_eq!((c.city.as_deref(), c.departement.as_deref()), (Some("Saint-Denis"), Some("974")));
+    assert_eq!(c.disciplines, vec!["HU"]);
+    assert_eq!(c.organizer.as_deref(), Some("Centre Equestre du Colorado"));
+
+    // Seul un lien "Voir": nom par défaut
+    let c = &found[1];
+    assert_eq!((c.id.as_str(), c.name.as_str()), ("202649005", "Concours 202649005"));
+    assert_eq!((c.city.as_deref(), c.departement.as_deref()), (Some("Saumur"), Some("49")));
+    assert_eq!(c.disciplines, vec!["SO", "CC"]);
+  }
+
+  #[test]
+  fn search_json_state() {
+    let (found, has_next) = parse_search_page(NEXT_DATA_HTML, 3);
+    assert!(!has_next);
+    assert_eq!(found.len(), 2);
+
+    let c = &found[0];
+    assert_eq!((c.id.as_str(), c.name.as_str()), ("202633006", "CSO Pro Elite"));
+    assert_eq!((c.start_date.as_deref(), c.end_date.as_deref()), (Some("2026-07-10"), Some("2026-07-12")));
+    assert_eq!((c.city.as_deref(), c.departement.as_deref()), (Some("Bordeaux"), Some("33")));
+    assert_eq!(c.disciplines, vec!["SO"]);
+    assert_eq!(c.organizer.as_deref(), Some("Jumping de Bordeaux"));
+
+    let c = &found[1];
+    assert_eq!((c.id.as_str(), c.name.as_str()), ("202613007", "Pony Games Club"));
+    assert_eq!((c.start_date.as_deref(), c.end_date.as_deref()), (Some("2026-07-19"), None));
+    assert_eq!((c.city.as_deref(), c.departement.as_deref()), (Some("Aix-en-Provence"), Some("13")));
+    assert_eq!(c.disciplines, vec!["PG"]);
+    assert_eq!(c.organizer.as_deref(), Some("Ecurie du Soleil"));
+  }
+
+  #[test]
+  fn search_pagination() {
+    assert!(!parse_search_page("<p>Aucun résultat</p>", 1).1);
+    assert!(parse_search_page(r#"<a href="?page=2">2</a>"#, 1).1);
+    assert!(!parse_search_page(r#"<a href="?page=2">2</a>"#, 2).1);
+    assert!(parse_search_page(r#"<link rel='next' href="/concours?p=x">"#, 5).1);
+    assert!(parse_search_page("", 1).0.is_empty());
+  }
+
+  #[test]
+  fn validate_table() {
+    let ok = |s: ContestSearch| validate(&s).unwrap();
+    let err = |s: ContestSearch| validate(&s).unwrap_err();
+
+    let v = ok(ContestSearch { departement: opt(" 2a "), discipline: opt("cso"), ..Default::default() });
+    assert_eq!((v.departement.as_deref(), v.discipline.as_deref(), v.max_pages), (Some("2A"), Some("SO"), Some(3)));
+    assert_eq!(ok(ContestSearch { departement: opt("2B"), ..Default::default() }).departement.as_deref(), Some("2B"));
+    assert_eq!(ok(ContestSearch { departement: opt("974"), ..Default::default() }).departement.as_deref(), Some("974"));
+    assert_eq!(ok(ContestSearch { departement: opt("41"), ..Default::default() }).departement.as_deref(), Some("41"));
+    assert_eq!(ok(ContestSearch { departement: opt("  "), organizer: opt(""), ..Default::default() }).departement, None);
+    assert_eq!(ok(ContestSearch { max_pages: Some(50), ..Default::default() }).max_pages, Some(10));
+    assert_eq!(ok(ContestSearch { max_pages: Some(0), ..Default::default() }).max_pages, Some(1));
+    assert_eq!(ok(ContestSearch { date_from: opt("2026-04-12"), date_to: opt("2026-04-12"), ..Default::default() }).date_to.as_deref(), Some("2026-04-12"));
+
+    for dep in ["4", "410", "2C", "975A", "AB"] {
+      assert!(err(ContestSearch { departement: opt(dep), ..Default::default() }).starts_with("Département invalide"), "{dep}");
+    }
+    assert_eq!(ok(ContestSearch { date_from: opt("2026-4-1"), ..Default::default() }).date_from.as_deref(), Some("2026-04-01"));
+    for date in ["12/04/2026", "2026-02-30", "demain"] {
+      assert!(err(ContestSearch { date_from: opt(date), ..Default::default() }).starts_with("Date de début invalide"), "{date}");
+    }
+    assert!(err(ContestSearch { date_to: opt("2026-13-01"), ..Default::default() }).starts_with("Date de fin invalide"));
+    assert_eq!(
+      err(ContestSearch { date_from: opt("2026-05-01"), date_to: opt("2026-04-30"), ..Default::default() }),
+      "La date de début doit précéder la date de fin."
+    );
+    assert_eq!(err(ContestSearch { discipline: opt("polo"), ..Default::default() }), "Discipline inconnue: polo");
+  }
+
+  #[test]
+  fn matches_table() {
+    let c = ContestSummary {
+      start_date: opt("2026-04-12"),
+      end_date: opt("2026-04-14"),
+      departement: opt("41"),
+      disciplines: vec!["SO".into()],
+      organizer: opt("Parc Équestre Fédéral"),
+      ..summary("202641001")
+    };
+    let s = |f: fn(&mut ContestSearch)| {
+      let mut s = ContestSearch::default();
+      f(&mut s);
+      s
+    };
+    let cases: &[(ContestSearch, bool)] = &[
+      (ContestSearch::default(), true),
+      // Période: le concours chevauche [from, to]
+      (s(|s| s.date_from = opt("2026-04-14")), true),
+      (s(|s| s.date_from = opt("2026-04-15")), false),
+      (s(|s| s.date_to = opt("2026-04-12")), true),
+      (s(|s| s.date_to = opt("2026-04-11")), false),
+      (s(|s| { s.date_from = opt("2026-04-13"); s.date_to = opt("2026-04-13"); }), true),
+      (s(|s| s.departement = opt("41")), true),
+      (s(|s| s.departement = opt("45")), false),
+      (s(|s| s.discipline = opt("SO")), true),
+      (s(|s| s.discipline = opt("DR")), false),
+      // Organisateur: sous-chaîne sans accents ni casse
+      (s(|s| s.organizer = opt("parc equestre")), true),
+      (s(|s| s.organizer = opt("Sologne")), false),
+    ];
+    for (i, (search, expected)) in cases.iter().enumerate() {
+      assert_eq!(matches(search, &c), *expected, "cas {i}");
+    }
+
+    // Champ inconnu sur le résumé: le concours n'est pas rejeté
+    let unknown = summary("202641009");
+    let strict = ContestSearch {
+      date_from: opt("2030-01-01"),
+      departement: opt("45"),
+      discipline: opt("DR"),
+      organizer: opt("x"),
+      ..Default::default()
+    };
+    assert!(matches(&strict, &unknown));
+    // Date de fin absente: la date de début sert de fin
+    let one_day = ContestSummary { start_date: opt("2026-04-20"), ..summary("202675002") };
+    assert!(!matches(&s(|s| s.date_from = opt("2026-04-21")), &one_day));
+  }
+
+  #[test]
+  fn dates_and_places() {
+    assert_eq!(dates_in("Du 6/6/2026 au 07/06/2026"), vec!["2026-06-06", "2026-06-07"]);
+    assert_eq!(dates_in("2026-07-10T00:00:00"), vec!["2026-07-10"]);
+    assert!(dates_in("31/02/2026 ou 12/2026").is_empty());
+
+    let place = |s: &str| place_in(s).map(|(c, d)| (c.unwrap_or_default(), d));
+    assert_eq!(place("Lamotte-Beuvron (41)"), Some(("Lamotte-Beuvron".into(), "41".into())));
+    assert_eq!(place("Lieu : Ajaccio (2a)"), Some(("Ajaccio".into(), "2A".into())));
+    assert_eq!(place("41600 Lamotte-Beuvron, France"), Some(("Lamotte-Beuvron".into(), "41".into())));
+    assert_eq!(place("97400 Saint-Denis"), Some(("Saint-Denis".into(), "974".into())));
+    assert_eq!(place("(974)"), Some((String::new(), "974".into())));
+    assert_eq!(place("Sans lieu"), None);
+  }
+
+  fn fee(label: &str, amount_eur: f64) -> ContestFee {
+    ContestFee { label: label.into(), amount_eur }
+  }
+
+  #[test]
+  fn detail_table() {
+    let d = parse_detail("202641001", DETAIL_TABLE_HTML);
+    assert_eq!(d, ContestDetail {
+      id: "202641001".into(),
+      name: "CSO Amateur & Club de Printemps".into(),
+      venue: opt("Parc Equestre Fédéral, 41600 Lamotte-Beuvron"),
+      city: opt("Lamotte-Beuvron"),
+      departement: opt("41"),
+      start_date: opt("2026-04-12"),
+      end_date: opt("2026-04-14"),
+      organizer: opt("Club Hippique de Sologne"),
+      fees: vec![fee("Engagement", 25.5), fee("Box", 60.0)],
+      opening_date: opt("2026-03-01T08:00"),
+      closing_date: opt("2026-04-06T23:59"),
+      contact: ContestContact { name: opt("Marie Dupont"), email: opt("marie@chs.fr"), phone: opt("06 12 34 56 78") },
+      url: "https://ffecompet.ffe.com/concours/202641001".into(),
+      fetched_at: 0,
+    });
+  }
+
+  #[test]
+  fn detail_key_value_lines() {
+    let d = parse_detail("202675002", DETAIL_LINES_HTML);
+    assert_eq!(d, ContestDetail {
+      id: "202675002".into(),
+      // Pas de <h1>: titre de la page sans le suffixe du site
+      name: "Dressage Club et Poney".into(),
+      venue: opt("Centre équestre de Vincennes, Route de la Pyramide, Paris (75)"),
+      city: opt("Paris"),
+      departement: opt("75"),
+      start_date: opt("2026-04-20"),
+      end_date: opt("2026-04-20"),
+      organizer: opt("UCPA Vincennes"),
+      fees: vec![fee("Frais d'engagement", 18.0), fee("Frais d'engagement", 5.0)],
+      opening_date: opt("2026-03-15"),
+      closing_date: opt("2026-04-13T12:00"),
+      contact: ContestContact { name: opt("Paul Martin"), email: opt("secretariat@ucpa-vincennes.fr"), phone: opt("01 43 98 00 00") },
+      url: "https://ffecompet.ffe.com/concours/202675002".into(),
+      fetched_at: 0,
+    });
+  }
+
+  #[test]
+  fn detail_json_state() {
+    let d = parse_detail("202649003", DETAIL_NEXT_DATA_HTML);
+    assert_eq!(d, ContestDetail {
+      id: "202649003".into(),
+      name: "CCE de Saumur".into(),
+      venue: opt("Terrain du Breil"),
+      city: opt("Saumur"),
+      departement: opt("49"),
+      start_date: opt("2026-05-01"),
+      end_date: opt("2026-05-03"),
+      organizer: opt("IFCE"),
+      fees: vec![fee("Engagement", 42.0), fee("Box", 75.0)],
+      opening_date: opt("2026-03-20T08:00"),
+      closing_date: opt("2026-04-20T12:00"),
+      contact: ContestContact { name: opt("Secrétariat"), email: opt("cce@ifce.fr"), phone: opt("02 41 53 50 50") },
+      url: "https://ffecompet.ffe.com/concours/202649003".into(),
+      fetched_at: 0,
+    });
+  }
+
+  #[test]
+  fn detail_without_data() {
+    let d = parse_detail("202600001", "<html><body><p>Page indisponible</p></body></html>");
+    assert_eq!(d.name, "Concours 202600001");
+    assert_eq!((d.start_date, d.closing_date, d.contact.email), (None, None, None));
+    assert!(d.fees.is_empty());
+  }
+
+  #[test]
+  fn datetimes() {
+    assert_eq!(datetime_in("01/03/2026 à 8h00").as_deref(), Some("2026-03-01T08:00"));
+    assert_eq!(datetime_in("2026-04-20T12:00:00").as_deref(), Some("2026-04-20T12:00"));
+    assert_eq!(datetime_in("13/04/2026 à 12h").as_deref(), Some("2026-04-13T12:00"));
+    assert_eq!(datetime_in("15/03/2026").as_deref(), Some("2026-03-15"));
+    assert_eq!(datetime_in("bientôt"), None);
+  }
+}
@@ -0,0 +1,365 @@
+// Cookie jar de la session FFE, avec la sémantique domaine / chemin / secure / expiration
+// (RFC 6265 simplifiée). Branché sur reqwest via `ClientBuilder::cookie_provider`, ce qui
+// réécrit automatiquement les `Set-Cookie` reçus (session rafraîchie) dans le jar.
+
+use std::sync::{
+  atomic::{AtomicU64, Ordering},
+  RwLock,
+};
+use reqwest::{header::HeaderValue, Url};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredCookie {
+  pub name: String,
+  pub value: String,
+  /// Domaine en minuscules, sans point initial
+  pub domain: String,
+  /// true si le cookie ne vaut que pour l'hôte exact (pas d'attribut Domain)
+  pub host_only: bool,
+  pub path: String,
+  pub secure: bool,
+  pub http_only: bool,
+  /// Expiration (timestamp unix); None = cookie de session
+  pub expires: Option<i64>,
+}
+
+impl StoredCookie {
+  fn is_expired(&self, now: i64) -> bool {
+    self.expires.is_some_and(|e| e <= now)
+  }
+
+  fn same_key(&self, other: &StoredCookie) -> bool {
+    self.name == other.name && self.domain == other.domain && self.path == other.path
+  }
+
+  fn matches(&self, url: &Url, now: i64) -> bool {
+    let Some(host) = url.host_str().map(|h| h.to_ascii_lowercase()) else { return false; };
+    if self.is_expired(now) {
+      return false;
+    }
+    if self.secure && url.scheme() != "https" {
+      return false;
+    }
+    domain_match(&host, &self.domain, self.host_only) && path_match(url.path(), &self.path)
+  }
+
+  /// Conversion d'un cookie `cookie::Cookie` (Set-Cookie ou WebView) reçu pour `url`.
+  /// Renvoie None si le domaine annoncé ne correspond pas à l'hôte d'origine.
+  pub fn from_cookie(c: &cookie::Cookie<'_>, url: &Url) -> Option<StoredCookie> {
+    let host = url.host_str()?.to_ascii_lowercase();
+
+    let (domain, host_only) = match c.domain().map(|d| d.trim_start_matches('.').to_ascii_lowercase()) {
+      Some(d) if !d.is_empty() => {
+        if !domain_match(&host, &d, false) {
+          return None;
+        }
+        (d, false)
+      }
+      _ => (host, true),
+    };
+
+    let path = match c.path() {
+      Some(p) if p.starts_with('/') => p.to_string(),
+      _ => default_path(url.path()),
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    // Max-Age prime sur Expires
+    let expires = if let Some(max_age) = c.max_age() {
+      Some(now + max_age.whole_seconds())
+    } else {
+      c.expires_datetime().map(|t| t.unix_timestamp())
+    };
+
+    Some(StoredCookie {
+      name: c.name().to_string(),
+      value: c.value().to_string(),
+      domain,
+      host_only,
+      path,
+      secure: c.secure().unwrap_or(false),
+      http_only: c.http_only().unwrap_or(false),
+      expires,
+    })
+  }
+
+  /// Conversion d'un cookie lu dans le store de la WebView: le domaine y est toujours renseigné
+  /// (".ffe.com" = cookie de domaine, "sso.ffe.com" = cookie limité à l'hôte).
+  pub fn from_browser(c: &cookie::Cookie<'_>) -> Option<StoredCookie> {
+    let raw_domain = c.domain()?.to_ascii_lowercase();
+    let domain = raw_domain.trim_start_matches('.').to_string();
+    if domain.is_empty() {
+      return None;
+    }
+
+    Some(StoredCookie {
+      name: c.name().to_string(),
+      value: c.value().to_string(),
+      host_only: !raw_domain.starts_with('.'),
+      domain,
+      path: c.path().filter(|p| p.starts_with('/')).unwrap_or("/").to_string(),
+      secure: c.secure().unwrap_or(false),
+      http_only: c.http_only().unwrap_or(false),
+      expires: c.expires_datetime().map(|t| t.unix_timestamp()),
+    })
+  }
+
+  /// Ancien format persistant ("a=b; c=d", sans domaine): rattaché à tout *.ffe.com.
+  pub fn from_legacy_header(header: &str) -> Vec<StoredCookie> {
+    header
+      .split(';')
+      .filter_map(|kv| kv.trim().split_once('='))
+      .filter(|(k, _)| !k.trim().is_empty())
+      .map(|(k, v)| StoredCookie {
+        name: k.trim().to_string(),
+        value: v.trim().to_string(),
+        domain: "ffe.com".into(),
+        host_only: false,
+        path: "/".into(),
+        secure: false,
+        http_only: false,
+        expires: None,
+      })
+      .collect()
+  }
+}
+
+fn domain_match(host: &str, domain: &str, host_only: bool) -> bool {
+  if host == domain {
+    return true;
+  }
+  !host_only && host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.')
+}
+
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+  let request_path = if request_path.is_empty() { "/" } else { request_path };
+  if request_path == cookie_path {
+    return true;
+  }
+  request_path.starts_with(cookie_path)
+    && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/'))
+}
+
+/// Chemin par défaut: répertoire du chemin de la requête ("/a/b/c" -> "/a/b").
+fn default_path(request_path: &str) -> String {
+  match request_path.rfind('/') {
+    Some(0) | None => "/".to_string(),
+    Some(i) => request_path[..i].to_string(),
+  }
+}
+
+#[derive(Default)]
+pub struct CookieJar {
+  cookies: RwLock<Vec<StoredCookie>>,
+  /// Incrémenté à chaque modification (permet de savoir s'il faut re-persister)
+  generation: AtomicU64,
+}
+
+impl CookieJar {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn generation(&self) -> u64 {
+    self.generation.load(Ordering::SeqCst)
+  }
+
+  fn touch(&self) {
+    self.generation.fetch_add(1, Ordering::SeqCst);
+  }
+
+  /// Ajoute / remplace un cookie (clé: nom + domaine + chemin). Un cookie expiré supprime l'existant.
+  pub fn insert(&self, c: StoredCookie) {
+    let now = chrono::Utc::now().timestamp();
+    let mut g = self.cookies.write().unwrap();
+    let before = g.len();
+    let existing = g.iter().position(|x| x.same_key(&c));
+
+    match (existing, c.is_expired(now)) {
+      (Some(i), true) => { g.remove(i); }
+      (Some(i), false) => {
+        if g[i] == c { return; }
+        g[i] = c;
+      }
+      (None, true) => {}
+      (None, false) => g.push(c),
+    }
+    if existing.is_some() || g.len() != before {
+      drop(g);
+      self.touch();
+    }
+  }
+
+  /// Remplace tout le contenu du jar (ex: cookies relus depuis la WebView de login).
+  /// Renvoie true si le contenu a changé.
+  pub fn replace_all(&self, cookies: Vec<StoredCookie>) -> bool {
+    let now = chrono::Utc::now().timestamp();
+    let fresh: Vec<StoredCookie> = cookies.into_iter().filter(|c| !c.is_expired(now)).collect();
+    let mut g = self.cookies.write().unwrap();
+    if *g == fresh {
+      return false;
+    }
+    *g = fresh;
+    drop(g);
+    self.touch();
+    true
+  }
+
+  pub fn clear(&self) {
+    self.replace_all(Vec::new());
+  }
+
+  /// Cookies encore valides (pour la persistance).
+  pub fn snapshot(&self) -> Vec<StoredCookie> {
+    let now = chrono::Utc::now().timestamp();
+    self.cookies.read().unwrap().iter().filter(|c| !c.is_expired(now)).cloned().collect()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.snapshot().is_empty()
+  }
+
+  /// Expiration la plus proche parmi les cookies HttpOnly persistants (cookies d'authentification;
+  /// les cookies posés en JS, ex: mesure d'audience, ne sont pas HttpOnly).
+  pub fn earliest_auth_expiry(&self) -> Option<i64> {
+    self.snapshot().iter().filter(|c| c.http_only).filter_map(|c| c.expires).min()
+  }
+
+  /// Cookies d'authentification (HttpOnly) sans expiration: cookies de session, qui expirent
+  /// côté serveur après une période d'inactivité.
+  pub fn has_session_auth_cookie(&self) -> bool {
+    self.snapshot().iter().any(|c| c.http_only && c.expires.is_none())
+  }
+
+  /// Header `Cookie` pour `url` (chemins les plus spécifiques en premier, cf. RFC 6265 §5.4).
+  pub fn header_for(&self, url: &Url) -> Option<String> {
+    let now = chrono::Utc::now().timestamp();
+    let g = self.cookies.read().unwrap();
+    let mut matching: Vec<&StoredCookie> = g.iter().filter(|c| c.matches(url, now)).collect();
+    if matching.is_empty() {
+      return None;
+    }
+    matching.sort_by_key(|c| std::cmp::Reverse(c.path.len()));
+    Some(matching.iter().map(|c| format!("{}={}", c.name, c.value)).collect::<Vec<_>>().join("; "))
+  }
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+  fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+    for h in cookie_headers {
+      let Ok(raw) = h.to_str() else { continue; };
+      let Ok(c) = cookie::Cookie::parse(raw) else { continue; };
+      if let Some(sc) = StoredCookie::from_cookie(&c, url) {
+        self.insert(sc);
+      }
+    }
+  }
+
+  fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+    self.header_for(url).and_then(|s| HeaderValue::from_str(&s).ok())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn url(s: &str) -> Url {
+    Url::parse(s).unwrap()
+  }
+
+  fn set(jar: &CookieJar, raw: &str, from: &str) {
+    let c = cookie::Cookie::parse(raw).unwrap();
+    jar.insert(StoredCookie::from_cookie(&c, &url(from)).expect("cookie accepté"));
+  }
+
+  #[test]
+  fn domain_match_rules() {
+    assert!(domain_match("ffe.com", "ffe.com", true));
+    assert!(domain_match("sso.ffe.com", "sso.ffe.com", true));
+    assert!(domain_match("ffecompet.ffe.com", "ffe.com", false));
+    assert!(domain_match("a.b.ffe.com", "ffe.com", false));
+    // Cookie limité à l'hôte: pas les sous-domaines
+    assert!(!domain_match("ffecompet.ffe.com", "ffe.com", true));
+    // Suffixe sans frontière de label
+    assert!(!domain_match("evilffe.com", "ffe.com", false));
+    assert!(!domain_match("ffe.com", "ffecompet.ffe.com", false));
+  }
+
+  #[test]
+  fn path_match_rules() {
+    assert!(path_match("/", "/"));
+    assert!(path_match("", "/"));
+    assert!(path_match("/concours/1", "/"));
+    assert!(path_match("/concours", "/concours"));
+    assert!(path_match("/concours/1", "/concours"));
+    assert!(path_match("/concours/1", "/concours/"));
+    assert!(!path_match("/concoursX", "/concours"));
+    assert!(!path_match("/", "/concours"));
+    assert!(!path_match("/Concours/1", "/concours"));
+  }
+
+  #[test]
+  fn default_path_is_request_directory() {
+    assert_eq!(default_path(""), "/");
+    assert_eq!(default_path("/"), "/");
+    assert_eq!(default_path("/login"), "/");
+    assert_eq!(default_path("/a/b/c"), "/a/b");
+    assert_eq!(default_path("/a/b/"), "/a/b");
+    assert_eq!(default_path("sans-slash"), "/");
+  }
+
+  #[test]
+  fn host_only_and_domain_cookies() {
+    let jar = CookieJar::new();
+    set(&jar, "host=1; Path=/", "https://sso.ffe.com/login");
+    set(&jar, "dom=2; Domain=.ffe.com; Path=/", "https://sso.ffe.com/login");
+
+    assert_eq!(jar.header_for(&url("https://sso.ffe.com/")).as_deref(), Some("host=1; dom=2"));
+    assert_eq!(jar.header_for(&url("https://ffecompet.ffe.com/")).as_deref(), Some("dom=2"));
+    assert_eq!(jar.header_for(&url("https://example.com/")), None);
+
+    // Domain qui ne couvre pas l'hôte d'origine: refusé
+    let c = cookie::Cookie::parse("x=1; Domain=example.com").unwrap();
+    assert!(StoredCookie::from_cookie(&c, &url("https://sso.ffe.com/")).is_none());
+  }
+
+  #[test]
+  fn path_and_secure_in_header() {
+    let jar = CookieJar::new();
+    set(&jar, "root=1; Path=/", "https://ffecompet.ffe.com/");
+    set(&jar, "deep=2; Path=/concours; Secure", "https://ffecompet.ffe.com/");
+    // Chemin par défaut: répertoire de la requête
+    set(&jar, "dflt=3", "https://ffecompet.ffe.com/engagement/panier");
+
+    // Chemin le plus spécifique en premier
+    assert_eq!(jar.header_for(&url("https://ffecompet.ffe.com/concours/1")).as_deref(), Some("deep=2; root=1"));
+    assert_eq!(jar.header_for(&url("http://ffecompet.ffe.com/concours/1")).as_deref(), Some("root=1"));
+    assert_eq!(jar.header_for(&url("https://ffecompet.ffe.com/engagement/x")).as_deref(), Some("dflt=3; root=1"));
+  }
+
+  #[test]
+  fn expiry() {
+    let now = chrono::Utc::now().timestamp();
+    let jar = CookieJar::new();
+    set(&jar, "a=1; Max-Age=3600; HttpOnly", "https://ffecompet.ffe.com/");
+    set(&jar, "b=2; Expires=Wed, 01 Jan 2020 00:00:00 GMT", "https://ffecompet.ffe.com/");
+    set(&jar, "js=3; Max-Age=60", "https://ffecompet.ffe.com/");
+    assert_eq!(jar.header_for(&url("https://ffecompet.ffe.com/")).as_deref(), Some("a=1; js=3"));
+
+    // Seuls les cookies HttpOnly comptent pour l'expiration de la session
+    let exp = jar.earliest_auth_expiry().unwrap();
+    assert!((now + 3590..=now + 3610).contains(&exp));
+
+    // Max-Age=0 supprime le cookie existant
+    set(&jar, "a=1; Max-Age=0", "https://ffecompet.ffe.com/");
+    assert_eq!(jar.header_for(&url("https://ffecompet.ffe.com/")).as_deref(), Some("js=3"));
+    assert_eq!(jar.earliest_auth_expiry(), None);
+
+    // Cookies expirés écartés au remplacement
+    let expired = StoredCookie { expires: Some(now - 1), ..StoredCookie::from_legacy_header("old=1")[0].clone() };
+    assert!(jar.replace_all(vec![expired]));
+    assert!(jar.is_empty());
+  }
+}
@@ -1,5 +1,9 @@
-use rusqlite::{params, Connection};
-use crate::models::{AddTargetPayload, Target};
+use rusqlite::{params, Connection, OptionalExtension};
+use crate::conditional::CachedPage;
+use crate::contest_cache::CachedContest;
+use crate::epreuves::EpreuveDetail;
+use crate::{ffe_url, text};
+use crate::models::{AddPushChannelPayload, AddTargetPayload, Alert, ContestDetail, DigestRow, EmailRecipient, PushChannel, PushKind, Snooze, Target, TargetEvent, WatchKind};
 
 pub fn init_db(conn: &Connection) -> rusqlite::Result<()> {
   conn.execute_batch(include_str!("schema.sql"))?;
@@ -7,6 +11,14 @@ pub fn init_db(conn: &Connection) -> rusqlite::Result<()> {
   // Migration légère: ajout de last_slots si absent
   // (SQLite n'a pas IF NOT EXISTS sur ADD COLUMN dans toutes les versions)
   let _ = conn.execute("ALTER TABLE targets ADD COLUMN last_slots INTEGER", []);
+  // Compte FFE de la cible (NULL => compte par défaut)
+  let _ = conn.execute("ALTER TABLE targets ADD COLUMN account TEXT", []);
+  // Type de surveillance (statut de la page / programme du concours)
+  let _ = conn.execute("ALTER TABLE targets ADD COLUMN kind TEXT NOT NULL DEFAULT 'status'", []);
+  // Contrôle de la liste des engagés (cible passée en ENGAGED)
+  let _ = conn.execute("ALTER TABLE targets ADD COLUMN check_engaged INTEGER NOT NULL DEFAULT 0", []);
+  // Ancien cache HTTP partagé par URL (remplacé par page_cache, propre à chaque cible)
+  let _ = conn.execute("DROP TABLE IF EXISTS url_cache", []);
 
   Ok(())
 }
@@ -20,23 +32,54 @@ pub fn add_target(conn: &Connection, p: AddTargetPayload) -> rusqlite::Result<()
   let interval_hot = p.interval_hot_sec.unwrap_or(45).max(10);
 
   conn.execute(
-    r#"INSERT INTO targets(label,url,cheval,cavalier,interval_normal_sec,interval_hot_sec,hot_from,hot_to)
-       VALUES(?,?,?,?,?,?,?,?)"#,
-    params![p.label, p.url, p.cheval, p.cavalier, interval_normal, interval_hot, p.hot_from, p.hot_to],
+    r#"INSERT INTO targets(label,url,cheval,cavalier,interval_normal_sec,interval_hot_sec,hot_from,hot_to,account,kind,check_engaged)
+       VALUES(?,?,?,?,?,?,?,?,?,?,?)"#,
+    params![p.label, p.url, p.cheval, p.cavalier, interval_normal, interval_hot, p.hot_from, p.hot_to, p.account, watch_kind_str(p.kind), p.check_engaged],
   )?;
   Ok(())
 }
 
+/// Cible déjà surveillée: même type, même concours / épreuve (`ffe_url::same_target`) et même
+/// cavalier (casse, accents et espaces ignorés; absent = sans cavalier).
+pub fn find_duplicate(conn: &Connection, p: &AddTargetPayload) -> rusqlite::Result<Option<Target>> {
+  let rider = |c: Option<&str>| text::normalize(c.unwrap_or(""));
+  let wanted = rider(p.cavalier.as_deref());
+  Ok(list_targets(conn)?
+    .into_iter()
+    .find(|t| t.kind == p.kind && rider(t.cavalier.as_deref()) == wanted && ffe_url::same_target(&t.url, &p.url)))
+}
+
+/// Ajoute les cibles en une transaction, sauf celles déjà surveillées.
+/// Renvoie pour chaque payload `true` si la cible a été créée.
+pub fn add_targets(conn: &mut Connection, payloads: Vec<AddTargetPayload>) -> rusqlite::Result<Vec<bool>> {
+  let tx = conn.transaction()?;
+  let mut created = Vec::with_capacity(payloads.len());
+  for p in payloads {
+    let exists = find_duplicate(&tx, &p)?.is_some();
+    if !exists {
+      add_target(&tx, p)?;
+    }
+    created.push(!exists);
+  }
+  tx.commit()?;
+  Ok(created)
+}
+
 pub fn delete_target(conn: &Connection, id: i64) -> rusqlite::Result<()> {
   // IMPORTANT: supprimer d'abord les enfants
   conn.execute("DELETE FROM events WHERE target_id=?", params![id])?;
+  conn.execute("DELETE FROM push_channels WHERE target_id=?", params![id])?;
+  conn.execute("DELETE FROM target_snoozes WHERE target_id=?", params![id])?;
+  conn.execute("DELETE FROM queued_alerts WHERE target_id=?", params![id])?;
+  conn.execute("DELETE FROM contest_snapshots WHERE target_id=?", params![id])?;
+  conn.execute("DELETE FROM page_cache WHERE target_id=?", params![id])?;
   conn.execute("DELETE FROM targets WHERE id=?", params![id])?;
   Ok(())
 }
 
 pub fn list_targets(conn: &Connection) -> rusqlite::Result<Vec<Target>> {
   let mut stmt = conn.prepare(
-    "SELECT id,label,url,cheval,cavalier,interval_normal_sec,interval_hot_sec,hot_from,hot_to,last_status,last_checked_at,last_error,last_slots
+    "SELECT id,label,url,cheval,cavalier,interval_normal_sec,interval_hot_sec,hot_from,hot_to,last_status,last_checked_at,last_error,last_slots,account,kind,check_engaged
      FROM targets ORDER BY id DESC"
   )?;
   let rows = stmt.query_map([], |r| {
@@ -54,6 +97,9 @@ pub fn list_targets(conn: &Connection) -> rusqlite::Result<Vec<Target>> {
       last_checked_at: r.get(10)?,
       last_error: r.get(11)?,
       last_slots: r.get(12)?,
+      account: r.get(13)?,
+      kind: if r.get::<_, String>(14)? == "contest" { WatchKind::Contest } else { WatchKind::Status },
+      check_engaged: r.get(15)?,
     })
   })?;
 
@@ -75,6 +121,29 @@ pub fn update_status(conn: &Connection, id: i64, status: &str, now: i64, err: Op
   Ok(())
 }
 
+/// Contrôle d'une cible concours: statut, et différences du programme dans l'historique.
+pub fn record_contest_check(conn: &Connection, id: i64, status: &str, now: i64, note: Option<&str>) -> rusqlite::Result<()> {
+  conn.execute(
+    "UPDATE targets SET last_status=?, last_checked_at=?, last_change_at=CASE WHEN last_status<>? THEN ? ELSE last_change_at END, last_error=NULL WHERE id=?",
+    params![status, now, status, now, id]
+  )?;
+  conn.execute(
+    "INSERT INTO events(target_id, ts, status, note) VALUES(?,?,?,?)",
+    params![id, now, status, note.unwrap_or("")]
+  )?;
+  Ok(())
+}
+
+/// Historique d'une cible, du plus récent au plus ancien.
+pub fn target_events(conn: &Connection, id: i64, limit: i64) -> rusqlite::Result<Vec<TargetEvent>> {
+  let mut stmt = conn.prepare("SELECT ts, status, note FROM events WHERE target_id=? ORDER BY ts DESC, id DESC LIMIT ?")?;
+  let rows = stmt.query_map(params![id, limit], |r| {
+    let note: Option<String> = r.get(2)?;
+    Ok(TargetEvent { ts: r.get(0)?, status: r.get(1)?, note: note.filter(|n| !n.is_empty()) })
+  })?;
+  rows.collect()
+}
+
 pub fn set_last_slots(conn: &Connection, id: i64, slots: i32) -> rusqlite::Result<()> {
   conn.execute(
     "UPDATE targets SET last_slots=? WHERE id=?",
@@ -82,3 +151,453 @@ pub fn set_last_slots(conn: &Connection, id: i64, slots: i32) -> rusqlite::Resul
   )?;
   Ok(())
 }
+
+/// Vérification sans changement de contenu: on met juste à jour l'heure (pas d'event).
+pub fn touch_checked(conn: &Connection, id: i64, now: i64) -> rusqlite::Result<()> {
+  conn.execute("UPDATE targets SET last_checked_at=?, last_error=NULL WHERE id=?", params![now, id])?;
+  Ok(())
+}
+
+// ===================== CACHE HTTP (requêtes conditionnelles) =====================
+// Un cache par cible: deux cibles sur la même URL (cavaliers / comptes différents) ont
+// chacune leur empreinte, sinon la seconde verrait "inchangé" et raterait l'ouverture.
+
+pub fn get_cached_page(conn: &Connection, target_id: i64) -> rusqlite::Result<Option<CachedPage>> {
+  conn
+    .query_row(
+      "SELECT etag, last_modified, body_hash FROM page_cache WHERE target_id=?",
+      params![target_id],
+      |r| Ok(CachedPage { etag: r.get(0)?, last_modified: r.get(1)?, body_hash: r.get(2)? }),
+    )
+    .optional()
+}
+
+pub fn set_cached_page(conn: &Connection, target_id: i64, page: &CachedPage, now: i64) -> rusqlite::Result<()> {
+  conn.execute(
+    r#"INSERT INTO page_cache(target_id, etag, last_modified, body_hash, checked_at) VALUES(?,?,?,?,?)
+       ON CONFLICT(target_id) DO UPDATE SET etag=excluded.etag, last_modified=excluded.last_modified,
+         body_hash=excluded.body_hash, checked_at=excluded.checked_at"#,
+    params![target_id, page.etag, page.last_modified, page.body_hash, now]
+  )?;
+  Ok(())
+}
+
+// ===================== CACHE CONCOURS (épreuves) =====================
+
+pub fn get_contest_cache(conn: &Connection, contest_id: &str) -> rusqlite::Result<Option<CachedContest>> {
+  let row: Option<(String, String, i64, i64)> = conn
+    .query_row(
+      "SELECT payload, payload_hash, fetched_at, ttl_sec FROM contest_cache WHERE contest_id=?",
+      params![contest_id],
+      |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+    )
+    .optional()?;
+  // Liste illisible (ancien format) => traitée comme absente
+  Ok(row.and_then(|(payload, payload_hash, fetched_at, ttl_sec)| {
+    let epreuves = serde_json::from_str(&payload).ok()?;
+    Some(CachedContest { epreuves, payload_hash, fetched_at, ttl_sec })
+  }))
+}
+
+pub fn set_contest_cache(conn: &Connection, contest_id: &str, c: &CachedContest) -> rusqlite::Result<()> {
+  let payload = serde_json::to_string(&c.epreuves)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+  conn.execute(
+    r#"INSERT INTO contest_cache(contest_id, payload, payload_hash, fetched_at, ttl_sec) VALUES(?,?,?,?,?)
+       ON CONFLICT(contest_id) DO UPDATE SET payload=excluded.payload, payload_hash=excluded.payload_hash,
+         fetched_at=excluded.fetched_at, ttl_sec=excluded.ttl_sec"#,
+    params![contest_id, payload, c.payload_hash, c.fetched_at, c.ttl_sec]
+  )?;
+  Ok(())
+}
+
+// ===================== PROGRAMME DES CIBLES CONCOURS =====================
+
+/// Dernière liste d'épreuves vue par une cible concours (None avant le premier contrôle).
+pub fn get_contest_snapshot(conn: &Connection, target_id: i64) -> rusqlite::Result<Option<Vec<EpreuveDetail>>> {
+  let raw: Option<String> = conn
+    .query_row("SELECT payload FROM contest_snapshots WHERE target_id=?", params![target_id], |r| r.get(0))
+    .optional()?;
+  Ok(raw.and_then(|s| serde_json::from_str(&s).ok()))
+}
+
+pub fn set_contest_snapshot(conn: &Connection, target_id: i64, epreuves: &[EpreuveDetail], now: i64) -> rusqlite::Result<()> {
+  let payload = serde_json::to_string(epreuves)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+  conn.execute(
+    r#"INSERT INTO contest_snapshots(target_id, payload, checked_at) VALUES(?,?,?)
+       ON CONFLICT(target_id) DO UPDATE SET payload=excluded.payload, checked_at=excluded.checked_at"#,
+    params![target_id, payload, now]
+  )?;
+  Ok(())
+}
+
+// ===================== COMPTES FFE =====================
+
+/// Comptes nommés créés par l'utilisateur (le compte par défaut n'est pas stocké).
+pub fn list_accounts(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+  let mut stmt = conn.prepare("SELECT name FROM ffe_accounts ORDER BY created_at, name")?;
+  let rows = stmt.query_map([], |r| r.get(0))?;
+  rows.collect()
+}
+
+pub fn add_account(conn: &Connection, name: &str, now: i64) -> rusqlite::Result<()> {
+  conn.execute(
+    "INSERT OR IGNORE INTO ffe_accounts(name, created_at) VALUES(?,?)",
+    params![name, now]
+  )?;
+  Ok(())
+}
+
+pub fn delete_account(conn: &Connection, name: &str) -> rusqlite::Result<()> {
+  conn.execute("DELETE FROM ffe_accounts WHERE name=?", params![name])?;
+  Ok(())
+}
+
+/// Nombre de cibles rattachées au compte (NULL compte pour le compte par défaut).
+pub fn count_targets_for_account(conn: &Connection, name: &str, is_default: bool) -> rusqlite::Result<i64> {
+  conn.query_row(
+    "SELECT COUNT(*) FROM targets WHERE account=? OR (? AND account IS NULL)",
+    params![name, is_default],
+    |r| r.get(0)
+  )
+}
+
+// ===================== RÉGLAGES (clé / valeur JSON) =====================
+
+pub fn get_setting<T: serde::de::DeserializeOwned>(conn: &Connection, key: &str) -> rusqlite::Result<Option<T>> {
+  let raw: Option<String> = conn
+    .query_row("SELECT value FROM settings WHERE key=?", params![key], |r| r.get(0))
+    .optional()?;
+  // Valeur illisible (ancien format) => on retombe sur les valeurs par défaut
+  Ok(raw.and_then(|s| serde_json::from_str(&s).ok()))
+}
+
+pub fn set_setting<T: serde::Serialize>(conn: &Connection, key: &str, value: &T) -> rusqlite::Result<()> {
+  let raw = serde_json::to_string(value)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+  conn.execute(
+    "INSERT INTO settings(key, value) VALUES(?,?) ON CONFLICT(key) DO UPDATE SET value=excluded.value",
+    params![key, raw]
+  )?;
+  Ok(())
+}
+
+// ===================== DESTINATAIRES EMAIL =====================
+
+pub fn add_email_recipient(conn: &Connection, cavalier: &str, email: &str) -> rusqlite::Result<()> {
+  conn.execute(
+    "INSERT OR IGNORE INTO email_recipients(cavalier, email) VALUES(?,?)",
+    params![cavalier.trim(), email.trim()]
+  )?;
+  Ok(())
+}
+
+pub fn delete_email_recipient(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+  conn.execute("DELETE FROM email_recipients WHERE id=?", params![id])?;
+  Ok(())
+}
+
+pub fn list_email_recipients(conn: &Connection) -> rusqlite::Result<Vec<EmailRecipient>> {
+  let mut stmt = conn.prepare("SELECT id,cavalier,email FROM email_recipients ORDER BY cavalier, email")?;
+  let rows = stmt.query_map([], |r| Ok(EmailRecipient { id: r.get(0)?, cavalier: r.get(1)?, email: r.get(2)? }))?;
+
+  let mut out = Vec::new();
+  for row in rows { out.push(row?); }
+  Ok(out)
+}
+
+pub fn recipients_for_rider(conn: &Connection, cavalier: &str) -> rusqlite::Result<Vec<String>> {
+  let mut stmt = conn.prepare("SELECT email FROM email_recipients WHERE cavalier=? ORDER BY email")?;
+  let rows = stmt.query_map(params![cavalier.trim()], |r| r.get(0))?;
+
+  let mut out = Vec::new();
+  for row in rows { out.push(row?); }
+  Ok(out)
+}
+
+/// Récapitulatif par cible des évènements depuis `since` (timestamp unix).
+pub fn digest_rows(conn: &Connection, cavalier: &str, since: i64) -> rusqlite::Result<Vec<DigestRow>> {
+  let mut stmt = conn.prepare(
+    "SELECT id,label,url,last_status,last_checked_at,last_error FROM targets WHERE cavalier=? COLLATE NOCASE ORDER BY id"
+  )?;
+  let targets = stmt.query_map(params![cavalier.trim()], |r| {
+    Ok(DigestRow {
+      target_id: r.get(0)?,
+      label: r.get(1)?,
+      url: r.get(2)?,
+      last_status: r.get(3)?,
+      last_checked_at: r.get(4)?,
+      last_error: r.get(5)?,
+      checks: 0,
+      statuses: Vec::new(),
+    })
+  })?;
+
+  let mut ev = conn.prepare("SELECT status FROM events WHERE target_id=? AND ts>=? ORDER BY ts")?;
+  let mut out = Vec::new();
+  for row in targets {
+    let mut row = row?;
+    let statuses = ev.query_map(params![row.target_id, since], |r| r.get::<_, String>(0))?;
+    for s in statuses {
+      let s = s?;
+      row.checks += 1;
+      // on ne garde que les transitions (CLOSED, CLOSED, OPEN => CLOSED, OPEN)
+      if row.statuses.last() != Some(&s) {
+        row.statuses.push(s);
+      }
+    }
+    out.push(row);
+  }
+  Ok(out)
+}
+
+/// Cavaliers ayant au moins une cible et un destinataire email.
+pub fn riders_with_recipients(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+  let mut stmt = conn.prepare(
+    "SELECT DISTINCT r.cavalier FROM email_recipients r JOIN targets t ON r.cavalier = t.cavalier ORDER BY r.cavalier"
+  )?;
+  let rows = stmt.query_map([], |r| r.get(0))?;
+
+  let mut out = Vec::new();
+  for row in rows { out.push(row?); }
+  Ok(out)
+}
+
+// ===================== CANAUX PUSH (ntfy / Gotify) =====================
+
+fn watch_kind_str(k: WatchKind) -> &'static str {
+  match k {
+    WatchKind::Status => "status",
+    WatchKind::Contest => "contest",
+  }
+}
+
+fn push_kind_str(k: PushKind) -> &'static str {
+  match k {
+    PushKind::Ntfy => "ntfy",
+    PushKind::Gotify => "gotify",
+  }
+}
+
+fn row_to_push_channel(r: &rusqlite::Row) -> rusqlite::Result<PushChannel> {
+  let kind: String = r.get(1)?;
+  Ok(PushChannel {
+    id: r.get(0)?,
+    kind: if kind == "gotify" { PushKind::Gotify } else { PushKind::Ntfy },
+    server_url: r.get(2)?,
+    topic: r.get(3)?,
+    token: r.get(4)?,
+    cavalier: r.get(5)?,
+    target_id: r.get(6)?,
+  })
+}
+
+pub fn add_push_channel(conn: &Connection, p: AddPushChannelPayload) -> rusqlite::Result<i64> {
+  conn.execute(
+    "INSERT INTO push_channels(kind,server_url,topic,token,cavalier,target_id) VALUES(?,?,?,?,?,?)",
+    params![push_kind_str(p.kind), p.server_url.trim().trim_end_matches('/'), p.topic, p.token, p.cavalier, p.target_id]
+  )?;
+  Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_push_channel(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+  conn.execute("DELETE FROM push_channels WHERE id=?", params![id])?;
+  Ok(())
+}
+
+pub fn get_push_channel(conn: &Connection, id: i64) -> rusqlite::Result<Option<PushChannel>> {
+  conn.query_row(
+    "SELECT id,kind,server_url,topic,token,cavalier,target_id FROM push_channels WHERE id=?",
+    params![id],
+    row_to_push_channel,
+  ).optional()
+}
+
+pub fn list_push_channels(conn: &Connection) -> rusqlite::Result<Vec<PushChannel>> {
+  let mut stmt = conn.prepare("SELECT id,kind,server_url,topic,token,cavalier,target_id FROM push_channels ORDER BY id")?;
+  let rows = stmt.query_map([], row_to_push_channel)?;
+
+  let mut out = Vec::new();
+  for row in rows { out.push(row?); }
+  Ok(out)
+}
+
+/// Canaux concernés par une cible: ceux de la cible + ceux de son cavalier.
+pub fn push_channels_for(conn: &Connection, target_id: i64, cavalier: Option<&str>) -> rusqlite::Result<Vec<PushChannel>> {
+  let mut stmt = conn.prepare(
+    "SELECT id,kind,server_url,topic,token,cavalier,target_id FROM push_channels
+     WHERE target_id=? OR (target_id IS NULL AND cavalier IS NOT NULL AND cavalier=?)
+     ORDER BY id"
+  )?;
+  let rows = stmt.query_map(params![target_id, cavalier.map(str::trim)], row_to_push_channel)?;
+
+  let mut out = Vec::new();
+  for row in rows { out.push(row?); }
+  Ok(out)
+}
+
+// ===================== SNOOZE / FILE D'ALERTES =====================
+
+pub fn snooze_target(conn: &Connection, id: i64, until: i64) -> rusqlite::Result<()> {
+  conn.execute(
+    "INSERT INTO target_snoozes(target_id, until) VALUES(?,?) ON CONFLICT(target_id) DO UPDATE SET until=excluded.until",
+    params![id, until]
+  )?;
+  Ok(())
+}
+
+pub fn unsnooze_target(conn: &Connection, id: i64) -> rusqlite::Result<()> {
+  conn.execute("DELETE FROM target_snoozes WHERE target_id=?", params![id])?;
+  Ok(())
+}
+
+pub fn snoozed_until(conn: &Connection, id: i64) -> rusqlite::Result<Option<i64>> {
+  conn.query_row("SELECT until FROM target_snoozes WHERE target_id=?", params![id], |r| r.get(0)).optional()
+}
+
+/// Snoozes encore actifs (les expirés sont purgés au passage).
+pub fn active_snoozes(conn: &Connection, now: i64) -> rusqlite::Result<Vec<Snooze>> {
+  conn.execute("DELETE FROM target_snoozes WHERE until<=?", params![now])?;
+
+  let mut stmt = conn.prepare(
+    "SELECT s.target_id, t.label, s.until FROM target_snoozes s JOIN targets t ON t.id = s.target_id ORDER BY s.until"
+  )?;
+  let rows = stmt.query_map([], |r| Ok(Snooze { target_id: r.get(0)?, label: r.get(1)?, until: r.get(2)? }))?;
+
+  let mut out = Vec::new();
+  for row in rows { out.push(row?); }
+  Ok(out)
+}
+
+pub fn queue_alert(conn: &Connection, alert: &Alert, now: i64) -> rusqlite::Result<()> {
+  let payload = serde_json::to_string(alert)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+  conn.execute(
+    "INSERT INTO queued_alerts(target_id, ts, payload) VALUES(?,?,?)",
+    params![alert.target_id, now, payload]
+  )?;
+  Ok(())
+}
+
+pub fn count_queued_alerts(conn: &Connection) -> rusqlite::Result<i64> {
+  conn.query_row("SELECT COUNT(*) FROM queued_alerts", [], |r| r.get(0))
+}
+
+/// Retire de la file et renvoie les alertes en attente, triées par date.
+pub fn take_queued_alerts(conn: &mut Connection, target_ids: &[i64]) -> rusqlite::Result<Vec<(i64, Alert)>> {
+  let tx = conn.transaction()?;
+  let mut out = Vec::new();
+  {
+    let mut sel = tx.prepare("SELECT ts, payload FROM queued_alerts WHERE target_id=? ORDER BY ts")?;
+    for id in target_ids {
+      let rows = sel.query_map(params![id], |r| Ok((r.get::<_, i64>(0)?, r.get::<_, String>(1)?)))?;
+      for row in rows {
+        let (ts, payload) = row?;
+        if let Ok(alert) = serde_json::from_str::<Alert>(&payload) {
+          out.push((ts, alert));
+        }
+      }
+      tx.execute("DELETE FROM queued_alerts WHERE target_id=?", params![id])?;
+    }
+  }
+  tx.commit()?;
+  Ok(out)
+}
+
+pub fn queued_target_ids(conn: &Connection) -> rusqlite::Result<Vec<i64>> {
+  let mut stmt = conn.prepare("SELECT DISTINCT target_id FROM queued_alerts ORDER BY target_id")?;
+  let rows = stmt.query_map([], |r| r.get(0))?;
+
+  let mut out = Vec::new();
+  for row in rows { out.push(row?); }
+  Ok(out)
+}
+
+// ===================== FICHES CONCOURS =====================
+
+pub fn save_contest(conn: &Connection, c: &ContestDetail) -> rusqlite::Result<()> {
+  let payload = serde_json::to_string(c)
+    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+  conn.execute(
+    r#"INSERT INTO contests(id, start_date, payload, fetched_at) VALUES(?,?,?,?)
+       ON CONFLICT(id) DO UPDATE SET start_date=excluded.start_date, payload=excluded.payload,
+         fetched_at=excluded.fetched_at"#,
+    params![c.id, c.start_date, payload, c.fetched_at]
+  )?;
+  Ok(())
+}
+
+pub fn get_contest(conn: &Connection, id: &str) -> rusqlite::Result<Option<ContestDetail>> {
+  let raw: Option<String> = conn
+    .query_row("SELECT payload FROM contests WHERE id=?", params![id], |r| r.get(0))
+    .optional()?;
+  // Fiche illisible (ancien format) => relue depuis ffecompet
+  Ok(raw.and_then(|s| serde_json::from_str(&s).ok()))
+}
+
+/// Fiches enregistrées, par date de début (dates inconnues en dernier).
+pub fn list_contests(conn: &Connection) -> rusqlite::Result<Vec<ContestDetail>> {
+  let mut stmt = conn.prepare("SELECT payload FROM contests ORDER BY start_date IS NULL, start_date, id")?;
+  let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+
+  let mut out = Vec::new();
+  for row in rows {
+    if let Ok(c) = serde_json::from_str(&row?) {
+      out.push(c);
+    }
+  }
+  Ok(out)
+}
+
+pub fn delete_contest(conn: &Connection, id: &str) -> rusqlite::Result<()> {
+  conn.execute("DELETE FROM contests WHERE id=?", params![id])?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn payload(url: &str, cavalier: Option<&str>, kind: WatchKind) -> AddTargetPayload {
+    AddTargetPayload {
+      label: url.into(),
+      url: url.into(),
+      cheval: None,
+      cavalier: cavalier.map(str::to_string),
+      interval_normal_sec: None,
+      interval_hot_sec: None,
+      hot_from: None,
+      hot_to: None,
+      account: None,
+      kind,
+      check_engaged: false,
+    }
+  }
+
+  #[test]
+  fn duplicates_use_normalized_urls_and_riders() {
+    let mut conn = Connection::open_in_memory().unwrap();
+    init_db(&conn).unwrap();
+    let url = "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=4";
+    add_target(&conn, payload(url, Some("Marie Dupont"), WatchKind::Status)).unwrap();
+
+    let dup = |u: &str, c: Option<&str>, k: WatchKind| find_duplicate(&conn, &payload(u, c, k)).unwrap().is_some();
+    assert!(dup(url, Some("Marie Dupont"), WatchKind::Status));
+    assert!(dup("https://m.ffecompet.ffe.com/concours/202635080/epreuve/4?utm_source=x", Some("  marie  DUPONT "), WatchKind::Status));
+    assert!(dup(url, Some("Marie Dupönt"), WatchKind::Status));
+    assert!(!dup(url, Some("Paul Martin"), WatchKind::Status));
+    assert!(!dup(url, None, WatchKind::Status));
+    assert!(!dup(url, Some("Marie Dupont"), WatchKind::Contest));
+    assert!(!dup("https://ffecompet.ffe.com/concours/202635080?watch_epreuve=5", Some("Marie Dupont"), WatchKind::Status));
+
+    // Lot: la cible existante et le doublon interne au lot sont ignorés
+    let created = add_targets(&mut conn, vec![
+      payload("https://ffecompet.ffe.com/concours/202635080/epreuve/4", Some("MARIE DUPONT"), WatchKind::Status),
+      payload("https://ffecompet.ffe.com/concours/202635080?watch_epreuve=5", None, WatchKind::Status),
+      payload("https://ffecompet.ffe.com/concours/202635080#epreuve-5", None, WatchKind::Status),
+    ]).unwrap();
+    assert_eq!(created, vec![false, true, false]);
+    assert_eq!(list_targets(&conn).unwrap().len(), 2);
+  }
+}
@@ -1,3 +1,6 @@
+use serde_json::{Map, Value};
+use crate::{embedded::{extract_json_states, get_str, get_u32}, text::{contains_word, decode_entities, normalize}};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Status {
   Unknown,
@@ -5,13 +8,37 @@ pub enum Status {
   Open,
   Full,
   Error,
+  /// La requête a abouti sur la page de connexion SSO (session FFE expirée)
+  SessionExpired,
+}
+
+/// Reconnaît une réponse "page de connexion" : redirection vers le SSO FFE
+/// ou formulaire d'identification dans le HTML.
+pub fn is_login_page(final_url: &str, html: &str) -> bool {
+  let u = final_url.to_lowercase();
+  if u.contains("sso.ffe.com/login") || u.contains("/identification") || u.trim_end_matches('/').ends_with("/login") {
+    return true;
+  }
+
+  let h = normalize(&decode_entities(html));
+  let has_password = h.contains("type=\"password\"") || h.contains("type='password'") || h.contains("type=password");
+  let login_markers = ["sso.ffe.com", "mot de passe oublie", "name=\"username\"", "id=\"kc-form-login\"", "/login-actions/"];
+  has_password && login_markers.iter().any(|k| h.contains(k))
+}
+
+/// Marqueur d'une page servie à un utilisateur connecté (lien / bouton "Déconnexion").
+pub fn has_logout_marker(html: &str) -> bool {
+  let h = normalize(&decode_entities(html));
+  ["deconnexion", "/logout"].iter().any(|k| h.contains(k))
 }
 
+/// Comparaisons sur le texte normalisé (entités décodées, sans accents ni casse):
+/// "COMPLÈTE", "complete" et "compl&egrave;te" sont équivalents.
 pub fn detect_status_from_html(html: &str) -> Status {
-  let h = html.to_lowercase();
+  let h = normalize(&decode_entities(html));
 
   // FULL first
-  let full_keys = ["complet", "complète", "plus de place", "liste d'attente", "full"];
+  let full_keys = ["complet", "plus de place", "liste d'attente", "full"];
   if full_keys.iter().any(|k| h.contains(k)) {
     return Status::Full;
   }
@@ -23,14 +50,149 @@ pub fn detect_status_from_html(html: &str) -> Status {
   }
 
   // CLOSED
-  let closed_keys = ["engagement fermé", "fermé", "ouverture le", "ouvre le", "pas encore ouvert"];
-  if closed_keys.iter().any(|k| h.contains(k)) {
+  // "ferme" en mot entier: sans accents, il ne doit pas matcher "fermer" / "fermeture"
+  let closed_keys = ["ouverture le", "ouvre le", "pas encore ouvert"];
+  let closed_words = ["ferme", "fermee", "fermes", "fermees"];
+  if closed_keys.iter().any(|k| h.contains(k)) || closed_words.iter().any(|w| contains_word(&h, w)) {
     return Status::Closed;
   }
 
   Status::Unknown
 }
 
+/// Statut d'une page: l'état JSON embarqué (pages hydratées) prime, sinon le texte du HTML.
+/// `epreuve`: n° de l'épreuve surveillée (`?watch_epreuve=N`), pour ne lire que son statut.
+pub fn detect_status(html: &str, epreuve: Option<u32>) -> Status {
+  match detect_status_from_states(&extract_json_states(html), epreuve) {
+    Status::Unknown => detect_status_from_html(html),
+    s => s,
+  }
+}
+
+/// Nom de clé comparable: "engagementsOuverts", "engagements_ouverts" → "engagementsouverts".
+fn key_name(k: &str) -> String {
+  normalize(k).chars().filter(|c| c.is_alphanumeric()).collect()
+}
+
+fn status_from_text(v: &str) -> Status {
+  let v = normalize(v);
+  if v.contains("complet") || v.contains("full") {
+    Status::Full
+  } else if v.contains("pas encore") || v.contains("a venir") || contains_word(&v, "ferme") || contains_word(&v, "fermes") || v.contains("closed") {
+    Status::Closed
+  } else if v.contains("ouvert") || contains_word(&v, "open") {
+    Status::Open
+  } else {
+    Status::Unknown
+  }
+}
+
+/// N° d'une épreuve décrite par l'objet (mêmes clés que epreuves.rs: numéro + libellé,
+/// ou clé `numEpreuve` explicite). None pour le concours lui-même (il porte la liste `epreuves`).
+fn epreuve_num(obj: &Map<String, Value>) -> Option<u32> {
+  if obj.contains_key("epreuves") {
+    return None;
+  }
+  let explicit = get_u32(obj, &["numEpreuve", "numeroEpreuve"]);
+  let generic = || {
+    get_u32(obj, &["numero", "num", "no", "ordre"])
+      .filter(|_| get_str(obj, &["libelle", "label", "nom", "intitule", "designation"]).is_some())
+  };
+  explicit.or_else(generic).filter(|n| (1..1000).contains(n))
+}
+
+/// Statut porté par une clé. Seules les clés propres aux engagements sont lues (un `open`
+/// ou `isOpen` générique décrit souvent un élément d'interface: modale, menu, accordéon);
+/// `statut` / `etat` seuls ne comptent que sur l'objet d'une épreuve.
+fn key_status(key: &str, v: &Value, on_epreuve: bool) -> Status {
+  match v {
+    // Booléens: "complet": true, "engagementsOuverts": false ...
+    Value::Bool(b) => match key {
+      "complet" | "epreuvecomplete" if *b => Status::Full,
+      "engagementouvert" | "engagementsouverts" | "inscriptionouverte" | "inscriptionsouvertes" => {
+        if *b { Status::Open } else { Status::Closed }
+      }
+      _ => Status::Unknown,
+    },
+    // Libellés: "statutEngagement": "Engagements ouverts", "etatEngagement": "COMPLET" ...
+    Value::String(s) => match key {
+      "statutengagement" | "statutengagements" | "etatengagement" | "etatengagements" | "statutinscription" | "statutinscriptions" => {
+        status_from_text(s)
+      }
+      "statut" | "status" | "etat" if on_epreuve => status_from_text(s),
+      _ => Status::Unknown,
+    },
+    _ => Status::Unknown,
+  }
+}
+
+#[derive(Default)]
+struct Found {
+  /// Statuts lus dans l'objet de l'épreuve surveillée
+  target: Vec<Status>,
+  /// L'objet de l'épreuve surveillée a été trouvé
+  target_seen: bool,
+  /// Statuts lus hors de tout objet épreuve (niveau concours / page)
+  page: Vec<Status>,
+  /// Statuts de toutes les épreuves (sans épreuve surveillée)
+  any: Vec<Status>,
+}
+
+/// `within`: n° de l'épreuve englobante la plus proche.
+fn collect_states(v: &Value, epreuve: Option<u32>, within: Option<u32>, found: &mut Found) {
+  match v {
+    Value::Object(obj) => {
+      let own = epreuve_num(obj);
+      let within = own.or(within);
+      // Objet d'une autre épreuve: ignoré avec tout son contenu
+      if let (Some(want), Some(n)) = (epreuve, within) {
+        if want != n {
+          return;
+        }
+      }
+      if epreuve.is_some() && own.is_some() {
+        found.target_seen = true;
+      }
+      for (k, vv) in obj {
+        let status = key_status(&key_name(k), vv, own.is_some());
+        if status != Status::Unknown {
+          match (epreuve, within) {
+            (None, _) => found.any.push(status),
+            (Some(_), Some(_)) => found.target.push(status),
+            (Some(_), None) => found.page.push(status),
+          }
+        }
+        collect_states(vv, epreuve, within, found);
+      }
+    }
+    Value::Array(arr) => {
+      for it in arr {
+        collect_states(it, epreuve, within, found);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// Statut lu dans l'état JSON embarqué, avec la même priorité que le HTML (FULL, OPEN, CLOSED).
+/// Avec `epreuve`, seul l'objet de cette épreuve est lu (les statuts du niveau concours ne
+/// servent que si la page ne décrit pas l'épreuve).
+pub fn detect_status_from_states(states: &[Value], epreuve: Option<u32>) -> Status {
+  let mut found = Found::default();
+  for v in states {
+    collect_states(v, epreuve, None, &mut found);
+  }
+  let statuses = match epreuve {
+    None => &found.any,
+    Some(_) if found.target_seen => &found.target,
+    Some(_) => &found.page,
+  };
+  [Status::Full, Status::Open, Status::Closed]
+    .into_iter()
+    .find(|s| statuses.contains(s))
+    .unwrap_or(Status::Unknown)
+}
+
 /// Détecte le nombre de places restantes à partir d'un motif de type `52 / 60`
 /// (souvent affiché comme "engagés 52 / 60").
 /// Retourne `Some(slots_restants)` si on trouve un motif, sinon `None`.
@@ -99,3 +261,71 @@ fn read_int(bytes: &[u8], start: usize) -> Option<(i32, usize)> {
   }
   Some((val as i32, i))
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn states(v: Value) -> Vec<Value> {
+    vec![v]
+  }
+
+  #[test]
+  fn engagement_flags() {
+    assert_eq!(detect_status_from_states(&states(json!({"epreuve":{"engagementsOuverts":true}})), None), Status::Open);
+    assert_eq!(detect_status_from_states(&states(json!({"engagements_ouverts":false})), None), Status::Closed);
+    assert_eq!(detect_status_from_states(&states(json!({"complet":true,"engagementsOuverts":true})), None), Status::Full);
+    assert_eq!(detect_status_from_states(&states(json!({"etatEngagement":"COMPLET"})), None), Status::Full);
+    assert_eq!(detect_status_from_states(&states(json!({"statutEngagements":"Engagements fermés"})), None), Status::Closed);
+    assert_eq!(detect_status_from_states(&[], None), Status::Unknown);
+  }
+
+  #[test]
+  fn ui_flags_are_ignored() {
+    let ui = json!({"modal":{"open":false},"menu":{"isOpen":true},"accordion":{"ouvert":false},"cart":{"full":true},"status":"closed"});
+    assert_eq!(detect_status_from_states(&states(ui), None), Status::Unknown);
+
+    // L'état d'interface ne masque plus le texte du HTML
+    let html = r#"<p>Engagements ouverts</p><script id="__NEXT_DATA__" type="application/json">{"modal":{"open":false}}</script>"#;
+    assert_eq!(detect_status(html, None), Status::Open);
+  }
+
+  #[test]
+  fn generic_status_only_on_epreuve() {
+    let page = json!({"app":{"statut":"ouvert"},"epreuves":[{"num":2,"libelle":"Club 2","statut":"Complet"}]});
+    assert_eq!(detect_status_from_states(&states(page), None), Status::Full);
+  }
+
+  #[test]
+  fn watched_epreuve_only() {
+    let page = json!({"concours":{"numero":202635080,"epreuves":[
+      {"numEpreuve":1,"libelle":"Club 3","engagementsOuverts":true,"complet":true},
+      {"numEpreuve":2,"libelle":"Club 2","engagementsOuverts":false},
+      {"num":3,"libelle":"Club 1","statut":"Engagements ouverts"}
+    ]}});
+    let st = states(page);
+    assert_eq!(detect_status_from_states(&st, Some(1)), Status::Full);
+    assert_eq!(detect_status_from_states(&st, Some(2)), Status::Closed);
+    assert_eq!(detect_status_from_states(&st, Some(3)), Status::Open);
+    assert_eq!(detect_status_from_states(&st, None), Status::Full);
+  }
+
+  #[test]
+  fn watched_epreuve_falls_back_to_page_level() {
+    let page = json!({"concours":{"engagementsOuverts":true}});
+    assert_eq!(detect_status_from_states(&states(page), Some(4)), Status::Open);
+
+    // Épreuve décrite sans statut: le niveau concours ne l'emporte pas, on passe au HTML
+    let page = json!({"concours":{"engagementsOuverts":true,"epreuves":[{"numEpreuve":4,"libelle":"Pro 2"}]}});
+    assert_eq!(detect_status_from_states(&states(page), Some(4)), Status::Unknown);
+  }
+
+  #[test]
+  fn html_text() {
+    assert_eq!(detect_status_from_html("<td>COMPL&Egrave;TE</td>"), Status::Full);
+    assert_eq!(detect_status_from_html("<td>Engagements ouverts</td>"), Status::Open);
+    assert_eq!(detect_status_from_html("<td>Épreuve fermée</td>"), Status::Closed);
+    assert_eq!(detect_status_from_html("<a>Fermer</a>"), Status::Unknown);
+  }
+}
@@ -0,0 +1,151 @@
+// État JSON embarqué dans les pages hydratées côté client: blocs
+// `<script type="application/json">` (dont `__NEXT_DATA__`) et affectations
+// `window.__INITIAL_STATE__ = {...}` (ou équivalents Nuxt / Redux / Apollo).
+// Les données du tableau des épreuves et les statuts d'engagement y sont souvent
+// présents alors que le HTML rendu côté serveur est vide.
+
+use std::sync::OnceLock;
+use regex::Regex;
+use serde_json::{Map, Value};
+use crate::text;
+
+/// Variables globales d'état connues (`window.X = ...` ou `X = ...`).
+const STATE_GLOBALS: &[&str] = &[
+  "__INITIAL_STATE__",
+  "__PRELOADED_STATE__",
+  "__NUXT__",
+  "__APOLLO_STATE__",
+  "__DATA__",
+];
+
+fn script_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?is)<script\b([^>]*)>(.*?)</script\s*>").unwrap())
+}
+
+fn json_type_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r#"(?i)\btype\s*=\s*["']?application/(?:ld\+)?json\b|\bid\s*=\s*["']?__NEXT_DATA__\b"#).unwrap()
+  })
+}
+
+/// Première valeur JSON en tête de `s` (le reste du script est ignoré).
+fn leading_json(s: &str) -> Option<Value> {
+  let s = s.trim_start();
+  if !(s.starts_with('{') || s.starts_with('[') || s.starts_with('"')) {
+    return None;
+  }
+  let v = serde_json::Deserializer::from_str(s).into_iter::<Value>().next()?.ok()?;
+  match v {
+    // `JSON.parse("...")` / état sérialisé dans une chaîne
+    Value::String(inner) => serde_json::from_str::<Value>(&inner).ok().filter(|v| v.is_object() || v.is_array()),
+    v => Some(v),
+  }
+}
+
+/// Valeur affectée à une variable d'état dans un script inline.
+fn assigned_state(script: &str, global: &str) -> Option<Value> {
+  let mut from = 0;
+  while let Some(pos) = script[from..].find(global) {
+    let after = &script[from + pos + global.len()..];
+    from += pos + global.len();
+
+    // `X = ...` (pas `X ==`), avec guillemets éventuels: window["X"] = ...
+    let rest = after.trim_start_matches(['"', '\'', ']']).trim_start();
+    let Some(rest) = rest.strip_prefix('=') else { continue };
+    if rest.starts_with('=') {
+      continue;
+    }
+    let rest = rest.trim_start();
+    let rest = rest.strip_prefix("JSON.parse(").unwrap_or(rest);
+    if let Some(v) = leading_json(rest) {
+      return Some(v);
+    }
+  }
+  None
+}
+
+/// Tous les blobs JSON d'état trouvés dans la page (ordre du document).
+pub fn extract_json_states(html: &str) -> Vec<Value> {
+  let mut out = Vec::new();
+  for cap in script_re().captures_iter(html) {
+    let attrs = &cap[1];
+    let body = cap[2].trim();
+    if body.is_empty() {
+      continue;
+    }
+
+    if json_type_re().is_match(attrs) {
+      if let Some(v) = leading_json(body) {
+        out.push(v);
+      }
+      continue;
+    }
+
+    // Script exécutable: uniquement les affectations d'état connues
+    for global in STATE_GLOBALS {
+      if let Some(v) = assigned_state(body, global) {
+        out.push(v);
+      }
+    }
+  }
+  out
+}
+
+/// Premier champ numérique présent parmi `keys` (nombre ou chaîne numérique).
+pub fn get_u32(obj: &Map<String, Value>, keys: &[&str]) -> Option<u32> {
+  keys.iter().find_map(|k| match obj.get(*k)? {
+    Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+    Value::String(s) => s.trim().parse().ok(),
+    _ => None,
+  })
+}
+
+/// Premier champ texte non vide parmi `keys` (entités décodées, espaces regroupés).
+pub fn get_str(obj: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+  keys.iter().find_map(|k| {
+    let s = obj.get(*k)?.as_str()?;
+    let s = text::decode_entities(s).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!s.is_empty()).then_some(s)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  #[test]
+  fn json_script_blocks() {
+    let html = r#"<head>
+<script id="__NEXT_DATA__" type="application/json">{"props":{"epreuves":[{"num":1}]}}</script>
+<script type="application/ld+json">[{"@type":"Event"}]</script>
+<script type='application/json'>   </script>
+<script type="application/json">pas du json</script>
+</head>"#;
+    assert_eq!(extract_json_states(html), vec![json!({"props":{"epreuves":[{"num":1}]}}), json!([{"@type":"Event"}])]);
+  }
+
+  #[test]
+  fn state_assignments() {
+    let html = r#"<script>
+  window.__INITIAL_STATE__ = {"concours":{"numero":202635080}};
+  if (window.__PRELOADED_STATE__ == null) {}
+</script>
+<script>window["__NUXT__"]=JSON.parse("{\"a\":1}");</script>
+<script>__APOLLO_STATE__ = [1, 2]; console.log(__APOLLO_STATE__);</script>
+<script>var autre = {"x": 1};</script>"#;
+    assert_eq!(
+      extract_json_states(html),
+      vec![json!({"concours":{"numero":202635080}}), json!({"a":1}), json!([1, 2])]
+    );
+  }
+
+  #[test]
+  fn no_state() {
+    assert!(extract_json_states("<p>rien</p><script>track('x')</script>").is_empty());
+    // Chaîne JSON qui n'encode pas un objet / tableau
+    assert!(extract_json_states(r#"<script>window.__DATA__ = JSON.parse("\"abc\"");</script>"#).is_empty());
+  }
+}
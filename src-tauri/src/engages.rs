@@ -0,0 +1,374 @@
+// Liste des engagés d'une épreuve (cavalier, licence, cheval, club), publiée par ffecompet une fois
+// les engagements ouverts. Lue dans l'état JSON embarqué, sinon dans le tableau HTML (colonnes
+// repérées par leur en-tête). Sert à confirmer qu'un engagement a bien été pris en compte.
+
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::{embedded::{self, get_str}, epreuves, http, text};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Engage {
+  pub rider: String,
+  pub licence: Option<String>,
+  pub horse: String,
+  pub club: Option<String>,
+}
+
+impl Engage {
+  /// Notre couple figure dans la liste. Le cavalier est reconnu à son nom (sans accents ni casse,
+  /// dans n'importe quel ordre: "DUPONT Marie" = "Marie Dupont") ou à sa licence, saisie avec le
+  /// nom ("Marie Dupont 123456A") ou seule. Le nom seul ne suffit pas (homonymes, autre monture):
+  /// il faut aussi le cheval; sans cheval, seule la licence identifie l'engagement.
+  pub fn is(&self, cavalier: &str, cheval: Option<&str>) -> bool {
+    let words = |s: &str| {
+      text::normalize(s).split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_string).collect::<Vec<_>>()
+    };
+    let (licences, mut names): (Vec<String>, Vec<String>) = words(cavalier).into_iter().partition(|w| w.chars().any(|c| c.is_ascii_digit()));
+    let mut rider = words(&self.rider);
+    names.sort();
+    rider.sort();
+
+    let licence_ok = self.licence.as_deref().map(|l| words(l).concat()).is_some_and(|l| !l.is_empty() && licences.contains(&l));
+    let name_ok = !names.is_empty() && names == rider;
+    match cheval.map(str::trim).filter(|c| !c.is_empty()) {
+      Some(c) => (name_ok || licence_ok) && text::normalize(&self.horse) == text::normalize(c),
+      None => licence_ok,
+    }
+  }
+}
+
+/// Le contrôle des engagés peut reconnaître la cible: cavalier + cheval, ou licence du cavalier.
+pub fn check_error(cavalier: Option<&str>, cheval: Option<&str>) -> Option<String> {
+  let filled = |v: Option<&str>| v.is_some_and(|v| !v.trim().is_empty());
+  let licence = cavalier.is_some_and(|c| c.split_whitespace().any(|w| w.chars().any(|c| c.is_ascii_digit())));
+  let ok = licence || (filled(cavalier) && filled(cheval));
+  (!ok).then(|| "Contrôle des engagés: renseigne le cavalier et le cheval, ou la licence du cavalier.".to_string())
+}
+
+// ---------- État JSON embarqué ----------
+
+const RIDER_KEYS: &[&str] = &["cavalier", "nomCavalier", "cavalierNom", "rider"];
+const HORSE_KEYS: &[&str] = &["cheval", "nomCheval", "chevalNom", "poney", "horse"];
+const LICENCE_KEYS: &[&str] = &["licence", "numLicence", "numeroLicence", "licenceCavalier"];
+const CLUB_KEYS: &[&str] = &["club", "nomClub", "structure", "ecurie"];
+
+/// Champ texte, ou objet `{nom, prenom}` / `{nom}`.
+fn person(obj: &Map<String, Value>, keys: &[&str]) -> Option<String> {
+  get_str(obj, keys).or_else(|| {
+    keys.iter().find_map(|k| {
+      let o = obj.get(*k)?.as_object()?;
+      let name = get_str(o, &["nom", "name", "libelle"])?;
+      Some(match get_str(o, &["prenom", "firstName"]) {
+        Some(first) => format!("{name} {first}"),
+        None => name,
+      })
+    })
+  })
+}
+
+fn licence(obj: &Map<String, Value>) -> Option<String> {
+  let direct = |o: &Map<String, Value>| {
+    LICENCE_KEYS.iter().find_map(|k| match o.get(*k)? {
+      Value::Number(n) => Some(n.to_string()),
+      Value::String(s) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+      _ => None,
+    })
+  };
+  direct(obj).or_else(|| RIDER_KEYS.iter().find_map(|k| obj.get(*k)?.as_object().and_then(direct)))
+}
+
+fn walk_json(v: &Value, out: &mut Vec<Engage>) {
+  match v {
+    Value::Object(obj) => {
+      if let (Some(rider), Some(horse)) = (person(obj, RIDER_KEYS), person(obj, HORSE_KEYS)) {
+        push_unique(out, Engage { rider, licence: licence(obj), horse, club: person(obj, CLUB_KEYS) });
+        return;
+      }
+      for vv in obj.values() {
+        walk_json(vv, out);
+      }
+    }
+    Value::Array(arr) => {
+      for it in arr {
+        walk_json(it, out);
+      }
+    }
+    _ => {}
+  }
+}
+
+fn push_unique(out: &mut Vec<Engage>, e: Engage) {
+  if !out.iter().any(|x| x.rider == e.rider && x.horse == e.horse) {
+    out.push(e);
+  }
+}
+
+// ---------- Tableau HTML ----------
+
+fn table_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?is)<table\b.*?</table\s*>").unwrap())
+}
+
+fn tr_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?is)<tr\b.*?</tr\s*>").unwrap())
+}
+
+fn cell_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?is)<(t[hd])\b[^>]*>(.*?)</t[hd]\s*>").unwrap())
+}
+
+#[derive(Default)]
+struct Columns {
+  rider: Option<usize>,
+  licence: Option<usize>,
+  horse: Option<usize>,
+  club: Option<usize>,
+}
+
+fn columns(headers: &[String]) -> Columns {
+  let mut c = Columns::default();
+  for (i, h) in headers.iter().enumerate() {
+    let h = text::normalize(h);
+    let slot = if h.contains("licence") || h == "lic" || h == "n° lic" {
+      &mut c.licence
+    } else if h.contains("cheval") || h.contains("poney") || h.contains("monture") {
+      &mut c.horse
+    } else if h.contains("cavalier") || h.contains("engage") || h.contains("concurrent") {
+      &mut c.rider
+    } else if h.contains("club") || h.contains("structure") || h.contains("ecurie") {
+      &mut c.club
+    } else {
+      continue;
+    };
+    slot.get_or_insert(i);
+  }
+  c
+}
+
+fn cell_text(html: &str) -> String {
+  epreuves::html_to_text(html).split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn from_tables(html: &str) -> Vec<Engage> {
+  let mut out = Vec::new();
+  for table in table_re().find_iter(html) {
+    let mut cols: Option<Columns> = None;
+    for tr in tr_re().find_iter(table.as_str()) {
+      let cells: Vec<(bool, String)> = cell_re()
+        .captures_iter(tr.as_str())
+        .map(|c| (c[1].eq_ignore_ascii_case("th"), cell_text(&c[2])))
+        .collect();
+      if cells.is_empty() {
+        continue;
+      }
+      // Ligne d'en-tête: repère les colonnes
+      if cells.iter().all(|(th, _)| *th) {
+        let found = columns(&cells.iter().map(|(_, t)| t.clone()).collect::<Vec<_>>());
+        if found.rider.is_some() && found.horse.is_some() {
+          cols = Some(found);
+        }
+        continue;
+      }
+      let Some(c) = &cols else { continue };
+      let get = |i: Option<usize>| i.and_then(|i| cells.get(i)).map(|(_, t)| t.clone()).filter(|t| !t.is_empty());
+      if let (Some(rider), Some(horse)) = (get(c.rider), get(c.horse)) {
+        push_unique(&mut out, Engage { rider, licence: get(c.licence), horse, club: get(c.club) });
+      }
+    }
+  }
+  out
+}
+
+/// Engagés trouvés dans la page (état JSON, sinon tableau).
+pub fn parse(html: &str) -> Vec<Engage> {
+  let mut out = Vec::new();
+  for v in embedded::extract_json_states(html) {
+    walk_json(&v, &mut out);
+  }
+  if out.is_empty() {
+    out = from_tables(html);
+  }
+  out
+}
+
+/// La page affiche explicitement une liste vide.
+fn is_empty_list(html: &str) -> bool {
+  let n = text::normalize(&epreuves::html_to_text(html));
+  n.contains("aucun engage") || n.contains("pas d'engage") || n.contains("aucune inscription")
+}
+
+// ---------- Téléchargement ----------
+
+fn link_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r#"(?i)href\s*=\s*["']([^"']*engag[^"']*)["']"#).unwrap())
+}
+
+/// URLs possibles de la liste des engagés: liens de la page du concours qui visent l'épreuve,
+/// puis formes connues de ffecompet.
+fn candidates(contest_html: &str, contest_id: &str, num: u32) -> Vec<String> {
+  let base = epreuves::contest_url(contest_id);
+  let Ok(base_url) = reqwest::Url::parse(&base) else { return Vec::new() };
+  let num_re = Regex::new(&format!(r"(?i)(?:epreuves?[=/]|/){num}(?:\D|$)")).unwrap();
+
+  let mut out: Vec<String> = Vec::new();
+  for c in link_re().captures_iter(contest_html) {
+    let href = text::decode_entities(&c[1]);
+    if num_re.is_match(&href) {
+      if let Ok(u) = base_url.join(&href) {
+        out.push(u.to_string());
+      }
+    }
+  }
+  for guess in [format!("{base}/epreuve/{num}/engages"), format!("{base}/engages?epreuve={num}")] {
+    if !out.contains(&guess) {
+      out.push(guess);
+    }
+  }
+  out
+}
+
+/// Liste des engagés de l'épreuve `num` du concours.
+pub async fn fetch(client: &reqwest::Client, contest_id: &str, num: u32) -> Result<Vec<Engage>, String> {
+  let contest_id = contest_id.trim();
+  let base = epreuves::contest_url(contest_id);
+  let resp = client.get(&base).send().await.map_err(|e| format!("HTTP error: {e}"))?;
+  let status = resp.status();
+  let contest_html = http::read_text(resp).await.map_err(|e| format!("HTTP read error: {e:#}"))?;
+  if !status.is_success() {
+    return Err(format!("HTTP {status} sur {base}"));
+  }
+
+  for url in candidates(&contest_html, contest_id, num) {
+    let Ok(resp) = client.get(&url).send().await else { continue };
+    if !resp.status().is_success() {
+      continue;
+    }
+    let Ok(html) = http::read_text(resp).await else { continue };
+    let found = parse(&html);
+    if !found.is_empty() || is_empty_list(&html) {
+      return Ok(found);
+    }
+  }
+  Err(format!("Liste des engagés introuvable pour l'épreuve {num} du concours {contest_id} (engagements pas encore ouverts ?)"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TABLE_HTML: &str = include_str!("../tests/fixtures/engages_table.html");
+  const NEXT_DATA_HTML: &str = include_str!("../tests/fixtures/engages_next_data.html");
+
+  fn engage(rider: &str, licence: Option<&str>, horse: &str, club: Option<&str>) -> Engage {
+    Engage { rider: rider.into(), licence: licence.map(str::to_string), horse: horse.into(), club: club.map(str::to_string) }
+  }
+
+  #[test]
+  fn table_fixture() {
+    assert_eq!(parse(TABLE_HTML), vec![
+      engage("DUPONT Marie", Some("123456A"), "Quartz du Bois", Some("Centre Équestre de Paris")),
+      engage("MARTIN Paul", Some("654321B"), "Ulysse de l'Ile", None),
+      engage("LÉGER Hélène", Some("111222C"), "Vesta", Some("Ecurie du Val")),
+    ]);
+  }
+
+  #[test]
+  fn json_fixture_wins_over_table() {
+    assert_eq!(parse(NEXT_DATA_HTML), vec![
+      engage("DUPONT Marie", Some("123456A"), "Quartz du Bois", Some("Centre Équestre de Paris")),
+      engage("MARTIN Paul", Some("654321"), "Ulysse de l'Ile", None),
+      engage("LÉGER Hélène", None, "Vesta", Some("Ecurie du Val")),
+    ]);
+  }
+
+  #[test]
+  fn json_walker_shapes() {
+    let v: Value = serde_json::from_str(
+      r#"{"data":{"liste":[
+        {"rider":"Anne Petit","horse":"Alto","licenceCavalier":" 999888D "},
+        {"cavalierNom":{"name":"ROUX"},"chevalNom":"Bingo"},
+        {"cavalier":"Sans cheval"},
+        {"groupe":[{"cavalier":"Anne Petit","cheval":"Alto"}]}
+      ]}}"#,
+    )
+    .unwrap();
+    let mut out = Vec::new();
+    walk_json(&v, &mut out);
+    assert_eq!(out, vec![engage("Anne Petit", Some("999888D"), "Alto", None), engage("ROUX", None, "Bingo", None)]);
+  }
+
+  #[test]
+  fn header_columns() {
+    let h = |v: &[&str]| columns(&v.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    let c = h(&["Ordre", "N° Lic", "Cavalier", "Cheval / Poney", "Club"]);
+    assert_eq!((c.licence, c.rider, c.horse, c.club), (Some(1), Some(2), Some(3), Some(4)));
+    let c = h(&["Concurrent", "Monture", "Écurie", "Licence"]);
+    assert_eq!((c.rider, c.horse, c.club, c.licence), (Some(0), Some(1), Some(2), Some(3)));
+    // "Licence cavalier" est la licence, pas le cavalier; la première colonne reconnue l'emporte
+    let c = h(&["Licence cavalier", "Engagé", "Cavalier", "Poney"]);
+    assert_eq!((c.licence, c.rider, c.horse), (Some(0), Some(1), Some(3)));
+    let c = h(&["Date", "Lieu"]);
+    assert_eq!((c.rider, c.horse), (None, None));
+  }
+
+  #[test]
+  fn table_without_rider_and_horse_columns_is_ignored() {
+    let html = "<table><tr><th>Cavalier</th><th>Club</th></tr><tr><td>DUPONT Marie</td><td>CE Paris</td></tr></table>";
+    assert!(parse(html).is_empty());
+  }
+
+  #[test]
+  fn matching_requires_horse_or_licence() {
+    let e = engage("DUPONT Marie", Some("123456A"), "Quartz du Bois", None);
+    // Ordre des mots, casse, accents, espaces
+    assert!(e.is("Marie Dupont", Some("Quartz du Bois")));
+    assert!(e.is("  marie   DUPONT ", Some("QUARTZ DU BOIS")));
+    assert!(engage("LÉGER Hélène", None, "Vesta", None).is("Helene Leger", Some("vesta")));
+    // Autre cheval, autre cavalier, nom partiel
+    assert!(!e.is("Marie Dupont", Some("Vesta")));
+    assert!(!e.is("Paul Dupont", Some("Quartz du Bois")));
+    assert!(!e.is("Dupont", Some("Quartz du Bois")));
+    // Nom seul: insuffisant
+    assert!(!e.is("Marie Dupont", None));
+    assert!(!e.is("Marie Dupont", Some("  ")));
+    // Licence: suffit seule, ou avec le nom
+    assert!(e.is("123456A", None));
+    assert!(e.is("Marie Dupont 123456a", None));
+    assert!(e.is("123456A", Some("Quartz du Bois")));
+    assert!(!e.is("654321B", None));
+    assert!(!e.is("123456A", Some("Vesta")));
+    assert!(!engage("DUPONT Marie", None, "Quartz du Bois", None).is("123456A", None));
+    assert!(!e.is("", Some("Quartz du Bois")));
+  }
+
+  #[test]
+  fn check_needs_horse_or_licence() {
+    assert_eq!(check_error(Some("Marie Dupont"), Some("Quartz du Bois")), None);
+    assert_eq!(check_error(Some("123456A"), None), None);
+    assert_eq!(check_error(Some("Marie Dupont 123456A"), Some(" ")), None);
+    assert!(check_error(Some("Marie Dupont"), None).is_some());
+    assert!(check_error(Some("Marie Dupont"), Some("  ")).is_some());
+    assert!(check_error(None, Some("Quartz du Bois")).is_some());
+  }
+
+  #[test]
+  fn empty_list_and_candidates() {
+    assert!(is_empty_list("<p>Aucun engagé pour le moment.</p>"));
+    assert!(!is_empty_list(TABLE_HTML));
+
+    let contest = r#"<a href="/concours/202635080/epreuve/2/engages">Engagés</a>
+      <a href="/concours/202635080/epreuve/12/engages">Engagés</a>
+      <a href="/engagements?concours=202635080&amp;epreuve=2">Liste</a>"#;
+    assert_eq!(candidates(contest, "202635080", 2), vec![
+      "https://ffecompet.ffe.com/concours/202635080/epreuve/2/engages".to_string(),
+      "https://ffecompet.ffe.com/engagements?concours=202635080&epreuve=2".to_string(),
+      "https://ffecompet.ffe.com/concours/202635080/engages?epreuve=2".to_string(),
+    ]);
+  }
+}
@@ -1,38 +1,767 @@
+// Épreuves d'un concours ffecompet. La page concours est lue en HTTP (les pages FFE n'ont pas
+// accès à `invoke`), puis on essaie dans l'ordre:
+//   1) l'état JSON embarqué (page hydratée: __NEXT_DATA__, window.__INITIAL_STATE__, ...)
+//   2) une URL d'API JSON repérée dans le HTML (requête supplémentaire)
+//   3) le texte visible du tableau ("1 SO Amateur 2 Grand Prix (1,15 m), ... 52 / 60")
+// Disciplines reconnues: CSO (SO), dressage (DR), complet (CC), hunter (HU) et pony-games (PG).
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::{conditional, embedded::{self, get_str, get_u32}, http, label::{self, EpreuveLabel, Level, Series, Table}, text};
+
+pub const FFECOMPET: &str = "https://ffecompet.ffe.com";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Discipline {
+  So,
+  Dr,
+  Cc,
+  Hu,
+  Pg,
+}
+
+impl Discipline {
+  pub const ALL: [Discipline; 5] = [Discipline::So, Discipline::Dr, Discipline::Cc, Discipline::Hu, Discipline::Pg];
+
+  /// Code FFE affiché dans le tableau des épreuves
+  pub fn code(self) -> &'static str {
+    match self {
+      Discipline::So => "SO",
+      Discipline::Dr => "DR",
+      Discipline::Cc => "CC",
+      Discipline::Hu => "HU",
+      Discipline::Pg => "PG",
+    }
+  }
+
+  /// Code ("DR") ou nom ("Dressage", "Concours complet", "Pony-Games", ...).
+  pub fn parse(s: &str) -> Option<Self> {
+    let n = text::normalize(s);
+    let n = n.trim();
+    Discipline::ALL.into_iter().find(|d| n.eq_ignore_ascii_case(d.code())).or(match n {
+      "cso" | "saut d'obstacles" | "saut d'obstacle" | "obstacle" => Some(Discipline::So),
+      "dressage" => Some(Discipline::Dr),
+      "cce" | "complet" | "concours complet" | "concours complet d'equitation" => Some(Discipline::Cc),
+      "hunter" => Some(Discipline::Hu),
+      "pony games" | "pony-games" | "ponygames" => Some(Discipline::Pg),
+      _ => None,
+    })
+  }
+
+  /// Hauteur d'obstacles significative dans le libellé
+  fn has_heights(self) -> bool {
+    matches!(self, Discipline::So | Discipline::Cc | Discipline::Hu)
+  }
+}
 
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
+/// Lien brut remonté par un scraping côté WebView (debug).
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Epreuve {
   pub label: String,
   pub url: String,
 }
 
-// Parsing simple: on cherche des liens vers des pages d’épreuves dans le HTML.
-#[allow(dead_code)]
-pub fn parse_epreuves(html: &str) -> Vec<Epreuve> {
-  // capture href + texte du lien
-  // Exemple: <a href="/epreuve/....">Épreuve 110cm ...</a>
-  let re = Regex::new(r#"<a[^>]+href="([^"]+)"[^>]*>([^<]+)</a>"#).unwrap();
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpreuveDetail {
+  pub num: u32,
+  /// Code discipline FFE ("SO" = saut d'obstacles), si connu
+  pub discipline: Option<String>,
+  /// Libellé tel qu'affiché par la FFE (ex: "Amateur 2 Grand Prix (1,15 m)")
+  pub label: String,
+  pub height_cm: Option<u32>,
+  /// Série + niveau lus dans le libellé (ex: "Amateur 2")
+  pub category: Option<String>,
+  /// Niveau propre à la discipline: reprise de dressage ("Préliminaire", "Grand Prix"),
+  /// format de complet ("CCI2*-S", sinon la catégorie)
+  pub level: Option<String>,
+  pub engaged: Option<u32>,
+  pub max: Option<u32>,
+  /// URL surveillable, "taguée" ?watch_epreuve=N
+  pub url: String,
+  /// Libellé décomposé (série, niveau, barème, hauteur)
+  pub parsed: EpreuveLabel,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EpreuveSort {
+  /// Ordre du programme
+  #[default]
+  Num,
+  /// Hauteur croissante (sans hauteur en dernier)
+  Height,
+  /// Difficulté croissante: série, niveau, hauteur
+  Level,
+}
+
+fn any_of<T: PartialEq>(wanted: &[T], v: Option<T>) -> bool {
+  wanted.is_empty() || v.is_some_and(|v| wanted.contains(&v))
+}
+
+/// Critères appliqués au libellé décomposé (listes vides = pas de filtre).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EpreuveFilter {
+  pub min_height_cm: Option<u32>,
+  pub max_height_cm: Option<u32>,
+  pub series: Vec<Series>,
+  pub levels: Vec<Level>,
+  pub tables: Vec<Table>,
+  pub sort: EpreuveSort,
+}
+
+impl EpreuveFilter {
+  pub fn matches(&self, e: &EpreuveDetail) -> bool {
+    // Une borne de hauteur exclut les épreuves sans hauteur connue
+    let height_ok = match (self.min_height_cm, self.max_height_cm) {
+      (None, None) => true,
+      (min, max) => e.height_cm.is_some_and(|h| min.is_none_or(|m| h >= m) && max.is_none_or(|m| h <= m)),
+    };
+    height_ok
+      && any_of(&self.series, e.parsed.series)
+      && any_of(&self.levels, e.parsed.level)
+      && any_of(&self.tables, e.parsed.table)
+  }
+
+  /// Filtre puis trie la liste.
+  pub fn apply(&self, list: Vec<EpreuveDetail>) -> Vec<EpreuveDetail> {
+    let mut out: Vec<EpreuveDetail> = list.into_iter().filter(|e| self.matches(e)).collect();
+    match self.sort {
+      EpreuveSort::Num => out.sort_by_key(|e| e.num),
+      EpreuveSort::Height => out.sort_by_key(|e| (e.height_cm.is_none(), e.height_cm, e.num)),
+      EpreuveSort::Level => out.sort_by_key(|e| (e.parsed.difficulty(), e.num)),
+    }
+    out
+  }
+}
+
+/// Ligne extraite avant calcul des champs dérivés (hauteur, catégorie, URL).
+#[derive(Debug, Clone, Default)]
+struct Row {
+  num: u32,
+  discipline: Option<String>,
+  label: String,
+  engaged: Option<u32>,
+  max: Option<u32>,
+}
+
+pub fn contest_url(contest_id: &str) -> String {
+  format!("{FFECOMPET}/concours/{contest_id}")
+}
+
+pub fn epreuve_url(contest_id: &str, num: u32) -> String {
+  format!("{}?watch_epreuve={num}", contest_url(contest_id))
+}
+
+fn finish(contest_id: &str, rows: Vec<Row>) -> Vec<EpreuveDetail> {
+  // Une épreuve par numéro: la première occurrence gagne, complétée par les suivantes
+  let mut by_num: BTreeMap<u32, Row> = BTreeMap::new();
+  for row in rows {
+    let entry = by_num.entry(row.num).or_insert_with(|| row.clone());
+    entry.discipline = entry.discipline.take().or(row.discipline);
+    entry.engaged = entry.engaged.or(row.engaged);
+    entry.max = entry.max.or(row.max);
+  }
+
+  by_num
+    .into_values()
+    .map(|r| {
+      let discipline = r.discipline.as_deref().and_then(Discipline::parse);
+      let mut parsed = label::parse(&r.label);
+      // Pas de hauteur en dressage / pony-games, même si le libellé contient un nombre
+      if !discipline.is_none_or(Discipline::has_heights) {
+        parsed.height_cm = None;
+      }
+      EpreuveDetail {
+        height_cm: parsed.height_cm,
+        category: parsed.category(),
+        level: discipline.and_then(|d| level_from_label(d, &parsed, &r.label)),
+        url: epreuve_url(contest_id, r.num),
+        num: r.num,
+        // Code FFE normalisé quand la discipline est reconnue
+        discipline: discipline.map(|d| d.code().to_string()).or(r.discipline),
+        label: r.label,
+        engaged: r.engaged,
+        max: r.max,
+        parsed,
+      }
+    })
+    .collect()
+}
+
+fn dressage_test_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| {
+    Regex::new(r"(?i)\b(pr[ée]liminaire|pr[ée]paratoire|grand prix(?: libre| sp[ée]ciale)?|impos[ée]e(?: [a-d1-9]\b)?|libre|k[üu]r|saint[- ]georges|interm[ée]diaire(?: (?:ii|i|1|2|a|b)\b)?|[ée]quipes?)").unwrap()
+  })
+}
+
+fn eventing_format_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  RE.get_or_init(|| Regex::new(r"(?i)\bcc([ie])\s?([1-5])\s?\*(?:\s?-\s?(intro|s|l)\b)?").unwrap())
+}
+
+/// Niveau selon la discipline: nom de reprise (DR), format international ou catégorie (CC),
+/// catégorie ailleurs.
+fn level_from_label(discipline: Discipline, parsed: &EpreuveLabel, label: &str) -> Option<String> {
+  match discipline {
+    Discipline::Dr => dressage_test_re().captures(label).map(|c| c[1].to_string()),
+    Discipline::Cc => eventing_format_re()
+      .captures(label)
+      .map(|c| {
+        let suffix = match c.get(3).map(|m| m.as_str().to_lowercase()) {
+          Some(l) if l == "intro" => "-Intro".to_string(),
+          Some(l) => format!("-{}", l.to_uppercase()),
+          None => String::new(),
+        };
+        format!("CC{}{}*{suffix}", c[1].to_uppercase(), &c[2])
+      })
+      .or_else(|| parsed.category()),
+    Discipline::So | Discipline::Hu | Discipline::Pg => parsed.category(),
+  }
+}
+
+/// Garde les épreuves des disciplines demandées (liste vide = toutes). Une épreuve dont la
+/// discipline n'est pas indiquée est conservée.
+pub fn keep_disciplines(list: Vec<EpreuveDetail>, wanted: &[Discipline]) -> Vec<EpreuveDetail> {
+  if wanted.is_empty() {
+    return list;
+  }
+  list
+    .into_iter()
+    .filter(|e| match e.discipline.as_deref() {
+      None => true,
+      Some(d) => Discipline::parse(d).is_some_and(|d| wanted.contains(&d)),
+    })
+    .collect()
+}
+
+// ---------- Différences entre deux lectures du programme ----------
+
+/// Changement d'une épreuve entre deux lectures du concours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpreuveChange {
+  Added { num: u32, label: String },
+  Removed { num: u32, label: String },
+  /// `changes`: "hauteur 110 cm → 115 cm", "places 60 → 80", ...
+  Modified { num: u32, label: String, changes: Vec<String> },
+}
+
+impl EpreuveChange {
+  pub fn describe(&self) -> String {
+    match self {
+      EpreuveChange::Added { num, label } => format!("+ Epreuve {num} ajoutée : {label}"),
+      EpreuveChange::Removed { num, label } => format!("- Epreuve {num} supprimée : {label}"),
+      EpreuveChange::Modified { num, label, changes } => format!("~ Epreuve {num} ({label}) : {}", changes.join(", ")),
+    }
+  }
+}
 
+fn show<T: std::fmt::Display>(v: Option<T>, unit: &str) -> String {
+  v.map_or_else(|| "?".to_string(), |v| format!("{v}{unit}"))
+}
+
+/// Épreuves ajoutées, supprimées, ou dont la hauteur, le nombre de places ou le libellé a changé
+/// (le nombre d'engagés bouge en continu et n'est pas comparé).
+pub fn diff(old: &[EpreuveDetail], new: &[EpreuveDetail]) -> Vec<EpreuveChange> {
   let mut out = Vec::new();
+  for o in old {
+    if !new.iter().any(|n| n.num == o.num) {
+      out.push(EpreuveChange::Removed { num: o.num, label: o.label.clone() });
+    }
+  }
+  for n in new {
+    let Some(o) = old.iter().find(|o| o.num == n.num) else {
+      out.push(EpreuveChange::Added { num: n.num, label: n.label.clone() });
+      continue;
+    };
+    let mut changes = Vec::new();
+    if o.height_cm != n.height_cm {
+      changes.push(format!("hauteur {} → {}", show(o.height_cm, " cm"), show(n.height_cm, " cm")));
+    }
+    if o.max != n.max {
+      changes.push(format!("places {} → {}", show(o.max, ""), show(n.max, "")));
+    }
+    if text::normalize(&o.label) != text::normalize(&n.label) {
+      changes.push(format!("libellé « {} » → « {} »", o.label, n.label));
+    }
+    if !changes.is_empty() {
+      out.push(EpreuveChange::Modified { num: n.num, label: n.label.clone(), changes });
+    }
+  }
+  out.sort_by_key(|c| match c {
+    EpreuveChange::Added { num, .. } | EpreuveChange::Removed { num, .. } | EpreuveChange::Modified { num, .. } => *num,
+  });
+  out
+}
 
-  for cap in re.captures_iter(html) {
-    let href = cap.get(1).unwrap().as_str().trim().to_string();
-    let text = cap.get(2).unwrap().as_str().trim().to_string();
+// ---------- JSON (état embarqué ou API) ----------
+
+fn walk_json(v: &Value, out: &mut Vec<Row>) {
+  match v {
+    Value::Object(obj) => {
+      // Un objet qui contient la liste des épreuves est le concours lui-même (numero = n° concours)
+      let is_container = obj.contains_key("epreuves");
+      let num = get_u32(obj, &["numEpreuve", "numeroEpreuve", "numero", "num", "no", "ordre"]).filter(|n| (1..1000).contains(n));
+      let label = get_str(obj, &["libelle", "label", "nom", "intitule", "designation"]);
+      if let (false, Some(num), Some(label)) = (is_container, num, label) {
+        out.push(Row {
+          num,
+          discipline: get_str(obj, &["discipline", "codeDiscipline", "disc"]),
+          label,
+          engaged: get_u32(obj, &["nbEngages", "engages", "engaged", "nbEngage", "nbPartants", "participants"]),
+          max: get_u32(obj, &["nbMax", "maxEngages", "nbMaxEngages", "max", "capacite"]),
+        });
+      }
+      for vv in obj.values() {
+        walk_json(vv, out);
+      }
+    }
+    Value::Array(arr) => {
+      for it in arr {
+        walk_json(it, out);
+      }
+    }
+    _ => {}
+  }
+}
 
-    // Filtre : adapte si besoin selon les URL réelles FFE
-    if href.contains("epreuve") || href.contains("epreuves") {
-      let full = if href.starts_with("http") {
-        href
+/// Épreuves trouvées dans un document JSON quelconque (parcours récursif).
+pub fn from_json(contest_id: &str, v: &Value) -> Vec<EpreuveDetail> {
+  let mut rows = Vec::new();
+  walk_json(v, &mut rows);
+  finish(contest_id, rows)
+}
+
+/// Épreuves de l'état JSON embarqué dans la page.
+pub fn from_embedded_state(contest_id: &str, html: &str) -> Vec<EpreuveDetail> {
+  let mut rows = Vec::new();
+  for v in embedded::extract_json_states(html) {
+    walk_json(&v, &mut rows);
+  }
+  finish(contest_id, rows)
+}
+
+// ---------- Texte visible ----------
+
+/// HTML -> texte, avec un saut de ligne par cellule / ligne de tableau / bloc.
+pub fn html_to_text(html: &str) -> String {
+  fn is_nl_trigger(tag: &str) -> bool {
+    matches!(
+      tag.trim_start_matches('/'),
+      "br" | "p" | "tr" | "td" | "th" | "li" | "div" | "section" | "article" | "h1" | "h2" | "h3"
+    )
+  }
+
+  let mut out = String::with_capacity(html.len().min(200_000));
+  let mut tag_buf = String::new();
+  let mut in_tag = false;
+  let mut prev_was_space = false;
+
+  for ch in html.chars() {
+    if ch == '<' {
+      in_tag = true;
+      tag_buf.clear();
+      continue;
+    }
+    if in_tag {
+      if ch == '>' {
+        in_tag = false;
+        let t = tag_buf.trim().trim_start_matches('!').split_whitespace().next().unwrap_or("").to_ascii_lowercase();
+        if is_nl_trigger(&t) {
+          if !out.ends_with('\n') {
+            out.push('\n');
+          }
+          prev_was_space = false;
+        }
       } else {
-        format!("https://ffecompet.ffe.com{}", href)
-      };
+        tag_buf.push(ch);
+      }
+      continue;
+    }
 
-      // évite les doublons
-      if !out.iter().any(|e: &Epreuve| e.url == full) {
-        out.push(Epreuve { label: text, url: full });
+    if ch == '\n' || ch == '\r' {
+      if !out.ends_with('\n') {
+        out.push('\n');
+      }
+      prev_was_space = false;
+    } else if ch.is_whitespace() {
+      if !prev_was_space && !out.ends_with('\n') {
+        out.push(' ');
+        prev_was_space = true;
       }
+    } else {
+      out.push(ch);
+      prev_was_space = false;
     }
   }
 
+  // Entités décodées après le découpage des balises (`&lt;` ne doit pas ouvrir de balise)
+  text::decode_entities(&out).replace('\u{00A0}', " ")
+}
+
+/// Début d'épreuve dans le flux de tokens: `<num> <code discipline>` (ex: "1 SO", "4 DR").
+fn is_start(tokens: &[&str], i: usize) -> bool {
+  tokens[i].parse::<u32>().is_ok()
+    && tokens.get(i + 1).is_some_and(|t| Discipline::ALL.iter().any(|d| d.code() == *t))
+}
+
+/// Motif `X / Y` à la position i.
+fn ratio_at(tokens: &[&str], i: usize) -> Option<(u32, u32)> {
+  match (tokens.get(i), tokens.get(i + 1), tokens.get(i + 2)) {
+    (Some(x), Some(&"/"), Some(y)) => Some((x.parse().ok()?, y.parse().ok()?)),
+    _ => None,
+  }
+}
+
+/// Parsing par tokens: le tableau peut être éclaté en cellules ("1" puis "SO" sur la ligne
+/// suivante, etc.). Bloc = [num][discipline][libellé ... ,][... X / Y ...].
+pub fn from_text(contest_id: &str, text: &str) -> Vec<EpreuveDetail> {
+  let tokens: Vec<&str> = text.split_whitespace().collect();
+  let mut rows = Vec::new();
+  let mut i = 0;
+
+  while i < tokens.len() {
+    if !is_start(&tokens, i) {
+      i += 1;
+      continue;
+    }
+    let num: u32 = tokens[i].parse().unwrap_or_default();
+    let discipline = tokens[i + 1].to_string();
+    i += 2;
+
+    // Libellé: jusqu'à la virgule finale, un ratio ou la prochaine épreuve
+    let mut label_parts: Vec<&str> = Vec::new();
+    while i < tokens.len() && !is_start(&tokens, i) && ratio_at(&tokens, i).is_none() {
+      let t = tokens[i];
+      i += 1;
+      if t == "," {
+        break;
+      }
+      if let Some(t) = t.strip_suffix(',') {
+        label_parts.push(t);
+        break;
+      }
+      label_parts.push(t);
+    }
+
+    // Ratio engagés / max avant la prochaine épreuve
+    let mut ratio = None;
+    while i < tokens.len() && !is_start(&tokens, i) {
+      if let Some(r) = ratio_at(&tokens, i) {
+        ratio = Some(r);
+        i += 3;
+        break;
+      }
+      i += 1;
+    }
+
+    let label = label_parts.join(" ");
+    if !label.is_empty() {
+      rows.push(Row {
+        num,
+        discipline: Some(discipline),
+        label,
+        engaged: ratio.map(|r| r.0),
+        max: ratio.map(|r| r.1),
+      });
+    }
+  }
+
+  finish(contest_id, rows)
+}
+
+// ---------- Page complète ----------
+
+/// URLs absolues présentes dans le HTML (attributs, scripts).
+pub fn extract_urls(html: &str) -> Vec<String> {
+  let mut out: Vec<String> = Vec::new();
+  for (start, _) in html.match_indices("http") {
+    let rest = &html[start..];
+    if !(rest.starts_with("https://") || rest.starts_with("http://")) {
+      continue;
+    }
+    let end = rest
+      .find(|c: char| c == '"' || c == '\'' || c == '<' || c == '>' || c.is_whitespace())
+      .unwrap_or(rest.len());
+    // Les URLs d'attributs HTML sont encodées (`&amp;` entre paramètres)
+    let url = text::decode_entities(&rest[..end]);
+    if !out.contains(&url) {
+      out.push(url);
+    }
+  }
   out
 }
+
+/// URLs qui ressemblent à une API JSON du concours.
+fn api_candidates(html: &str, contest_id: &str) -> Vec<String> {
+  extract_urls(html)
+    .into_iter()
+    .filter(|u| {
+      let lu = u.to_lowercase();
+      (lu.contains("api") || lu.contains("json") || lu.contains("data")) && lu.contains(contest_id)
+    })
+    .collect()
+}
+
+/// Épreuves trouvées par l'API + empreinte de la réponse JSON.
+async fn try_api(client: &reqwest::Client, contest_id: &str, html: &str) -> Option<(Vec<EpreuveDetail>, String)> {
+  for url in api_candidates(html, contest_id) {
+    let Ok(resp) = client.get(&url).send().await else { continue };
+    let is_json_ct = resp
+      .headers()
+      .get(reqwest::header::CONTENT_TYPE)
+      .and_then(|v| v.to_str().ok())
+      .is_some_and(|ct| ct.contains("json"));
+    let Ok(body) = http::read_text(resp).await else { continue };
+
+    let trimmed = body.trim_start();
+    if !(is_json_ct || trimmed.starts_with('{') || trimmed.starts_with('[')) {
+      continue;
+    }
+    let Ok(v) = serde_json::from_str::<Value>(&body) else { continue };
+    let items = from_json(contest_id, &v);
+    if !items.is_empty() {
+      return Some((items, conditional::hash_bytes(body.bytes())));
+    }
+  }
+  None
+}
+
+/// Charge la page du concours et en extrait les épreuves, avec l'empreinte du contenu
+/// brut d'où elles viennent (page ou réponse de l'API).
+pub async fn fetch(client: &reqwest::Client, contest_id: &str) -> Result<(Vec<EpreuveDetail>, String), String> {
+  let contest_id = contest_id.trim();
+  if contest_id.is_empty() {
+    return Err("N° concours manquant".into());
+  }
+  let base_url = contest_url(contest_id);
+
+  let resp = client.get(&base_url).send().await.map_err(|e| format!("HTTP error: {e}"))?;
+  let status = resp.status();
+  let body = http::read_text(resp).await.map_err(|e| format!("HTTP read error: {e:#}"))?;
+  if !status.is_success() {
+    let snippet = body.chars().take(240).collect::<String>();
+    return Err(format!("HTTP {status} sur {base_url} — extrait: {snippet}"));
+  }
+
+  let from_state = from_embedded_state(contest_id, &body);
+  if !from_state.is_empty() {
+    return Ok((from_state, conditional::fingerprint(&body)));
+  }
+  if let Some(found) = try_api(client, contest_id, &body).await {
+    return Ok(found);
+  }
+
+  let text = html_to_text(&body);
+  let items = from_text(contest_id, &text);
+  if !items.is_empty() {
+    return Ok((items, conditional::fingerprint(&body)));
+  }
+
+  // Debug utile: quelques lignes contenant un code discipline pour comprendre le format réel
+  let mut samples: Vec<String> = text
+    .lines()
+    .map(str::trim)
+    .filter(|l| Discipline::ALL.iter().any(|d| l.contains(d.code())))
+    .take(8)
+    .map(|l| l.chars().take(220).collect())
+    .collect();
+  if samples.is_empty() {
+    samples.push(format!("urls_detected={}", extract_urls(&body).len()));
+  }
+  Err(format!("0 épreuve détectée (scrape Rust) — url={base_url} — samples={samples:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const TABLE_HTML: &str = include_str!("../tests/fixtures/concours_table.html");
+  const SPLIT_CELLS_HTML: &str = include_str!("../tests/fixtures/concours_split_cells.html");
+  const NEXT_DATA_HTML: &str = include_str!("../tests/fixtures/concours_next_data.html");
+  const MULTI_HTML: &str = include_str!("../tests/fixtures/concours_multi_disciplines.html");
+  const API_JSON: &str = include_str!("../tests/fixtures/api_epreuves.json");
+  const ID: &str = "202635080";
+
+  /// Même ordre que `fetch`, sans la tentative d'API.
+  fn parse_contest_page(contest_id: &str, html: &str) -> Vec<EpreuveDetail> {
+    let from_state = from_embedded_state(contest_id, html);
+    if !from_state.is_empty() {
+      return from_state;
+    }
+    from_text(contest_id, &html_to_text(html))
+  }
+
+  #[test]
+  fn table_fixture() {
+    let eps = parse_contest_page(ID, TABLE_HTML);
+    assert_eq!(eps.iter().map(|e| e.num).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+
+    let e = &eps[1];
+    assert_eq!(e.discipline.as_deref(), Some("SO"));
+    assert_eq!(e.label, "Amateur 2 Grand Prix (1,15 m)");
+    assert_eq!(e.height_cm, Some(115));
+    assert_eq!(e.category.as_deref(), Some("Amateur 2"));
+    assert_eq!((e.engaged, e.max), (Some(60), Some(60)));
+    assert_eq!(e.url, "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=2");
+
+    // Entités décodées dans le libellé
+    assert_eq!(eps[2].label, "Club Élite Spéciale (1,05 m)");
+    assert_eq!(eps[2].category.as_deref(), Some("Club Elite"));
+    assert_eq!(eps[2].parsed.table, Some(Table::Speciale));
+    // Pas de ratio affiché
+    assert_eq!((eps[3].engaged, eps[3].max), (None, None));
+  }
+
+  #[test]
+  fn split_cells_fixture() {
+    let eps = parse_contest_page(ID, SPLIT_CELLS_HTML);
+    assert_eq!(eps.len(), 3);
+    assert_eq!(eps[0].label, "Club 2 Vitesse (0,95 m)");
+    assert_eq!((eps[0].engaged, eps[0].max), (Some(12), Some(40)));
+    // Libellé sans virgule finale: arrêté au ratio
+    assert_eq!(eps[2].label, "Pro 1 Grand Prix (1,45 m)");
+    assert_eq!((eps[2].engaged, eps[2].max), (Some(25), Some(30)));
+  }
+
+  #[test]
+  fn next_data_fixture() {
+    let eps = parse_contest_page(ID, NEXT_DATA_HTML);
+    // Doublons fusionnés entre __NEXT_DATA__ et __INITIAL_STATE__
+    assert_eq!(eps.iter().map(|e| e.num).collect::<Vec<_>>(), vec![1, 2, 3]);
+    assert_eq!(eps[0].label, "Poney 1 Grand Prix (1,00 m)");
+    assert_eq!((eps[0].engaged, eps[0].max), (Some(30), Some(45)));
+    assert_eq!(eps[1].height_cm, Some(110));
+    assert_eq!(eps[1].max, Some(80));
+    assert_eq!(eps[2].discipline.as_deref(), Some("DR"));
+    assert_eq!(eps[2].level.as_deref(), Some("Grand Prix"));
+
+    let so = keep_disciplines(eps, &[Discipline::So]);
+    assert_eq!(so.iter().map(|e| e.num).collect::<Vec<_>>(), vec![1, 2]);
+  }
+
+  #[test]
+  fn multi_disciplines_fixture() {
+    let eps = parse_contest_page("202641001", MULTI_HTML);
+    let codes: Vec<_> = eps.iter().map(|e| e.discipline.as_deref().unwrap_or("")).collect();
+    assert_eq!(codes, vec!["SO", "DR", "DR", "CC", "CC", "HU", "PG"]);
+
+    assert_eq!(eps[1].level.as_deref(), Some("Préliminaire"));
+    // Pas de hauteur en dressage, même avec un nombre entre parenthèses
+    assert_eq!(eps[2].height_cm, None);
+    assert_eq!(eps[3].level.as_deref(), Some("Amateur 3"));
+    assert_eq!(eps[4].level.as_deref(), Some("CCI2*-S"));
+    assert_eq!(eps[4].height_cm, Some(110));
+    assert_eq!(eps[5].height_cm, Some(85));
+    assert_eq!(eps[6].level.as_deref(), Some("Poney 2"));
+    assert_eq!((eps[6].engaged, eps[6].max), (Some(6), Some(12)));
+
+    let dr_cc = keep_disciplines(eps, &[Discipline::Dr, Discipline::Cc]);
+    assert_eq!(dr_cc.iter().map(|e| e.num).collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+  }
+
+  #[test]
+  fn discipline_names() {
+    assert_eq!(Discipline::parse("so"), Some(Discipline::So));
+    assert_eq!(Discipline::parse("Concours complet"), Some(Discipline::Cc));
+    assert_eq!(Discipline::parse("Pony-Games"), Some(Discipline::Pg));
+    assert_eq!(Discipline::parse("Endurance"), None);
+  }
+
+  #[test]
+  fn api_fixture() {
+    let v: Value = serde_json::from_str(API_JSON).unwrap();
+    let eps = from_json(ID, &v);
+    assert_eq!(eps.len(), 3);
+    assert_eq!(eps[0].engaged, Some(18));
+    assert_eq!(eps[2].category.as_deref(), Some("Amateur Elite"));
+    assert_eq!(eps[2].height_cm, Some(125));
+  }
+
+  #[test]
+  fn filter_and_sort() {
+    let eps = parse_contest_page(ID, TABLE_HTML);
+    let by_height = EpreuveFilter { min_height_cm: Some(100), sort: EpreuveSort::Height, ..Default::default() };
+    assert_eq!(by_height.apply(eps.clone()).iter().map(|e| e.num).collect::<Vec<_>>(), vec![3, 2, 4]);
+
+    let amateur = EpreuveFilter { series: vec![Series::Amateur], sort: EpreuveSort::Level, ..Default::default() };
+    assert_eq!(amateur.apply(eps.clone()).iter().map(|e| e.num).collect::<Vec<_>>(), vec![2, 4]);
+
+    let gp_under_115 = EpreuveFilter { max_height_cm: Some(115), tables: vec![Table::GrandPrix], ..Default::default() };
+    assert_eq!(gp_under_115.apply(eps).iter().map(|e| e.num).collect::<Vec<_>>(), vec![2]);
+  }
+
+  #[test]
+  fn urls() {
+    assert_eq!(
+      extract_urls(r#"<a href="https://ffecompet.ffe.com/api/concours/1?a=1&amp;b=2">x</a>"#),
+      vec!["https://ffecompet.ffe.com/api/concours/1?a=1&b=2".to_string()]
+    );
+  }
+
+  #[test]
+  fn diff_detects_program_changes() {
+    let old = parse_contest_page(ID, TABLE_HTML);
+    assert!(diff(&old, &old).is_empty());
+
+    let mut new = old.clone();
+    // Supprimée: 1; hauteur: 2; places: 3; libellé: 4; ajoutée: 5
+    new.retain(|e| e.num != 1);
+    new[0].height_cm = Some(120);
+    new[1].max = Some(80);
+    new[2].label = "Club 1 Grand Prix (1,00 m)".into();
+    let mut added = new[0].clone();
+    added.num = 5;
+    added.label = "Amateur 1 Vitesse (1,20 m)".into();
+    new.push(added);
+
+    let changes = diff(&old, &new);
+    assert_eq!(changes, vec![
+      EpreuveChange::Removed { num: 1, label: old[0].label.clone() },
+      EpreuveChange::Modified { num: 2, label: old[1].label.clone(), changes: vec!["hauteur 115 cm → 120 cm".into()] },
+      EpreuveChange::Modified { num: 3, label: old[2].label.clone(), changes: vec![format!("places {} → 80", show(old[2].max, ""))] },
+      EpreuveChange::Modified {
+        num: 4,
+        label: "Club 1 Grand Prix (1,00 m)".into(),
+        changes: vec![format!("libellé « {} » → « Club 1 Grand Prix (1,00 m) »", old[3].label)],
+      },
+      EpreuveChange::Added { num: 5, label: "Amateur 1 Vitesse (1,20 m)".into() },
+    ]);
+    assert_eq!(changes[0].describe(), format!("- Epreuve 1 supprimée : {}", old[0].label));
+    assert_eq!(changes[1].describe(), format!("~ Epreuve 2 ({}) : hauteur 115 cm → 120 cm", old[1].label));
+    assert_eq!(changes[4].describe(), "+ Epreuve 5 ajoutée : Amateur 1 Vitesse (1,20 m)");
+  }
+
+  #[test]
+  fn diff_combines_changes_and_unknown_values() {
+    let old = parse_contest_page(ID, TABLE_HTML);
+    let mut new = old.clone();
+    new[1].height_cm = None;
+    new[1].max = Some(70);
+    let changes = diff(&old, &new);
+    assert_eq!(changes, vec![EpreuveChange::Modified {
+      num: 2,
+      label: old[1].label.clone(),
+      changes: vec!["hauteur 115 cm → ?".into(), "places 60 → 70".into()],
+    }]);
+  }
+
+  #[test]
+  fn diff_ignores_engaged_count_and_label_noise() {
+    let old = parse_contest_page(ID, TABLE_HTML);
+    let mut new = old.clone();
+    // Le nombre d'engagés bouge en continu
+    new[0].engaged = Some(new[0].engaged.unwrap_or(0) + 3);
+    // Espaces, casse et accents seuls
+    assert_eq!(old[2].label, "Club Élite Spéciale (1,05 m)");
+    new[2].label = "  Club  Elite\u{a0}SPECIALE (1,05 m) ".into();
+    new[1].label = old[1].label.replace(' ', "  ");
+    // Ordre de lecture différent
+    new.reverse();
+    assert!(diff(&old, &new).is_empty());
+  }
+}
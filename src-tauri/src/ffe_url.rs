@@ -0,0 +1,242 @@
+// Normalisation des URLs FFE collées par l'utilisateur: page concours, page épreuve, redirection
+// SSO (`returnUrl`), liens mobiles, paramètres de suivi (utm_*, fbclid, ...). On en extrait le
+// n° de concours et le n° d'épreuve pour produire l'URL canonique de la cible.
+
+use reqwest::Url;
+use crate::{epreuves, text};
+
+/// Paramètres de redirection qui portent l'URL de destination (SSO, liens de partage).
+const RETURN_PARAMS: &[&str] = &["returnurl", "return_url", "returnto", "redirect", "redirect_uri", "redirecturl", "service", "next", "url", "target"];
+const CONTEST_PARAMS: &[&str] = &["numconcours", "numeroconcours", "concours", "idconcours", "concoursid"];
+const EPREUVE_PARAMS: &[&str] = &["watch_epreuve", "numepreuve", "numeroepreuve", "epreuve", "idepreuve", "epreuveid"];
+/// Redirections imbriquées suivies au plus.
+const MAX_DEPTH: usize = 3;
+
+/// Concours (et épreuve) désignés par une URL ffecompet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfeUrl {
+  pub contest_id: String,
+  pub epreuve: Option<u32>,
+}
+
+impl FfeUrl {
+  /// URL surveillée: page du concours, avec `?watch_epreuve=N` pour une épreuve.
+  pub fn canonical(&self) -> String {
+    match self.epreuve {
+      Some(num) => epreuves::epreuve_url(&self.contest_id, num),
+      None => epreuves::contest_url(&self.contest_id),
+    }
+  }
+}
+
+fn is_contest_id(s: &str) -> bool {
+  s.len() == 9 && s.chars().all(|c| c.is_ascii_digit())
+}
+
+fn epreuve_num(s: &str) -> Result<u32, String> {
+  s.trim()
+    .parse::<u32>()
+    .ok()
+    .filter(|n| (1..1000).contains(n))
+    .ok_or_else(|| format!("N° d'épreuve invalide: {s}"))
+}
+
+/// (hôte FFE, hôte ffecompet y compris www. / m. / mobile.)
+fn is_ffe_host(host: &str) -> (bool, bool) {
+  let host = host.trim_end_matches('.').to_ascii_lowercase();
+  let ffe = host == "ffe.com" || host.ends_with(".ffe.com");
+  let compet = ffe && {
+    let sub = host.trim_end_matches(".ffe.com");
+    let sub = sub.strip_prefix("www.").or_else(|| sub.strip_prefix("m.")).or_else(|| sub.strip_prefix("mobile.")).unwrap_or(sub);
+    sub == "ffecompet"
+  };
+  (ffe, compet)
+}
+
+fn parse_url(input: &str) -> Result<Url, String> {
+  // Copie depuis une source HTML (`&amp;`), guillemets / chevrons autour, schéma absent
+  let s = text::decode_entities(input.trim());
+  let s = s.trim_matches(|c: char| c.is_whitespace() || "<>\"'".contains(c));
+  if s.is_empty() {
+    return Err("URL vide.".into());
+  }
+  let with_scheme = if s.contains("://") { s.to_string() } else { format!("https://{s}") };
+  let url = Url::parse(&with_scheme).map_err(|_| format!("URL invalide: {s}"))?;
+  if !matches!(url.scheme(), "http" | "https") {
+    return Err(format!("URL invalide (http ou https attendu): {s}"));
+  }
+  Ok(url)
+}
+
+fn query_value(url: &Url, names: &[&str]) -> Option<String> {
+  url
+    .query_pairs()
+    .find(|(k, v)| names.contains(&k.to_ascii_lowercase().as_str()) && !v.trim().is_empty())
+    .map(|(_, v)| v.trim().to_string())
+}
+
+fn from_url(url: &Url, depth: usize) -> Result<FfeUrl, String> {
+  let host = url.host_str().unwrap_or("");
+  let (ffe, compet) = is_ffe_host(host);
+
+  // Redirection (SSO, lien de partage): on suit l'URL de destination
+  if let Some(target) = query_value(url, RETURN_PARAMS) {
+    if depth < MAX_DEPTH {
+      if let Ok(inner) = parse_url(&target).and_then(|u| from_url(&u, depth + 1)) {
+        return Ok(inner);
+      }
+    }
+  }
+  if !ffe {
+    return Err(format!("URL non FFE ({host}) : colle le lien d'un concours ou d'une épreuve ffecompet.ffe.com."));
+  }
+  if !compet {
+    return Err(format!("Page FFE non reconnue ({host}) : colle le lien d'un concours ou d'une épreuve ffecompet."));
+  }
+
+  // Chemin: .../concours/{id}[/epreuve(s)/{n}], y compris préfixes mobiles (/m, /mobile, /app)
+  let segments: Vec<&str> = url.path_segments().map(|s| s.filter(|p| !p.is_empty()).collect()).unwrap_or_default();
+  let after = |names: &[&str]| {
+    segments
+      .windows(2)
+      .find(|w| names.contains(&w[0].to_ascii_lowercase().as_str()))
+      .map(|w| w[1].to_string())
+  };
+
+  let contest_id = after(&["concours", "concour", "contest"])
+    .filter(|s| is_contest_id(s))
+    .or_else(|| query_value(url, CONTEST_PARAMS).filter(|s| is_contest_id(s)))
+    .or_else(|| segments.iter().find(|s| is_contest_id(s)).map(|s| s.to_string()))
+    .ok_or_else(|| format!("N° de concours (9 chiffres) introuvable dans l'URL: {url}"))?;
+
+  // Fragment "#epreuve-2" / "#epreuve=2"
+  let fragment = url.fragment().and_then(|f| {
+    let f = f.to_ascii_lowercase();
+    let rest = f.strip_prefix("epreuve")?;
+    Some(rest.trim_start_matches(['-', '=', '_']).to_string())
+  });
+  let epreuve = match after(&["epreuve", "epreuves"]).or_else(|| query_value(url, EPREUVE_PARAMS)).or(fragment) {
+    Some(n) => Some(epreuve_num(&n)?),
+    None => None,
+  };
+  Ok(FfeUrl { contest_id, epreuve })
+}
+
+/// Reconnaît une URL ffecompet collée telle quelle.
+pub fn normalize(input: &str) -> Result<FfeUrl, String> {
+  from_url(&parse_url(input)?, 0)
+}
+
+/// Même cible: même concours / épreuve une fois les URLs normalisées (URLs brutes comparées
+/// sans casse si l'une n'est pas reconnue, pour les anciennes cibles).
+pub fn same_target(a: &str, b: &str) -> bool {
+  match (normalize(a), normalize(b)) {
+    (Ok(a), Ok(b)) => a == b,
+    _ => a.trim().eq_ignore_ascii_case(b.trim()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const ID: &str = "202635080";
+
+  #[test]
+  fn recognized_shapes() {
+    let cases: &[(&str, Option<u32>)] = &[
+      // Page concours
+      ("https://ffecompet.ffe.com/concours/202635080", None),
+      ("https://ffecompet.ffe.com/concours/202635080/", None),
+      // Chemin épreuve
+      ("https://ffecompet.ffe.com/concours/202635080/epreuve/3", Some(3)),
+      ("https://ffecompet.ffe.com/concours/202635080/epreuves/12/engages", Some(12)),
+      // URL canonique
+      ("https://ffecompet.ffe.com/concours/202635080?watch_epreuve=4", Some(4)),
+      ("https://ffecompet.ffe.com/concours/202635080?numEpreuve=5", Some(5)),
+      // Fragment
+      ("https://ffecompet.ffe.com/concours/202635080#epreuve-2", Some(2)),
+      ("https://ffecompet.ffe.com/concours/202635080#epreuve=7", Some(7)),
+      // Redirection SSO, y compris imbriquée et encodée
+      ("https://sso.ffe.com/login?returnUrl=https%3A%2F%2Fffecompet.ffe.com%2Fconcours%2F202635080%2Fepreuve%2F6", Some(6)),
+      (
+        "https://www.ffe.com/connexion?redirect=https%3A%2F%2Fsso.ffe.com%2Flogin%3FreturnUrl%3Dhttps%253A%252F%252Fffecompet.ffe.com%252Fconcours%252F202635080%253Fwatch_epreuve%253D8",
+        Some(8),
+      ),
+      // Hôtes mobiles / www.
+      ("https://m.ffecompet.ffe.com/concours/202635080", None),
+      ("https://mobile.ffecompet.ffe.com/m/concours/202635080/epreuve/9", Some(9)),
+      ("https://www.ffecompet.ffe.com/concours/202635080", None),
+      ("https://FFECOMPET.FFE.COM./concours/202635080", None),
+      // Paramètres de suivi
+      ("https://ffecompet.ffe.com/concours/202635080?utm_source=facebook&utm_medium=social&fbclid=abc", None),
+      ("https://ffecompet.ffe.com/concours/202635080?utm_source=x&watch_epreuve=10&utm_campaign=y", Some(10)),
+      // Copie depuis du HTML
+      ("https://ffecompet.ffe.com/concours/202635080?utm_source=x&amp;watch_epreuve=11", Some(11)),
+      ("  <https://ffecompet.ffe.com/concours/202635080>  ", None),
+      ("\"https://ffecompet.ffe.com/concours/202635080?watch_epreuve=1\"", Some(1)),
+      // Schéma absent
+      ("ffecompet.ffe.com/concours/202635080/epreuve/2", Some(2)),
+      ("http://ffecompet.ffe.com/concours/202635080", None),
+      // N° de concours en paramètre
+      ("https://ffecompet.ffe.com/fiche?numConcours=202635080&epreuve=3", Some(3)),
+    ];
+    for (input, epreuve) in cases {
+      assert_eq!(normalize(input), Ok(FfeUrl { contest_id: ID.into(), epreuve: *epreuve }), "{input}");
+    }
+  }
+
+  #[test]
+  fn rejected_shapes() {
+    let cases: &[(&str, &str)] = &[
+      ("", "URL vide"),
+      ("   ", "URL vide"),
+      ("https://example.com/concours/202635080", "URL non FFE"),
+      ("https://ffecompet.ffe.com.evil.io/concours/202635080", "URL non FFE"),
+      ("https://sso.example.com/login?returnUrl=https%3A%2F%2Fexample.org%2Fconcours%2F202635080", "URL non FFE"),
+      ("https://www.ffe.com/concours/202635080", "Page FFE non reconnue"),
+      ("ftp://ffecompet.ffe.com/concours/202635080", "http ou https attendu"),
+      // 8 ou 10 chiffres: pas un n° de concours
+      ("https://ffecompet.ffe.com/concours/20263508", "introuvable"),
+      ("https://ffecompet.ffe.com/concours/2026350801", "introuvable"),
+      // Épreuve 0 ou hors bornes
+      ("https://ffecompet.ffe.com/concours/202635080/epreuve/0", "N° d'épreuve invalide"),
+      ("https://ffecompet.ffe.com/concours/202635080?watch_epreuve=0", "N° d'épreuve invalide"),
+      ("https://ffecompet.ffe.com/concours/202635080#epreuve-1000", "N° d'épreuve invalide"),
+      ("https://ffecompet.ffe.com/concours/202635080?watch_epreuve=abc", "N° d'épreuve invalide"),
+    ];
+    for (input, message) in cases {
+      let err = normalize(input).expect_err(input);
+      assert!(err.contains(message), "{input}: {err}");
+    }
+  }
+
+  #[test]
+  fn canonical_round_trip() {
+    let contest = FfeUrl { contest_id: ID.into(), epreuve: None };
+    let epreuve = FfeUrl { contest_id: ID.into(), epreuve: Some(4) };
+    assert_eq!(contest.canonical(), "https://ffecompet.ffe.com/concours/202635080");
+    assert_eq!(epreuve.canonical(), "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=4");
+    assert_eq!(normalize(&contest.canonical()), Ok(contest));
+    assert_eq!(normalize(&epreuve.canonical()), Ok(epreuve));
+  }
+
+  #[test]
+  fn same_target_compares_normalized() {
+    assert!(same_target(
+      "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=4",
+      "m.ffecompet.ffe.com/concours/202635080/epreuve/4?utm_source=x"
+    ));
+    assert!(!same_target(
+      "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=4",
+      "https://ffecompet.ffe.com/concours/202635080"
+    ));
+    assert!(!same_target(
+      "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=4",
+      "https://ffecompet.ffe.com/concours/202635081?watch_epreuve=4"
+    ));
+    // Ancienne cible non reconnue: comparaison brute
+    assert!(same_target(" https://example.com/Page ", "https://example.com/page"));
+    assert!(!same_target("https://example.com/page", "https://ffecompet.ffe.com/concours/202635080"));
+  }
+}
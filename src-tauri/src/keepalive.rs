@@ -0,0 +1,277 @@
+// Keep-alive des sessions FFE: chaque compte connecté touche régulièrement une page authentifiée
+// légère (les Set-Cookie reçus rafraîchissent le jar), ce qui évite l'expiration par inactivité
+// quand aucune cible n'est surveillée. On garde la dernière réponse authentifiée par compte et
+// on prévient avant l'expiration probable des cookies d'authentification.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use crate::{accounts, cookie_jar::CookieJar, http, detect::{has_logout_marker, is_login_page}, models::SessionHealth};
+
+const KEEPALIVE_URL: &str = "https://ffecompet.ffe.com/";
+/// Intervalle entre deux passages pour un même compte
+const KEEPALIVE_EVERY: i64 = 10 * 60;
+/// Avertissement quand l'expiration estimée est à moins de ...
+const WARN_BEFORE: i64 = 15 * 60;
+/// Durée d'inactivité après laquelle le SSO FFE invalide une session dont les cookies
+/// d'authentification n'ont pas d'Expires (cookies de session). La FFE ne publie pas cette
+/// valeur: 30 min est le défaut des serveurs courants (Tomcat `session-timeout`, Keycloak
+/// « SSO Session Idle »). Si la session tient plus longtemps, on prévient simplement trop tôt;
+/// le keep-alive (`KEEPALIVE_EVERY`, plus court) la garde active de toute façon.
+const SESSION_IDLE_TTL: i64 = 30 * 60;
+
+pub enum KeepAliveEvent {
+  /// L'expiration estimée approche (timestamp unix)
+  Expiring { account: String, expires_at: i64 },
+  /// Le keep-alive a été renvoyé vers la page de connexion
+  Expired { account: String },
+}
+
+#[derive(Default)]
+struct Health {
+  last_ok: Option<i64>,
+  last_check: Option<i64>,
+  last_error: Option<String>,
+  expired: bool,
+  /// Expiration déjà signalée (évite de répéter l'avertissement)
+  warned_for: Option<i64>,
+}
+
+static HEALTH: OnceLock<Mutex<HashMap<String, Health>>> = OnceLock::new();
+
+fn with_health<T>(account: &str, f: impl FnOnce(&mut Health) -> T) -> T {
+  let cell = HEALTH.get_or_init(|| Mutex::new(HashMap::new()));
+  let mut g = cell.lock().unwrap();
+  f(g.entry(account.to_string()).or_default())
+}
+
+/// Réponse authentifiée observée (keep-alive, watcher, scraping).
+pub fn mark_authenticated(account: &str) {
+  let now = chrono::Utc::now().timestamp();
+  with_health(account, |h| {
+    h.last_ok = Some(now);
+    h.expired = false;
+  });
+}
+
+/// Oublie l'état du compte (déconnexion / suppression).
+pub fn forget(account: &str) {
+  if let Some(m) = HEALTH.get() {
+    m.lock().unwrap().remove(account);
+  }
+}
+
+/// Expiration estimée de la session: Expires des cookies d'authentification, et pour les cookies
+/// de session, dernière réponse authentifiée + durée d'inactivité (la plus proche des deux).
+fn estimated_expiry(account: &str, last_ok: Option<i64>) -> Option<i64> {
+  jar_expiry(&accounts::jar(account), last_ok)
+}
+
+fn jar_expiry(jar: &CookieJar, last_ok: Option<i64>) -> Option<i64> {
+  let idle = last_ok.filter(|_| jar.has_session_auth_cookie()).map(|t| t + SESSION_IDLE_TTL);
+  match (jar.earliest_auth_expiry(), idle) {
+    (Some(a), Some(b)) => Some(a.min(b)),
+    (a, b) => a.or(b),
+  }
+}
+
+pub fn health(account: &str) -> SessionHealth {
+  let last_ok = with_health(account, |h| h.last_ok);
+  let expires_at = estimated_expiry(account, last_ok);
+  with_health(account, |h| SessionHealth {
+    account: account.to_string(),
+    last_ok: h.last_ok,
+    last_check: h.last_check,
+    last_error: h.last_error.clone(),
+    expires_at,
+    expired: h.expired,
+  })
+}
+
+/// Page de keep-alive lue avec les cookies du compte: (URL finale, HTML).
+async fn fetch(account: &str) -> Result<(String, String), String> {
+  match http::client(Some(account)) {
+    Ok(client) => match client.get(KEEPALIVE_URL).send().await {
+      Ok(resp) if resp.status().is_success() => {
+        let final_url = resp.url().to_string();
+        http::read_text(resp).await.map(|html| (final_url, html)).map_err(|e| format!("read body: {e:#}"))
+      }
+      Ok(resp) => Err(format!("HTTP {}", resp.status())),
+      Err(e) => Err(format!("http: {e}")),
+    },
+    Err(e) => Err(format!("client: {e}")),
+  }
+}
+
+/// Vérifie les cookies du jar par une requête HTTP: `Ok(true)` si la page est authentifiée,
+/// `Ok(false)` si elle renvoie vers la page de connexion, `Err` si on ne peut pas conclure
+/// (réseau, page sans marqueur).
+pub async fn verify(account: &str) -> Result<bool, String> {
+  let (final_url, html) = fetch(account).await?;
+  if is_login_page(&final_url, &html) {
+    Ok(false)
+  } else if has_logout_marker(&html) {
+    mark_authenticated(account);
+    Ok(true)
+  } else {
+    Err("réponse sans marqueur de session".into())
+  }
+}
+
+/// Un passage de keep-alive pour un compte. Renvoie true si la session est tombée.
+async fn touch(account: &str) -> bool {
+  let now = chrono::Utc::now().timestamp();
+  let res = fetch(account).await;
+
+  with_health(account, |h| {
+    h.last_check = Some(now);
+    match res {
+      Ok((final_url, html)) if is_login_page(&final_url, &html) => {
+        let newly = !h.expired;
+        h.expired = true;
+        h.last_error = Some(format!("redirigé vers {final_url}"));
+        newly
+      }
+      Ok((_, html)) if has_logout_marker(&html) => {
+        h.last_ok = Some(now);
+        h.expired = false;
+        h.last_error = None;
+        false
+      }
+      Ok(_) => {
+        h.last_error = Some("réponse sans marqueur de session".into());
+        false
+      }
+      Err(e) => {
+        h.last_error = Some(e);
+        false
+      }
+    }
+  })
+}
+
+/// Avertissement unique par valeur d'expiration estimée.
+fn expiring_soon(account: &str, now: i64) -> Option<i64> {
+  let last_ok = with_health(account, |h| h.last_ok);
+  let expires_at = estimated_expiry(account, last_ok)?;
+  with_health(account, |h| warn_due(h, expires_at, now))
+}
+
+fn warn_due(h: &mut Health, expires_at: i64, now: i64) -> Option<i64> {
+  if expires_at - now > WARN_BEFORE || h.warned_for == Some(expires_at) {
+    return None;
+  }
+  h.warned_for = Some(expires_at);
+  Some(expires_at)
+}
+
+/// Boucle de keep-alive: toutes les minutes, touche les comptes dont le dernier passage date
+/// de plus de `KEEPALIVE_EVERY` et signale les sessions proches de l'expiration.
+pub async fn keepalive_loop(on_event: Arc<dyn Fn(KeepAliveEvent) + Send + Sync>) {
+  loop {
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    let now = chrono::Utc::now().timestamp();
+
+    for (account, jar) in accounts::all_jars() {
+      if jar.is_empty() {
+        continue;
+      }
+
+      let due = with_health(&account, |h| {
+        !h.expired && h.last_check.is_none_or(|t| now - t >= KEEPALIVE_EVERY)
+      });
+      if due && touch(&account).await {
+        on_event(KeepAliveEvent::Expired { account: account.clone() });
+        continue;
+      }
+
+      if let Some(expires_at) = expiring_soon(&account, now) {
+        on_event(KeepAliveEvent::Expiring { account, expires_at });
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::cookie_jar::StoredCookie;
+
+  fn cookie(name: &str, http_only: bool, expires: Option<i64>) -> StoredCookie {
+    StoredCookie {
+      name: name.into(),
+      value: "x".into(),
+      domain: "ffe.com".into(),
+      host_only: false,
+      path: "/".into(),
+      secure: true,
+      http_only,
+      expires,
+    }
+  }
+
+  #[test]
+  fn expiry_from_cookies_and_idle_ttl() {
+    let now = chrono::Utc::now().timestamp();
+    let jar = CookieJar::new();
+    assert_eq!(jar_expiry(&jar, Some(now)), None);
+
+    // Cookie posé en JS (pas HttpOnly): ignoré
+    jar.insert(cookie("_ga", false, Some(now + 60)));
+    assert_eq!(jar_expiry(&jar, Some(now)), None);
+
+    // Cookie d'authentification persistant: son Expires
+    jar.insert(cookie("AUTH", true, Some(now + 3600)));
+    assert_eq!(jar_expiry(&jar, Some(now)), Some(now + 3600));
+
+    // Cookie de session: dernière réponse authentifiée + inactivité, la plus proche gagne
+    jar.insert(cookie("JSESSIONID", true, None));
+    assert_eq!(jar_expiry(&jar, Some(now)), Some(now + SESSION_IDLE_TTL));
+    assert_eq!(jar_expiry(&jar, Some(now + 3600)), Some(now + 3600));
+    // Jamais authentifié: seul l'Expires compte
+    assert_eq!(jar_expiry(&jar, None), Some(now + 3600));
+
+    let jar = CookieJar::new();
+    jar.insert(cookie("JSESSIONID", true, None));
+    assert_eq!(jar_expiry(&jar, None), None);
+    assert_eq!(jar_expiry(&jar, Some(now - 60)), Some(now - 60 + SESSION_IDLE_TTL));
+  }
+
+  #[test]
+  fn warning_threshold_once_per_expiry() {
+    let now = 1_000_000;
+    let mut h = Health::default();
+    assert_eq!(warn_due(&mut h, now + WARN_BEFORE + 1, now), None);
+    assert_eq!(warn_due(&mut h, now + WARN_BEFORE, now), Some(now + WARN_BEFORE));
+    assert_eq!(warn_due(&mut h, now + WARN_BEFORE, now + 60), None);
+    // Session prolongée puis de nouveau proche de l'expiration: nouvel avertissement
+    assert_eq!(warn_due(&mut h, now + 600, now), Some(now + 600));
+    // Déjà expirée (estimation dépassée): toujours signalée une fois
+    assert_eq!(warn_due(&mut h, now - 10, now), Some(now - 10));
+  }
+
+  #[test]
+  fn health_of_account() {
+    let account = "test_keepalive_health";
+    let jar = accounts::jar(account);
+    jar.insert(cookie("JSESSIONID", true, None));
+
+    let h = health(account);
+    assert_eq!((h.account.as_str(), h.last_ok, h.expires_at, h.expired), (account, None, None, false));
+
+    mark_authenticated(account);
+    let h = health(account);
+    let last_ok = h.last_ok.unwrap();
+    assert_eq!(h.expires_at, Some(last_ok + SESSION_IDLE_TTL));
+
+    let now = chrono::Utc::now().timestamp();
+    assert_eq!(expiring_soon(account, now), None);
+    let near = last_ok + SESSION_IDLE_TTL - WARN_BEFORE;
+    assert_eq!(expiring_soon(account, near), Some(last_ok + SESSION_IDLE_TTL));
+    assert_eq!(expiring_soon(account, near + 60), None);
+
+    forget(account);
+    accounts::forget_jar(account);
+    assert_eq!(health(account).last_ok, None);
+  }
+}
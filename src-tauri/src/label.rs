@@ -0,0 +1,336 @@
+// Grammaire des libellés d'épreuves FFE: "<série> <niveau> <barème> (<hauteur>)".
+// Ex: "Amateur 2 Grand Prix (1,15 m)", "Club Elite Spéciale 1m05", "Am1 Barème A au chrono 120 cm".
+// Chaque élément est optionnel; le parseur tolère abréviations, accents et formats de hauteur variés.
+
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use crate::text;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Series {
+  Club,
+  Poney,
+  Amateur,
+  Pro,
+}
+
+impl Series {
+  pub fn name(self) -> &'static str {
+    match self {
+      Series::Club => "Club",
+      Series::Poney => "Poney",
+      Series::Amateur => "Amateur",
+      Series::Pro => "Pro",
+    }
+  }
+
+  fn from_word(w: &str) -> Option<Self> {
+    Some(match w {
+      "club" | "clb" => Series::Club,
+      "poney" | "poneys" | "pon" => Series::Poney,
+      "amateur" | "amateurs" | "ama" | "am" => Series::Amateur,
+      "pro" | "pros" => Series::Pro,
+      _ => return None,
+    })
+  }
+}
+
+/// Niveau dans la série: Elite est le plus difficile, puis 1, 2, 3, 4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Level {
+  #[serde(rename = "elite")]
+  Elite,
+  #[serde(rename = "1")]
+  One,
+  #[serde(rename = "2")]
+  Two,
+  #[serde(rename = "3")]
+  Three,
+  #[serde(rename = "4")]
+  Four,
+}
+
+impl Level {
+  pub fn name(self) -> &'static str {
+    match self {
+      Level::Elite => "Elite",
+      Level::One => "1",
+      Level::Two => "2",
+      Level::Three => "3",
+      Level::Four => "4",
+    }
+  }
+
+  /// Rang de difficulté croissante (4 < 3 < 2 < 1 < Elite)
+  pub fn rank(self) -> u8 {
+    match self {
+      Level::Four => 0,
+      Level::Three => 1,
+      Level::Two => 2,
+      Level::One => 3,
+      Level::Elite => 4,
+    }
+  }
+
+  fn from_word(w: &str) -> Option<Self> {
+    Some(match w {
+      "elite" => Level::Elite,
+      "1" => Level::One,
+      "2" => Level::Two,
+      "3" => Level::Three,
+      "4" => Level::Four,
+      _ => return None,
+    })
+  }
+}
+
+/// Barème / type d'épreuve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Table {
+  /// Barème A (au chrono, sans chrono, avec barrage)
+  A,
+  /// Barème C
+  C,
+  DeuxPhases,
+  Vitesse,
+  Preparatoire,
+  Speciale,
+  GrandPrix,
+  Derby,
+  Puissance,
+  SixBarres,
+}
+
+/// Motifs de barème (texte normalisé), le premier trouvé dans le libellé l'emporte.
+const TABLES: &[(&str, Table)] = &[
+  ("grand prix", Table::GrandPrix),
+  ("gp", Table::GrandPrix),
+  ("speciale", Table::Speciale),
+  ("special", Table::Speciale),
+  ("vitesse", Table::Vitesse),
+  ("preparatoire", Table::Preparatoire),
+  ("prepa", Table::Preparatoire),
+  ("deux phases", Table::DeuxPhases),
+  ("2 phases", Table::DeuxPhases),
+  ("bareme c", Table::C),
+  ("table c", Table::C),
+  ("bareme a", Table::A),
+  ("table a", Table::A),
+  ("a au chrono", Table::A),
+  ("a sans chrono", Table::A),
+  ("a avec barrage", Table::A),
+  ("derby", Table::Derby),
+  ("puissance", Table::Puissance),
+  ("six barres", Table::SixBarres),
+  ("6 barres", Table::SixBarres),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpreuveLabel {
+  pub series: Option<Series>,
+  pub level: Option<Level>,
+  pub table: Option<Table>,
+  pub height_cm: Option<u32>,
+}
+
+impl EpreuveLabel {
+  /// "Amateur 2", "Club Elite", "Pro" (None sans série)
+  pub fn category(&self) -> Option<String> {
+    let series = self.series?.name();
+    Some(match self.level {
+      Some(level) => format!("{series} {}", level.name()),
+      None => series.to_string(),
+    })
+  }
+
+  /// Clé de tri par difficulté: série, niveau, hauteur (inconnus en premier).
+  pub fn difficulty(&self) -> (Option<Series>, Option<u8>, Option<u32>) {
+    (self.series, self.level.map(Level::rank), self.height_cm)
+  }
+}
+
+fn height_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  // 1m10 / 1 m 10 | 1,10 m / 1.10m / 1,1m | 110 cm / 110cm
+  RE.get_or_init(|| {
+    Regex::new(r"\b(\d)\s?m\s?(\d{2})\b|\b(\d)[,.](\d{1,2})\s?m\b|\b(\d{2,3})\s?cm\b").unwrap()
+  })
+}
+
+/// Hauteur en cm (entre 40 cm et 2 m), quel que soit le format ("1m10", "110 cm", "1.10m",
+/// "(1,10 m)"), et position du motif dans le texte normalisé.
+fn find_height(n: &str) -> Option<(u32, std::ops::Range<usize>)> {
+  height_re().captures_iter(n).find_map(|c| {
+    let num = |i: usize| c.get(i).and_then(|m| m.as_str().parse::<u32>().ok());
+    let cm = if let (Some(m), Some(cm)) = (num(1), num(2)) {
+      m * 100 + cm
+    } else if let (Some(m), Some(dec)) = (num(3), c.get(4)) {
+      // "1,1 m" = 110 cm
+      let dec_cm = if dec.as_str().len() == 1 { num(4)? * 10 } else { num(4)? };
+      m * 100 + dec_cm
+    } else {
+      num(5)?
+    };
+    (40..=200).contains(&cm).then(|| (cm, c.get(0).unwrap().range()))
+  })
+}
+
+/// Sépare "am2" / "club1" / "proelite" en série + niveau.
+fn split_joined(w: &str) -> Option<(Series, Level)> {
+  let cut = w.find(|c: char| c.is_ascii_digit()).or_else(|| w.find("elite"))?;
+  let series = Series::from_word(&w[..cut])?;
+  Some((series, Level::from_word(&w[cut..])?))
+}
+
+fn find_table(n: &str) -> Option<Table> {
+  TABLES
+    .iter()
+    .filter_map(|(pat, table)| {
+      n.match_indices(pat)
+        .find(|(i, _)| word_bounded(n, *i, pat.len()))
+        .map(|(i, _)| (i, *table))
+    })
+    .min_by_key(|(i, _)| *i)
+    .map(|(_, t)| t)
+}
+
+fn word_bounded(n: &str, start: usize, len: usize) -> bool {
+  let before = n[..start].chars().next_back().is_none_or(|c| !c.is_alphanumeric());
+  let after = n[start + len..].chars().next().is_none_or(|c| !c.is_alphanumeric());
+  before && after
+}
+
+pub fn parse(label: &str) -> EpreuveLabel {
+  let mut n = text::normalize(label);
+  let mut out = EpreuveLabel::default();
+
+  // Hauteur d'abord (ses chiffres ne doivent pas être pris pour un niveau)
+  if let Some((cm, range)) = find_height(&n) {
+    out.height_cm = Some(cm);
+    n.replace_range(range, " ");
+  }
+
+  out.table = find_table(&n);
+
+  let words: Vec<&str> = n
+    .split(|c: char| !c.is_alphanumeric())
+    .filter(|w| !w.is_empty())
+    .collect();
+  for (i, w) in words.iter().enumerate() {
+    if let Some(series) = Series::from_word(w) {
+      out.series = Some(series);
+      out.level = words.get(i + 1).and_then(|next| Level::from_word(next));
+      break;
+    }
+    if let Some((series, level)) = split_joined(w) {
+      out.series = Some(series);
+      out.level = Some(level);
+      break;
+    }
+  }
+
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use Level::*;
+  use Series::*;
+
+  type Expected = (Option<Series>, Option<Level>, Option<Table>, Option<u32>);
+
+  /// Libellés relevés sur ffecompet (et variantes de saisie des organisateurs).
+  const FIXTURES: &[(&str, Expected)] = &[
+    ("Amateur 2 Grand Prix (1,15 m)", (Some(Amateur), Some(Two), Some(Table::GrandPrix), Some(115))),
+    ("Amateur 1 Grand Prix (1,20 m)", (Some(Amateur), Some(One), Some(Table::GrandPrix), Some(120))),
+    ("Amateur Elite Grand Prix (1,30 m)", (Some(Amateur), Some(Elite), Some(Table::GrandPrix), Some(130))),
+    ("Amateur 3 Préparatoire (1,00 m)", (Some(Amateur), Some(Three), Some(Table::Preparatoire), Some(100))),
+    ("Amateur 3 Spéciale (1,05 m)", (Some(Amateur), Some(Three), Some(Table::Speciale), Some(105))),
+    ("Amateur 2 Vitesse (1,10 m)", (Some(Amateur), Some(Two), Some(Table::Vitesse), Some(110))),
+    ("Amateur 1 Barème A au chrono (1,20 m)", (Some(Amateur), Some(One), Some(Table::A), Some(120))),
+    ("Amateur 2 Barème A sans chrono 1m10", (Some(Amateur), Some(Two), Some(Table::A), Some(110))),
+    ("Amateur 3 Deux phases 100 cm", (Some(Amateur), Some(Three), Some(Table::DeuxPhases), Some(100))),
+    ("Amateur 2 Barème C (1.10m)", (Some(Amateur), Some(Two), Some(Table::C), Some(110))),
+    ("AMATEUR 1 GRAND PRIX 1,20M", (Some(Amateur), Some(One), Some(Table::GrandPrix), Some(120))),
+    ("Am2 GP 1m15", (Some(Amateur), Some(Two), Some(Table::GrandPrix), Some(115))),
+    ("Am 1 Spéciale 1.25 m", (Some(Amateur), Some(One), Some(Table::Speciale), Some(125))),
+    ("Ama Elite Grand Prix 135cm", (Some(Amateur), Some(Elite), Some(Table::GrandPrix), Some(135))),
+    ("Club 2 Vitesse (0,95 m)", (Some(Club), Some(Two), Some(Table::Vitesse), Some(95))),
+    ("Club 1 Grand Prix (1,05 m)", (Some(Club), Some(One), Some(Table::GrandPrix), Some(105))),
+    ("Club Elite Spéciale (1,05 m)", (Some(Club), Some(Elite), Some(Table::Speciale), Some(105))),
+    ("Club Élite Grand Prix 1m10", (Some(Club), Some(Elite), Some(Table::GrandPrix), Some(110))),
+    ("Club 3 Préparatoire (0,85 m)", (Some(Club), Some(Three), Some(Table::Preparatoire), Some(85))),
+    ("Club 4 Barème A (0,75 m)", (Some(Club), Some(Four), Some(Table::A), Some(75))),
+    ("Club 4 Barème A 0m70", (Some(Club), Some(Four), Some(Table::A), Some(70))),
+    ("Club2 Derby 90 cm", (Some(Club), Some(Two), Some(Table::Derby), Some(90))),
+    ("Club 1 Six barres", (Some(Club), Some(One), Some(Table::SixBarres), None)),
+    ("Club Elite Puissance", (Some(Club), Some(Elite), Some(Table::Puissance), None)),
+    ("Pro 1 Grand Prix (1,45 m)", (Some(Pro), Some(One), Some(Table::GrandPrix), Some(145))),
+    ("Pro 2 Grand Prix (1,35 m)", (Some(Pro), Some(Two), Some(Table::GrandPrix), Some(135))),
+    ("Pro 3 Spéciale (1,25 m)", (Some(Pro), Some(Three), Some(Table::Speciale), Some(125))),
+    ("Pro Elite Grand Prix 1m50", (Some(Pro), Some(Elite), Some(Table::GrandPrix), Some(150))),
+    ("Pro1 GP 145 cm", (Some(Pro), Some(One), Some(Table::GrandPrix), Some(145))),
+    ("Pro 2 Barème A avec barrage (1.35 m)", (Some(Pro), Some(Two), Some(Table::A), Some(135))),
+    ("Poney 1 Grand Prix (1,00 m)", (Some(Poney), Some(One), Some(Table::GrandPrix), Some(100))),
+    ("Poney 2 Spéciale (0,90 m)", (Some(Poney), Some(Two), Some(Table::Speciale), Some(90))),
+    ("Poney Elite Grand Prix (1,15 m)", (Some(Poney), Some(Elite), Some(Table::GrandPrix), Some(115))),
+    ("Poney 3 Vitesse 75 cm", (Some(Poney), Some(Three), Some(Table::Vitesse), Some(75))),
+    ("Poney 4 Préparatoire 0m60", (Some(Poney), Some(Four), Some(Table::Preparatoire), Some(60))),
+    ("Grand Prix (Barème A) (1,20 m)", (None, None, Some(Table::GrandPrix), Some(120))),
+    ("Barème A au chrono - 1,1 m", (None, None, Some(Table::A), Some(110))),
+    ("Epreuve spéciale jeunes chevaux 4 ans", (None, None, Some(Table::Speciale), None)),
+    ("Amateur 2 (1,10&nbsp;m)", (Some(Amateur), Some(Two), None, Some(110))),
+    ("Amateur 2", (Some(Amateur), Some(Two), None, None)),
+    ("Amateur", (Some(Amateur), None, None, None)),
+    ("Préparatoire", (None, None, Some(Table::Preparatoire), None)),
+    ("Club 2 Grand Prix (2)", (Some(Club), Some(Two), Some(Table::GrandPrix), None)),
+    ("Amateur 3 Grand Prix (3,50 m)", (Some(Amateur), Some(Three), Some(Table::GrandPrix), None)),
+    ("Pro 2 – Grand Prix – 1m35", (Some(Pro), Some(Two), Some(Table::GrandPrix), Some(135))),
+    ("Amateur 1 grand prix, 1,20 m,", (Some(Amateur), Some(One), Some(Table::GrandPrix), Some(120))),
+    ("Programme: Amateur 2 Vitesse 1 m 10", (Some(Amateur), Some(Two), Some(Table::Vitesse), Some(110))),
+    ("", (None, None, None, None)),
+  ];
+
+  #[test]
+  fn fixture_table() {
+    for (label, (series, level, table, height)) in FIXTURES {
+      // Les entités éventuelles sont décodées en amont (scraping)
+      let parsed = parse(&text::decode_entities(label));
+      assert_eq!(
+        (parsed.series, parsed.level, parsed.table, parsed.height_cm),
+        (*series, *level, *table, *height),
+        "libellé: {label:?}"
+      );
+    }
+  }
+
+  #[test]
+  fn category_and_difficulty() {
+    let am_elite = parse("Amateur Elite Grand Prix (1,30 m)");
+    let am1 = parse("Amateur 1 Grand Prix (1,20 m)");
+    let am2 = parse("Amateur 2 Grand Prix (1,15 m)");
+    let club1 = parse("Club 1 Grand Prix (1,05 m)");
+    assert_eq!(am_elite.category().as_deref(), Some("Amateur Elite"));
+    assert_eq!(parse("Club Élite").category().as_deref(), Some("Club Elite"));
+    assert_eq!(parse("Grand Prix").category(), None);
+
+    let mut sorted = vec![am1.clone(), am_elite.clone(), club1.clone(), am2.clone()];
+    sorted.sort_by_key(EpreuveLabel::difficulty);
+    assert_eq!(sorted, vec![club1, am2, am1, am_elite]);
+  }
+
+  #[test]
+  fn heights() {
+    let h = |s: &str| parse(s).height_cm;
+    assert_eq!(h("(1,10 m)"), Some(110));
+    assert_eq!(h("1m10"), Some(110));
+    assert_eq!(h("110 cm"), Some(110));
+    assert_eq!(h("1.10m"), Some(110));
+    assert_eq!(h("0m95"), Some(95));
+    assert_eq!(h("Club 2"), None);
+  }
+}
@@ -0,0 +1,366 @@
+// Machine à états de la connexion FFE par identifiant / mot de passe.
+// Chaque étape est décidée à partir d'une sonde typée (cf. probe.rs) et émise vers l'UI
+// sous forme d'évènement `ffe:login`, au lieu d'un polling aveugle suivi d'une erreur générique.
+
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+use crate::probe::LoginProbe;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LoginState {
+  Loading,
+  CookieBanner,
+  FormFilled,
+  Submitted,
+  Redirecting,
+  CaptchaRequired,
+  TwoFactorRequired,
+  Connected,
+  Failed,
+}
+
+/// Payload de l'évènement `ffe:login`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginEvent {
+  pub account: String,
+  pub state: LoginState,
+  pub detail: Option<String>,
+  pub href: Option<String>,
+  pub ts: i64,
+}
+
+/// Ce que le flux de connexion doit faire après une sonde.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+  Wait,
+  AcceptCookies,
+  FillAndSubmit,
+  /// Intervention manuelle nécessaire (captcha / 2FA): la fenêtre est affichée
+  Manual,
+  Done,
+  Fail(String),
+}
+
+/// Délai après soumission avant de considérer que le formulaire n'a pas été accepté
+const SUBMIT_GRACE: Duration = Duration::from_secs(8);
+const MAX_SUBMITS: u32 = 2;
+
+pub struct LoginFlow {
+  /// Émission des changements d'état (évènement `ffe:login` vers l'UI)
+  emit: Box<dyn Fn(LoginEvent) + Send + Sync>,
+  account: String,
+  state: Option<LoginState>,
+  detail: Option<String>,
+  submits: u32,
+  last_submit: Option<Instant>,
+}
+
+impl LoginFlow {
+  pub fn new(app: tauri::AppHandle, account: &str) -> Self {
+    Self::with_emitter(account, move |ev| {
+      let _ = app.emit("ffe:login", ev);
+    })
+  }
+
+  fn with_emitter(account: &str, emit: impl Fn(LoginEvent) + Send + Sync + 'static) -> Self {
+    Self { emit: Box::new(emit), account: account.to_string(), state: None, detail: None, submits: 0, last_submit: None }
+  }
+
+  /// Change d'état et l'émet vers l'UI (uniquement si l'état ou le détail a changé).
+  pub fn set(&mut self, state: LoginState, detail: Option<String>, href: Option<&str>) {
+    if self.state == Some(state) && self.detail == detail {
+      return;
+    }
+    self.state = Some(state);
+    self.detail = detail.clone();
+    (self.emit)(LoginEvent {
+      account: self.account.clone(),
+      state,
+      detail,
+      href: href.map(str::to_string),
+      ts: chrono::Utc::now().timestamp(),
+    });
+  }
+
+  pub fn submitted(&mut self) {
+    self.submits += 1;
+    self.last_submit = Some(Instant::now());
+  }
+
+  /// Décide de l'étape suivante à partir de la dernière sonde (None = page en cours de chargement).
+  pub fn next(&mut self, probe: Option<&LoginProbe>) -> Step {
+    let Some(p) = probe else {
+      let state = if self.submits > 0 { LoginState::Redirecting } else { LoginState::Loading };
+      self.set(state, None, None);
+      return Step::Wait;
+    };
+    let href = Some(p.href.as_str());
+
+    // Le champ de code l'emporte sur tout le reste (la page peut déjà afficher "Déconnexion"
+    // alors que le SSO attend encore le code).
+    if p.has_two_factor {
+      self.set(LoginState::TwoFactorRequired, Some("code de vérification à saisir dans la fenêtre FFE".into()), href);
+      return Step::Manual;
+    }
+    // Une page authentifiée peut charger un captcha sans rien demander (reCAPTCHA invisible):
+    // le captcha ne bloque que tant que le formulaire de login est affiché.
+    if p.is_connected() && !p.has_login_form {
+      self.set(LoginState::Connected, None, href);
+      return Step::Done;
+    }
+    if p.has_captcha {
+      self.set(LoginState::CaptchaRequired, Some("captcha à résoudre dans la fenêtre FFE".into()), href);
+      return Step::Manual;
+    }
+    if p.is_connected() {
+      self.set(LoginState::Connected, None, href);
+      return Step::Done;
+    }
+    if p.has_cookie_banner {
+      self.set(LoginState::CookieBanner, None, href);
+      return Step::AcceptCookies;
+    }
+    if !p.has_login_form {
+      // Ni formulaire ni session: page intermédiaire du SSO
+      self.set(LoginState::Redirecting, None, href);
+      return Step::Wait;
+    }
+
+    // Formulaire de login affiché
+    if self.submits == 0 {
+      return Step::FillAndSubmit;
+    }
+    if let Some(err) = p.error_text.as_deref() {
+      return Step::Fail(format!("connexion refusée: {err}"));
+    }
+    let waited = self.last_submit.is_some_and(|t| t.elapsed() >= SUBMIT_GRACE);
+    match (waited, self.submits < MAX_SUBMITS) {
+      (false, _) => Step::Wait,
+      (true, true) => Step::FillAndSubmit,
+      (true, false) => Step::Fail("formulaire toujours affiché après soumission (identifiants refusés ?)".into()),
+    }
+  }
+
+  pub fn fail(&mut self, reason: &str, href: Option<&str>) {
+    self.set(LoginState::Failed, Some(reason.to_string()), href);
+  }
+}
+
+const HELPERS_JS: &str = r#"
+  function qAll(root, sel) { try { return Array.from(root.querySelectorAll(sel)); } catch(e) { return []; } }
+  function byText(root, tag, re) {
+    return qAll(root, tag).find(el => re.test((el.innerText || el.value || el.getAttribute('aria-label') || '').trim()));
+  }
+  function findFormDoc() {
+    // Cherche un iframe qui contient un password
+    for (const fr of qAll(document, 'iframe')) {
+      try {
+        const d = fr.contentDocument;
+        if (d && d.querySelector('input[type=password]')) return d;
+      } catch(e) {}
+    }
+    return document;
+  }
+"#;
+
+/// Accepte le bandeau cookies (didomi en priorité, sinon bouton "Accepter").
+pub fn accept_cookies_script() -> String {
+  format!(r#"
+    (function() {{
+      {HELPERS_JS}
+      const didomi = document.querySelector('#didomi-notice-agree-button');
+      if (didomi) {{ try {{ didomi.click(); return; }} catch(e) {{}} }}
+      const patterns = [/tout accepter/i, /accepter/i, /^ok$/i, /d'accord/i];
+      for (const doc of [document, findFormDoc()]) {{
+        for (const b of qAll(doc, 'button, a, input[type=button], input[type=submit]')) {{
+          const t = (b.innerText || b.value || b.getAttribute('aria-label') || '').trim();
+          if (patterns.some(p => p.test(t))) {{ try {{ b.click(); return; }} catch(e) {{}} }}
+        }}
+      }}
+    }})();
+  "#)
+}
+
+/// Remplit identifiant + mot de passe puis clique "Se connecter" (ou soumet le formulaire).
+/// NB: on n'essaie PAS de contourner des challenges; captcha / 2FA sont laissés à l'utilisateur.
+pub fn fill_and_submit_script(username: &str, password: &str) -> String {
+  format!(r#"
+    (function() {{
+      const USER = {user_json};
+      const PASS = {pass_json};
+      {HELPERS_JS}
+
+      const doc = findFormDoc();
+      const userSelectors = [
+        "input[name='username']",
+        "input[name='email']",
+        "input[name='login']",
+        "input[type='email']",
+        "input[placeholder*='email' i]",
+        "input[placeholder*='identifiant' i]",
+        "input[placeholder*='licence' i]",
+      ];
+      const passSelectors = [
+        "input[name='password']",
+        "input[type='password']",
+        "input[placeholder*='mot de passe' i]",
+        "input[placeholder*='password' i]",
+        "input[placeholder*='code sif' i]",
+      ];
+
+      let u = null;
+      for (const s of userSelectors) {{ u = doc.querySelector(s); if (u) break; }}
+      let p = null;
+      for (const s of passSelectors) {{ p = doc.querySelector(s); if (p) break; }}
+
+      for (const [el, val] of [[u, USER], [p, PASS]]) {{
+        if (!el) continue;
+        el.focus();
+        el.value = val;
+        el.dispatchEvent(new Event('input', {{ bubbles: true }}));
+        el.dispatchEvent(new Event('change', {{ bubbles: true }}));
+      }}
+
+      const reBtn = /(se connecter|connexion|s'identifier)/i;
+      const btn = byText(doc, 'button', reBtn) || byText(doc, 'input[type=submit], input[type=button]', reBtn);
+      if (btn) {{
+        try {{ btn.click(); return; }} catch(e) {{}}
+      }}
+
+      // fallback: submit du formulaire
+      if (p && p.form) {{
+        try {{ p.form.submit(); return; }} catch(e) {{}}
+      }}
+      if (p) {{
+        try {{
+          p.dispatchEvent(new KeyboardEvent('keydown', {{ key: 'Enter', code: 'Enter', which: 13, keyCode: 13, bubbles: true }}));
+        }} catch(e) {{}}
+      }}
+    }})();
+  "#, user_json = serde_json::to_string(username).unwrap(), pass_json = serde_json::to_string(password).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn flow() -> LoginFlow {
+    LoginFlow::with_emitter("test", |_| {})
+  }
+
+  fn probe(href: &str) -> LoginProbe {
+    LoginProbe {
+      request_id: 1,
+      href: href.into(),
+      title: String::new(),
+      has_logout: false,
+      has_login_form: false,
+      has_captcha: false,
+      has_two_factor: false,
+      has_cookie_banner: false,
+      error_text: None,
+    }
+  }
+
+  const LOGIN_URL: &str = "https://sso.ffe.com/login";
+
+  fn login_form() -> LoginProbe {
+    LoginProbe { has_login_form: true, ..probe(LOGIN_URL) }
+  }
+
+  #[test]
+  fn loading_then_fill() {
+    let mut f = flow();
+    assert_eq!(f.next(None), Step::Wait);
+    assert_eq!(f.state, Some(LoginState::Loading));
+
+    let banner = LoginProbe { has_cookie_banner: true, ..login_form() };
+    assert_eq!(f.next(Some(&banner)), Step::AcceptCookies);
+    assert_eq!(f.state, Some(LoginState::CookieBanner));
+
+    assert_eq!(f.next(Some(&login_form())), Step::FillAndSubmit);
+  }
+
+  #[test]
+  fn captcha_before_connected() {
+    let mut f = flow();
+    let p = LoginProbe { has_captcha: true, has_logout: true, ..login_form() };
+    assert_eq!(f.next(Some(&p)), Step::Manual);
+    assert_eq!(f.state, Some(LoginState::CaptchaRequired));
+  }
+
+  #[test]
+  fn captcha_script_on_authenticated_page() {
+    let mut f = flow();
+    f.submitted();
+    // Tableau de bord FFE avec reCAPTCHA chargé en arrière-plan: session ouverte
+    let p = LoginProbe { has_captcha: true, has_logout: true, ..probe("https://ffecompet.ffe.com/") };
+    assert_eq!(f.next(Some(&p)), Step::Done);
+    assert_eq!(f.state, Some(LoginState::Connected));
+  }
+
+  #[test]
+  fn captcha_without_session() {
+    let mut f = flow();
+    f.submitted();
+    // Page de challenge seule (ni formulaire ni session)
+    let p = LoginProbe { has_captcha: true, ..probe("https://sso.ffe.com/challenge") };
+    assert_eq!(f.next(Some(&p)), Step::Manual);
+    assert_eq!(f.state, Some(LoginState::CaptchaRequired));
+  }
+
+  #[test]
+  fn two_factor_before_connected() {
+    let mut f = flow();
+    f.submitted();
+    let p = LoginProbe { has_two_factor: true, has_logout: true, ..probe("https://sso.ffe.com/otp") };
+    assert_eq!(f.next(Some(&p)), Step::Manual);
+    assert_eq!(f.state, Some(LoginState::TwoFactorRequired));
+  }
+
+  #[test]
+  fn sso_hop_is_redirecting() {
+    let mut f = flow();
+    f.submitted();
+    // Page intermédiaire sans formulaire ni marqueur de session: pas encore connecté
+    let hop = probe("https://sso.ffe.com/oauth2/authorize?client_id=ffecompet");
+    assert_eq!(f.next(Some(&hop)), Step::Wait);
+    assert_eq!(f.state, Some(LoginState::Redirecting));
+
+    assert_eq!(f.next(None), Step::Wait);
+    assert_eq!(f.state, Some(LoginState::Redirecting));
+  }
+
+  #[test]
+  fn refused_credentials() {
+    let mut f = flow();
+    f.submitted();
+    let p = LoginProbe { error_text: Some("Identifiant ou mot de passe invalide".into()), ..login_form() };
+    assert_eq!(f.next(Some(&p)), Step::Fail("connexion refusée: Identifiant ou mot de passe invalide".into()));
+  }
+
+  #[test]
+  fn form_still_shown_after_submits() {
+    let mut f = flow();
+    f.submitted();
+    assert_eq!(f.next(Some(&login_form())), Step::Wait);
+
+    f.last_submit = Instant::now().checked_sub(SUBMIT_GRACE);
+    assert_eq!(f.next(Some(&login_form())), Step::FillAndSubmit);
+
+    f.submitted();
+    f.last_submit = Instant::now().checked_sub(SUBMIT_GRACE);
+    assert!(matches!(f.next(Some(&login_form())), Step::Fail(_)));
+  }
+
+  #[test]
+  fn success() {
+    let mut f = flow();
+    f.submitted();
+    let p = LoginProbe { has_logout: true, ..probe("https://ffecompet.ffe.com/") };
+    assert_eq!(f.next(Some(&p)), Step::Done);
+    assert_eq!(f.state, Some(LoginState::Connected));
+  }
+}
@@ -0,0 +1,492 @@
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Context;
+use lettre::{
+  message::{header::ContentType, Mailbox},
+  transport::smtp::authentication::Credentials,
+  AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use rusqlite::Connection;
+use crate::{db, models::{Alert, AlertKind, DigestRow, SmtpSecurity, SmtpSettings}};
+
+pub const SETTINGS_KEY: &str = "smtp";
+const LAST_DIGEST_KEY: &str = "smtp_last_digest";
+
+pub fn load_settings(conn: &Connection) -> SmtpSettings {
+  db::get_setting(conn, SETTINGS_KEY).ok().flatten().unwrap_or_default()
+}
+
+fn transport(s: &SmtpSettings) -> anyhow::Result<AsyncSmtpTransport<Tokio1Executor>> {
+  let mut builder = match s.security {
+    SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&s.host)?,
+    SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&s.host)?,
+    // Pas de chiffrement: uniquement pour un "sink" SMTP local (MailHog, smtp4dev, ...)
+    SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&s.host),
+  };
+  builder = builder.port(s.port).timeout(Some(Duration::from_secs(20)));
+
+  if let Some(user) = s.username.as_deref().filter(|u| !u.trim().is_empty()) {
+    builder = builder.credentials(Credentials::new(
+      user.trim().to_string(),
+      s.password.clone().unwrap_or_default(),
+    ));
+  }
+  Ok(builder.build())
+}
+
+pub async fn send(s: &SmtpSettings, to: &[String], subject: &str, body: String) -> anyhow::Result<()> {
+  if to.is_empty() {
+    return Ok(());
+  }
+
+  let from: Mailbox = s.from.parse().context("adresse expéditeur invalide")?;
+  let mut msg = Message::builder().from(from).subject(subject);
+  for addr in to {
+    let mb: Mailbox = addr.parse().with_context(|| format!("destinataire invalide: {addr}"))?;
+    msg = msg.to(mb);
+  }
+  let msg = msg.header(ContentType::TEXT_PLAIN).body(body)?;
+
+  transport(s)?.send(msg).await.context("envoi SMTP")?;
+  Ok(())
+}
+
+fn alert_body(alert: &Alert) -> String {
+  let mut lines = vec![alert.title(), String::new()];
+  if let Some(c) = alert.cavalier.as_deref() { lines.push(format!("Cavalier : {c}")); }
+  if let Some(h) = alert.cheval.as_deref() { lines.push(format!("Cheval : {h}")); }
+  if let Some(d) = alert.detail.as_deref() {
+    lines.push(String::new());
+    lines.push(d.to_string());
+  }
+  lines.push(format!("Lien : {}", alert.url));
+  lines.push(String::new());
+  lines.push(match alert.kind {
+    AlertKind::SessionExpired => "Reconnectez-vous à FFE Compet dans l'application pour reprendre la surveillance.".into(),
+    AlertKind::ContestChanged => "Vérifiez le programme du concours sur FFE Compet.".into(),
+    AlertKind::EngagementLost => "Vérifiez votre engagement sur FFE Compet (retrait, refus ou épreuve annulée).".into(),
+    _ => "Pensez à vous engager rapidement sur FFE Compet.".into(),
+  });
+  lines.join("\n")
+}
+
+/// Email immédiat pour une alerte (OPEN / place libérée), envoyé aux destinataires du cavalier.
+pub async fn send_alert(db_path: std::path::PathBuf, alert: Alert) -> anyhow::Result<()> {
+  let Some(cavalier) = alert.cavalier.clone().filter(|c| !c.trim().is_empty()) else {
+    return Ok(());
+  };
+
+  let (settings, to) = {
+    let conn = Connection::open(&db_path)?;
+    (load_settings(&conn), db::recipients_for_rider(&conn, &cavalier)?)
+  };
+  if !settings.enabled {
+    return Ok(());
+  }
+
+  send(&settings, &to, &format!("[FFE] {}", alert.title()), alert_body(&alert)).await
+}
+
+fn digest_body(cavalier: &str, rows: &[DigestRow]) -> String {
+  let mut out = format!("Récapitulatif de la watchlist — {cavalier}\n\n");
+  for r in rows {
+    out.push_str(&format!("• {} : {}\n", r.label, r.last_status));
+    if !r.statuses.is_empty() {
+      out.push_str(&format!("  24h : {} vérif(s), statuts {}\n", r.checks, r.statuses.join(" → ")));
+    }
+    if let Some(ts) = r.last_checked_at.and_then(|t| chrono::DateTime::from_timestamp(t, 0)) {
+      let local = ts.with_timezone(&chrono::Local);
+      out.push_str(&format!("  Dernière vérif : {}\n", local.format("%d/%m %H:%M")));
+    }
+    if let Some(e) = r.last_error.as_deref().filter(|e| !e.is_empty()) {
+      out.push_str(&format!("  Erreur : {e}\n"));
+    }
+    out.push_str(&format!("  {}\n", r.url));
+  }
+  out
+}
+
+/// Clé `settings` de la date du dernier récapitulatif envoyé à un cavalier.
+fn last_digest_key(cavalier: &str) -> String {
+  format!("{LAST_DIGEST_KEY}:{}", cavalier.trim().to_lowercase())
+}
+
+/// Envoie le récapitulatif (24 dernières heures) à chaque cavalier ayant des destinataires.
+/// Avec `day`, les cavaliers déjà servis ce jour-là sont sautés et chaque envoi réussi est
+/// marqué: un cavalier en échec n'empêche pas les autres et sera retenté seul. Err (avec le
+/// nombre de récapitulatifs partis) dès qu'un cavalier est en échec.
+pub async fn send_digest(db_path: &std::path::Path, day: Option<&str>) -> anyhow::Result<usize> {
+  let since = chrono::Utc::now().timestamp() - 24 * 3600;
+
+  let (settings, batches) = {
+    let conn = Connection::open(db_path)?;
+    let mut batches = Vec::new();
+    for cavalier in db::riders_with_recipients(&conn)? {
+      if let Some(day) = day {
+        let last: Option<String> = db::get_setting(&conn, &last_digest_key(&cavalier)).ok().flatten();
+        if last.as_deref() == Some(day) { continue; }
+      }
+      let rows = db::digest_rows(&conn, &cavalier, since)?;
+      let to = db::recipients_for_rider(&conn, &cavalier)?;
+      batches.push((cavalier, rows, to));
+    }
+    (load_settings(&conn), batches)
+  };
+
+  let mut sent = 0;
+  let mut errors = Vec::new();
+  for (cavalier, rows, to) in batches {
+    if !rows.is_empty() {
+      let subject = format!("[FFE] Récapitulatif quotidien — {cavalier}");
+      if let Err(e) = send(&settings, &to, &subject, digest_body(&cavalier, &rows)).await {
+        errors.push(format!("{cavalier}: {e:#}"));
+        continue;
+      }
+      sent += 1;
+    }
+    // Rien à signaler compte aussi comme servi pour la journée
+    if let Some(day) = day {
+      let conn = Connection::open(db_path)?;
+      db::set_setting(&conn, &last_digest_key(&cavalier), &day)?;
+    }
+  }
+  if !errors.is_empty() {
+    anyhow::bail!("récapitulatif non envoyé ({sent} envoyé(s)): {}", errors.join(" ; "));
+  }
+  Ok(sent)
+}
+
+/// Boucle du récapitulatif quotidien: vérifie chaque minute si l'heure d'envoi est atteinte
+/// (une seule fois par jour et par cavalier, les dates d'envoi sont gardées dans `settings`).
+/// Les cavaliers en échec sont retentés au plus tous les quarts d'heure; les erreurs sont
+/// remontées par `on_error`.
+pub async fn digest_loop(db_path: std::path::PathBuf, on_error: Arc<dyn Fn(String) + Send + Sync>) {
+  const RETRY: Duration = Duration::from_secs(15 * 60);
+  let mut last_attempt: Option<std::time::Instant> = None;
+  loop {
+    tokio::time::sleep(Duration::from_secs(60)).await;
+
+    let now = chrono::Local::now();
+    let today = now.format("%Y-%m-%d").to_string();
+
+    let due = match Connection::open(&db_path) {
+      Ok(conn) => {
+        let s = load_settings(&conn);
+        let last: Option<String> = db::get_setting(&conn, LAST_DIGEST_KEY).ok().flatten();
+        s.enabled
+          && s.digest_enabled
+          && chrono::Timelike::hour(&now) >= s.digest_hour
+          && last.as_deref() != Some(today.as_str())
+      }
+      Err(_) => false,
+    };
+    if !due || last_attempt.is_some_and(|t| t.elapsed() < RETRY) { continue; }
+    last_attempt = Some(std::time::Instant::now());
+
+    match send_digest(&db_path, Some(&today)).await {
+      // Tous les cavaliers sont servis (ceux en échec n'ont pas leur marqueur)
+      Ok(_) => {
+        if let Ok(conn) = Connection::open(&db_path) {
+          let _ = db::set_setting(&conn, LAST_DIGEST_KEY, &today);
+        }
+        last_attempt = None;
+      }
+      Err(e) => on_error(format!("{e:#}")),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::sync::Mutex;
+  use base64::{engine::general_purpose::STANDARD as B64, Engine};
+  use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+  use tokio::net::TcpListener;
+  use crate::models::{AddTargetPayload, WatchKind};
+
+  #[derive(Debug, Default)]
+  struct Received {
+    from: String,
+    to: Vec<String>,
+    data: String,
+  }
+
+  impl Received {
+    /// En-tête déplié, mots encodés RFC 2047 (base64) décodés.
+    fn header(&self, name: &str) -> String {
+      let head = self.data.split("\n\n").next().unwrap_or("").replace("\n ", " ").replace("\n\t", " ");
+      let prefix = format!("{}:", name.to_ascii_lowercase());
+      let raw = head.lines().find(|l| l.to_ascii_lowercase().starts_with(&prefix)).map(|l| l[prefix.len()..].trim().to_string()).unwrap_or_default();
+      // Les blancs entre deux mots encodés ne comptent pas
+      let mut out = String::new();
+      let mut prev_encoded = None;
+      for word in raw.split(' ').filter(|w| !w.is_empty()) {
+        let encoded = word.strip_prefix("=?utf-8?b?").or_else(|| word.strip_prefix("=?UTF-8?B?")).and_then(|w| w.strip_suffix("?="));
+        if prev_encoded.is_some() && !(prev_encoded == Some(true) && encoded.is_some()) {
+          out.push(' ');
+        }
+        match encoded {
+          Some(b64) => out.push_str(&String::from_utf8(B64.decode(b64).unwrap()).unwrap()),
+          None => out.push_str(word),
+        }
+        prev_encoded = Some(encoded.is_some());
+      }
+      out
+    }
+
+    /// Corps décodé (7bit / 8bit, quoted-printable ou base64).
+    fn body(&self) -> String {
+      let body = self.data.split_once("\n\n").map(|(_, b)| b).unwrap_or("");
+      match self.header("Content-Transfer-Encoding").to_ascii_lowercase().as_str() {
+        "base64" => String::from_utf8(B64.decode(body.lines().collect::<String>()).unwrap()).unwrap(),
+        "quoted-printable" => {
+          let mut bytes = Vec::new();
+          for line in body.lines() {
+            let (line, soft) = match line.strip_suffix('=') { Some(l) => (l, true), None => (line, false) };
+            let mut it = line.bytes();
+            while let Some(b) = it.next() {
+              if b == b'=' {
+                let hex = [it.next().unwrap(), it.next().unwrap()];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).unwrap(), 16).unwrap());
+              } else {
+                bytes.push(b);
+              }
+            }
+            if !soft { bytes.push(b'\n'); }
+          }
+          String::from_utf8(bytes).unwrap()
+        }
+        _ => body.to_string(),
+      }
+    }
+  }
+
+  type Inbox = Arc<Mutex<Vec<Received>>>;
+
+  fn addr(line: &str) -> String {
+    line.split_once('<').and_then(|(_, r)| r.split_once('>')).map(|(a, _)| a.to_string()).unwrap_or_default()
+  }
+
+  /// "Sink" SMTP local minimal (ni TLS ni AUTH) qui garde les messages reçus.
+  async fn smtp_sink() -> (u16, Inbox) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let inbox: Inbox = Arc::default();
+    let store = inbox.clone();
+    tokio::spawn(async move {
+      while let Ok((sock, _)) = listener.accept().await {
+        let store = store.clone();
+        tokio::spawn(async move {
+          let (r, mut w) = sock.into_split();
+          let mut lines = BufReader::new(r).lines();
+          w.write_all(b"220 sink ESMTP\r\n").await.unwrap();
+          let mut cur = Received::default();
+          while let Ok(Some(line)) = lines.next_line().await {
+            let cmd = line.to_ascii_uppercase();
+            let reply: &[u8] = if cmd.starts_with("EHLO") || cmd.starts_with("HELO") {
+              b"250-sink\r\n250 8BITMIME\r\n"
+            } else if cmd.starts_with("MAIL FROM:") {
+              cur.from = addr(&line);
+              b"250 OK\r\n"
+            } else if cmd.starts_with("RCPT TO:") {
+              cur.to.push(addr(&line));
+              b"250 OK\r\n"
+            } else if cmd == "DATA" {
+              w.write_all(b"354 Go ahead\r\n").await.unwrap();
+              while let Ok(Some(l)) = lines.next_line().await {
+                if l == "." { break; }
+                cur.data.push_str(l.strip_prefix('.').filter(|_| l.starts_with("..")).unwrap_or(&l));
+                cur.data.push('\n');
+              }
+              store.lock().unwrap().push(std::mem::take(&mut cur));
+              b"250 Queued\r\n"
+            } else if cmd == "QUIT" {
+              let _ = w.write_all(b"221 Bye\r\n").await;
+              break;
+            } else {
+              b"250 OK\r\n"
+            };
+            w.write_all(reply).await.unwrap();
+          }
+        });
+      }
+    });
+    (port, inbox)
+  }
+
+  fn settings(port: u16) -> SmtpSettings {
+    SmtpSettings { enabled: true, host: "127.0.0.1".into(), port, security: SmtpSecurity::None, ..SmtpSettings::default() }
+  }
+
+  /// Base SQLite jetable dans le répertoire temporaire.
+  fn temp_db(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("ffe_mailer_{name}_{}.sqlite", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    db::init_db(&Connection::open(&path).unwrap()).unwrap();
+    path
+  }
+
+  fn target(conn: &Connection, label: &str, cavalier: &str) -> i64 {
+    db::add_target(conn, AddTargetPayload {
+      label: label.into(),
+      url: "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=2".into(),
+      cheval: None,
+      cavalier: Some(cavalier.into()),
+      interval_normal_sec: None,
+      interval_hot_sec: None,
+      hot_from: None,
+      hot_to: None,
+      account: None,
+      kind: WatchKind::Status,
+      check_engaged: false,
+    })
+    .unwrap();
+    conn.last_insert_rowid()
+  }
+
+  #[tokio::test]
+  async fn send_reaches_sink() {
+    let (port, inbox) = smtp_sink().await;
+    let to = vec!["marie@example.com".to_string(), "coach@example.com".to_string()];
+    send(&settings(port), &to, "[FFE] Test", "Bonjour,\nÉpreuve 2 ouverte.".into()).await.unwrap();
+
+    let inbox = inbox.lock().unwrap();
+    assert_eq!(inbox.len(), 1);
+    let m = &inbox[0];
+    assert_eq!(m.from, "ffe-pre-engage@localhost");
+    assert_eq!(m.to, to);
+    assert_eq!(m.header("Subject"), "[FFE] Test");
+    assert_eq!(m.body().trim_end(), "Bonjour,\nÉpreuve 2 ouverte.");
+  }
+
+  #[tokio::test]
+  async fn send_checks_addresses() {
+    let (port, inbox) = smtp_sink().await;
+    // Aucun destinataire: pas de connexion
+    send(&settings(port), &[], "[FFE] Test", "x".into()).await.unwrap();
+    assert!(send(&settings(port), &["pas-une-adresse".into()], "[FFE] Test", "x".into()).await.is_err());
+    let bad_from = SmtpSettings { from: "".into(), ..settings(port) };
+    assert!(send(&bad_from, &["marie@example.com".into()], "[FFE] Test", "x".into()).await.is_err());
+    assert!(inbox.lock().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn alert_email_for_rider() {
+    let (port, inbox) = smtp_sink().await;
+    let path = temp_db("alert");
+    {
+      let conn = Connection::open(&path).unwrap();
+      db::set_setting(&conn, SETTINGS_KEY, &settings(port)).unwrap();
+      db::add_email_recipient(&conn, "Marie Dupont", "marie@example.com").unwrap();
+    }
+    let alert = Alert {
+      target_id: 1,
+      kind: AlertKind::EngagementLost,
+      label: "CSO Lamotte — Epreuve 2".into(),
+      url: "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=2".into(),
+      cavalier: Some("Marie Dupont".into()),
+      cheval: Some("Quartz du Bois".into()),
+      account: None,
+      detail: Some("Le couple n'apparaît plus dans la liste des engagés (épreuve FULL).".into()),
+    };
+    send_alert(path.clone(), alert.clone()).await.unwrap();
+    // Sans cavalier: pas d'email
+    send_alert(path.clone(), Alert { cavalier: None, ..alert.clone() }).await.unwrap();
+
+    let inbox = inbox.lock().unwrap();
+    assert_eq!(inbox.len(), 1);
+    assert_eq!(inbox[0].to, vec!["marie@example.com".to_string()]);
+    assert_eq!(inbox[0].header("Subject"), "[FFE] Engagement disparu — CSO Lamotte — Epreuve 2");
+    assert_eq!(inbox[0].body().trim_end(), [
+      "Engagement disparu — CSO Lamotte — Epreuve 2",
+      "",
+      "Cavalier : Marie Dupont",
+      "Cheval : Quartz du Bois",
+      "",
+      "Le couple n'apparaît plus dans la liste des engagés (épreuve FULL).",
+      "Lien : https://ffecompet.ffe.com/concours/202635080?watch_epreuve=2",
+      "",
+      "Vérifiez votre engagement sur FFE Compet (retrait, refus ou épreuve annulée).",
+    ].join("\n"));
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[tokio::test]
+  async fn digest_once_per_rider_and_day() {
+    let (port, inbox) = smtp_sink().await;
+    let path = temp_db("digest");
+    let now = chrono::Utc::now().timestamp();
+    {
+      let conn = Connection::open(&path).unwrap();
+      db::set_setting(&conn, SETTINGS_KEY, &settings(port)).unwrap();
+      let id = target(&conn, "Epreuve 2", "Marie Dupont");
+      for (dt, status, err) in [(300, "CLOSED", None), (200, "CLOSED", None), (100, "OPEN", Some("HTTP 503"))] {
+        db::update_status(&conn, id, status, now - dt, err).unwrap();
+      }
+      target(&conn, "Epreuve 5", "Paul Martin");
+      // Cavalier sans destinataire: pas de récapitulatif
+      target(&conn, "Epreuve 7", "Anne Petit");
+      db::add_email_recipient(&conn, "Marie Dupont", "marie@example.com").unwrap();
+      db::add_email_recipient(&conn, "Paul Martin", "paul@example.com").unwrap();
+    }
+
+    assert_eq!(send_digest(&path, Some("2026-10-18")).await.unwrap(), 2);
+    {
+      let inbox = inbox.lock().unwrap();
+      assert_eq!(inbox.len(), 2);
+      let marie = inbox.iter().find(|m| m.to == ["marie@example.com"]).expect("récapitulatif de Marie Dupont");
+      assert_eq!(marie.header("Subject"), "[FFE] Récapitulatif quotidien — Marie Dupont");
+      let body = marie.body();
+      assert!(body.starts_with("Récapitulatif de la watchlist — Marie Dupont\n\n• Epreuve 2 : OPEN\n"), "{body}");
+      assert!(body.contains("  24h : 3 vérif(s), statuts CLOSED → OPEN\n"), "{body}");
+      assert!(body.contains("  Erreur : HTTP 503\n"), "{body}");
+      assert!(!body.contains("Epreuve 5"), "{body}");
+      let paul = inbox.iter().find(|m| m.to == ["paul@example.com"]).expect("récapitulatif de Paul Martin");
+      assert!(paul.body().contains("• Epreuve 5 :"), "{}", paul.body());
+    }
+    {
+      let conn = Connection::open(&path).unwrap();
+      for rider in ["Marie Dupont", "Paul Martin"] {
+        let day: Option<String> = db::get_setting(&conn, &last_digest_key(rider)).unwrap();
+        assert_eq!(day.as_deref(), Some("2026-10-18"), "{rider}");
+      }
+    }
+
+    // Même jour: rien n'est renvoyé; jour suivant: nouvel envoi
+    assert_eq!(send_digest(&path, Some("2026-10-18")).await.unwrap(), 0);
+    assert_eq!(inbox.lock().unwrap().len(), 2);
+    assert_eq!(send_digest(&path, Some("2026-10-19")).await.unwrap(), 2);
+    assert_eq!(inbox.lock().unwrap().len(), 4);
+    let _ = std::fs::remove_file(&path);
+  }
+
+  #[tokio::test]
+  async fn digest_failure_is_retried_alone() {
+    let (port, inbox) = smtp_sink().await;
+    let path = temp_db("digest_fail");
+    {
+      let conn = Connection::open(&path).unwrap();
+      db::set_setting(&conn, SETTINGS_KEY, &settings(port)).unwrap();
+      target(&conn, "Epreuve 2", "Marie Dupont");
+      target(&conn, "Epreuve 5", "Paul Martin");
+      db::add_email_recipient(&conn, "Marie Dupont", "marie@example.com").unwrap();
+      db::add_email_recipient(&conn, "Paul Martin", "pas-une-adresse").unwrap();
+    }
+
+    let err = send_digest(&path, Some("2026-10-18")).await.unwrap_err().to_string();
+    assert!(err.contains("1 envoyé(s)") && err.contains("Paul Martin"), "{err}");
+    assert_eq!(inbox.lock().unwrap().len(), 1);
+    {
+      let conn = Connection::open(&path).unwrap();
+      let marie: Option<String> = db::get_setting(&conn, &last_digest_key("marie dupont")).unwrap();
+      let paul: Option<String> = db::get_setting(&conn, &last_digest_key("Paul Martin")).unwrap();
+      assert_eq!((marie.as_deref(), paul), (Some("2026-10-18"), None));
+    }
+
+    // Nouvel essai: seul le cavalier en échec est retenté
+    assert!(send_digest(&path, Some("2026-10-18")).await.is_err());
+    assert_eq!(inbox.lock().unwrap().len(), 1);
+    let _ = std::fs::remove_file(&path);
+  }
+}
@@ -5,23 +5,36 @@ mod detect;
 mod models;
 mod watcher;
 mod epreuves;
+mod mailer;
+mod push;
+mod alerts;
+mod session;
+mod cookie_jar;
+mod accounts;
+mod probe;
+mod login;
+mod keepalive;
+mod http;
+mod conditional;
+mod text;
+mod html_entities;
+mod embedded;
+mod label;
+mod contests;
+mod contest_cache;
+mod ffe_url;
+mod engages;
 
 use std::sync::{Arc, Mutex};
 use rusqlite::Connection;
-use models::{AddTargetPayload, Target};
+use models::{AddPushChannelPayload, AddTargetPayload, Alert, AlertKind, ContestDetail, ContestSearch, ContestSearchResult, EmailRecipient, FfeAccount, HttpSettings, MuteState, SessionHealth, PushChannel, QuietHours, QueuedSummary, SmtpSettings, Target, TargetEvent, WatchContestPayload, WatchContestResult, WatchKind};
 use watcher::Watcher;
 use tauri::{Manager, State, Emitter, WebviewUrl, WebviewWindowBuilder, Position, LogicalPosition};
 use tauri_plugin_opener::OpenerExt;
 use std::sync::OnceLock;
 
-// Cookie jar partagé (session FFE)
-static FFE_COOKIES: OnceLock<Mutex<String>> = OnceLock::new();
-
-
 // Debug du dernier essai de connexion (pour affichage côté UI)
 static LAST_LOGIN_DEBUG: OnceLock<Mutex<String>> = OnceLock::new();
-// Dernier "probe" (href/title) renvoyé par une WebView
-static PROBE_STATE: OnceLock<Mutex<(String, String)>> = OnceLock::new();
 
 fn set_login_debug_inner(msg: impl Into<String>) {
   let cell = LAST_LOGIN_DEBUG.get_or_init(|| Mutex::new(String::new()));
@@ -42,28 +55,25 @@ fn get_login_debug() -> String {
     .unwrap_or_else(|| "no debug".to_string())
 }
 
-#[tauri::command]
-fn set_probe(href: String, title: String) -> Result<(), String> {
-  let cell = PROBE_STATE.get_or_init(|| Mutex::new((String::new(), String::new())));
-  *cell.lock().unwrap() = (href, title);
-  Ok(())
+/// Échec d'une tâche de fond (email, push, sauvegarde de session): gardé comme dernier debug
+/// et signalé à l'UI (`app:background_error`).
+fn report_background_error(app: &tauri::AppHandle, source: &str, msg: String) {
+  set_login_debug_inner(format!("{source}: {msg}"));
+  let _ = app.emit("app:background_error", serde_json::json!({ "source": source, "message": msg }));
 }
 
-fn take_probe() -> Option<(String, String)> {
-  PROBE_STATE.get().map(|m| {
-    let mut g = m.lock().unwrap();
-    let out = (g.0.clone(), g.1.clone());
-    // ✅ évite de relire une ancienne valeur (race condition)
-    g.0.clear();
-    g.1.clear();
-    out
-  })
+#[tauri::command]
+fn set_probe(probe: probe::LoginProbe) -> Result<(), String> {
+  probe::resolve(probe);
+  Ok(())
 }
 
 
 struct AppState {
   watcher: Arc<Watcher>,
   handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
+  // Tâches annexes lancées avec le watcher (récapitulatif, fin de pause, ...)
+  background: Mutex<Vec<tokio::task::JoinHandle<()>>>,
   db_path: Mutex<Option<std::path::PathBuf>>,
 }
 
@@ -92,12 +102,41 @@ fn list_targets(app: tauri::AppHandle, state: State<AppState>) -> Result<Vec<Tar
 }
 
 #[tauri::command]
-fn add_target(app: tauri::AppHandle, state: State<AppState>, payload: AddTargetPayload) -> Result<(), String> {
+fn add_target(app: tauri::AppHandle, state: State<AppState>, mut payload: AddTargetPayload) -> Result<(), String> {
+  // Compte par défaut stocké à NULL
+  let account = resolve_account(payload.account.take())?;
+  ensure_known_account(&app, &account)?;
+  payload.account = (account != accounts::DEFAULT_ACCOUNT).then_some(account);
+  // URL canonique du concours / de l'épreuve (refus des URLs non FFE ou sans n° de concours)
+  let mut wanted = ffe_url::normalize(&payload.url)?;
+  // Surveillance du programme: toujours sur la page du concours
+  if payload.kind == WatchKind::Contest {
+    wanted.epreuve = None;
+  }
+  payload.url = wanted.canonical();
+  payload.cavalier = payload.cavalier.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+  if payload.check_engaged {
+    if let Some(e) = engages::check_error(payload.cavalier.as_deref(), payload.cheval.as_deref()) {
+      return Err(e);
+    }
+  }
+
   let db_path = get_db_path(&app,&state)?;
   let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  if let Some(t) = db::find_duplicate(&conn, &payload).map_err(|e| e.to_string())? {
+    return Err(format!("Cible déjà surveillée: {}", t.label));
+  }
   db::add_target(&conn, payload).map_err(|e| e.to_string())
 }
 
+/// Historique d'une cible (statuts, différences du programme pour une cible concours).
+#[tauri::command]
+fn get_target_history(app: tauri::AppHandle, state: State<AppState>, id: i64, limit: Option<i64>) -> Result<Vec<TargetEvent>, String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::target_events(&conn, id, limit.unwrap_or(100).clamp(1, 1000)).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 fn delete_target(app: tauri::AppHandle, state: State<AppState>, id: i64) -> Result<(), String> {
   let db_path = get_db_path(&app,&state)?;
@@ -115,19 +154,58 @@ async fn start_watcher(app: tauri::AppHandle, state: State<'_, AppState>) -> Res
 
   // callback notification propre
   let app_handle = app.clone();
-let notify_cb: Arc<dyn Fn(i64, String, String) + Send + Sync> = Arc::new(move |target_id, label, url| {
+  let alert_db = db_path.clone();
+let notify_cb: Arc<dyn Fn(Alert) + Send + Sync> = Arc::new(move |alert| {
+  // Session expirée: on tente de la revalider via la WebView de login, même si l'alerte est en pause
+  if alert.kind == AlertKind::SessionExpired {
+    let account = accounts::normalize(alert.account.as_deref());
+    tauri::async_runtime::spawn(revalidate_ffe_session(app_handle.clone(), account));
+  }
+
+  // Quiet hours / snooze: l'alerte est mise en file et résumée à la fin de la pause
+  let now = chrono::Utc::now().timestamp();
+  if let Ok(conn) = Connection::open(&alert_db) {
+    if let Ok(true) = alerts::defer_if_muted(&conn, &alert, now) {
+      return;
+    }
+  }
+
   let _ = app_handle.emit(
     "target_open",
-    serde_json::json!({ "id": target_id, "label": label, "url": url })
+    serde_json::json!({ "id": alert.target_id, "label": alert.label, "url": alert.url, "kind": alert.kind })
   );
+
+  // Email + push aux destinataires du cavalier / de la cible (sans bloquer le watcher)
+  let error_handle = app_handle.clone();
+  let delivery_db = alert_db.clone();
+  tokio::spawn(async move {
+    if let Err(e) = alerts::deliver_remote(delivery_db, alert).await {
+      report_background_error(&error_handle, "alertes", format!("{e:#}"));
+    }
+  });
 });
 
+  let summary_handle = app.clone();
+  let summary_cb: Arc<dyn Fn(Vec<QueuedSummary>) + Send + Sync> = Arc::new(move |summaries| {
+    let _ = summary_handle.emit("alerts:summary", summaries);
+  });
+
   let watcher = state.watcher.clone();
+  let bg_db = db_path.clone();
   let mut handle_guard = state.handle.lock().unwrap();
   let join = tokio::spawn(async move {
     watcher.run_loop(db_path, notify_cb).await;
   });
   *handle_guard = Some(join);
+
+  let mut bg = state.background.lock().unwrap();
+  for h in bg.drain(..) { h.abort(); }
+  let error_handle = app.clone();
+  let on_digest_error: Arc<dyn Fn(String) + Send + Sync> = Arc::new(move |e| report_background_error(&error_handle, "récapitulatif email", e));
+  bg.push(tokio::spawn(mailer::digest_loop(bg_db.clone(), on_digest_error)));
+  let error_handle = app.clone();
+  let on_flush_error: Arc<dyn Fn(String) + Send + Sync> = Arc::new(move |e| report_background_error(&error_handle, "alertes", e));
+  bg.push(tokio::spawn(alerts::flush_loop(bg_db, summary_cb, on_flush_error)));
   Ok(())
 }
 
@@ -138,9 +216,176 @@ async fn stop_watcher(state: State<'_, AppState>) -> Result<(), String> {
   if let Some(h) = handle_guard.take() {
     h.abort();
   }
+  for h in state.background.lock().unwrap().drain(..) {
+    h.abort();
+  }
   Ok(())
 }
 
+// ===================== QUIET HOURS / SNOOZE =====================
+
+#[tauri::command]
+fn get_mute_state(app: tauri::AppHandle, state: State<AppState>) -> Result<MuteState, String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  alerts::mute_state(&conn, chrono::Utc::now().timestamp()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_quiet_hours(app: tauri::AppHandle, state: State<AppState>, settings: QuietHours) -> Result<(), String> {
+  if alerts::parse_hhmm(&settings.from).is_none() || alerts::parse_hhmm(&settings.to).is_none() {
+    return Err("Heures invalides (format HH:MM).".into());
+  }
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::set_setting(&conn, alerts::QUIET_HOURS_KEY, &settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn snooze_target(app: tauri::AppHandle, state: State<AppState>, id: i64, minutes: i64) -> Result<i64, String> {
+  if minutes <= 0 {
+    return Err("Durée de pause invalide.".into());
+  }
+  let until = chrono::Utc::now().timestamp() + minutes * 60;
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::snooze_target(&conn, id, until).map_err(|e| e.to_string())?;
+  Ok(until)
+}
+
+#[tauri::command]
+fn unsnooze_target(app: tauri::AppHandle, state: State<AppState>, id: i64) -> Result<(), String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::unsnooze_target(&conn, id).map_err(|e| e.to_string())
+}
+
+// ===================== CLIENT HTTP =====================
+
+#[tauri::command]
+fn get_http_settings() -> HttpSettings {
+  http::settings()
+}
+
+#[tauri::command]
+fn set_http_settings(app: tauri::AppHandle, state: State<AppState>, settings: HttpSettings) -> Result<(), String> {
+  http::validate(&settings)?;
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::set_setting(&conn, http::SETTINGS_KEY, &settings).map_err(|e| e.to_string())?;
+  http::configure(settings);
+  Ok(())
+}
+
+// ===================== NOTIFICATIONS EMAIL =====================
+
+#[tauri::command]
+fn get_smtp_settings(app: tauri::AppHandle, state: State<AppState>) -> Result<SmtpSettings, String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  Ok(mailer::load_settings(&conn))
+}
+
+#[tauri::command]
+fn set_smtp_settings(app: tauri::AppHandle, state: State<AppState>, settings: SmtpSettings) -> Result<(), String> {
+  if settings.digest_hour > 23 {
+    return Err("Heure du récapitulatif invalide (0-23).".into());
+  }
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::set_setting(&conn, mailer::SETTINGS_KEY, &settings).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn list_email_recipients(app: tauri::AppHandle, state: State<AppState>) -> Result<Vec<EmailRecipient>, String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::list_email_recipients(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn add_email_recipient(app: tauri::AppHandle, state: State<AppState>, cavalier: String, email: String) -> Result<(), String> {
+  if cavalier.trim().is_empty() || !email.contains('@') {
+    return Err("Cavalier et adresse email valides requis.".into());
+  }
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::add_email_recipient(&conn, &cavalier, &email).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_email_recipient(app: tauri::AppHandle, state: State<AppState>, id: i64) -> Result<(), String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::delete_email_recipient(&conn, id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn send_test_email(app: tauri::AppHandle, state: State<'_, AppState>, to: String) -> Result<(), String> {
+  let db_path = get_db_path(&app,&state)?;
+  let settings = {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    mailer::load_settings(&conn)
+  };
+  mailer::send(&settings, &[to], "[FFE] Email de test", "La configuration SMTP fonctionne.".into())
+    .await
+    .map_err(|e| format!("{e:#}"))
+}
+
+#[tauri::command]
+async fn send_digest_now(app: tauri::AppHandle, state: State<'_, AppState>) -> Result<usize, String> {
+  let db_path = get_db_path(&app,&state)?;
+  mailer::send_digest(&db_path, None).await.map_err(|e| format!("{e:#}"))
+}
+
+// ===================== NOTIFICATIONS PUSH (ntfy / Gotify) =====================
+
+#[tauri::command]
+fn list_push_channels(app: tauri::AppHandle, state: State<AppState>) -> Result<Vec<PushChannel>, String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::list_push_channels(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn add_push_channel(app: tauri::AppHandle, state: State<AppState>, payload: AddPushChannelPayload) -> Result<i64, String> {
+  let server = payload.server_url.trim();
+  if !(server.starts_with("http://") || server.starts_with("https://")) {
+    return Err("URL du serveur push invalide (http:// ou https://).".into());
+  }
+  if payload.cavalier.as_deref().map(str::trim).unwrap_or("").is_empty() && payload.target_id.is_none() {
+    return Err("Le canal doit viser un cavalier ou une cible.".into());
+  }
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::add_push_channel(&conn, payload).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn delete_push_channel(app: tauri::AppHandle, state: State<AppState>, id: i64) -> Result<(), String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::delete_push_channel(&conn, id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn test_push_channel(app: tauri::AppHandle, state: State<'_, AppState>, id: i64) -> Result<(), String> {
+  let db_path = get_db_path(&app,&state)?;
+  let channel = {
+    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    db::get_push_channel(&conn, id).map_err(|e| e.to_string())?
+  }.ok_or("canal push introuvable")?;
+
+  let msg = push::PushMessage {
+    title: "FFE Pre-Engage — test".into(),
+    message: "Les notifications push fonctionnent.".into(),
+    severity: models::Severity::Info,
+    click_url: None,
+  };
+  let client = push::client().map_err(|e| format!("HTTP client error: {e:#}"))?;
+  push::send(&client, &channel, &msg).await.map_err(|e| format!("{e:#}"))
+}
+
 #[tauri::command]
 fn open_url(app: tauri::AppHandle, url: String) -> Result<(), String> {
   app.opener()
@@ -149,150 +394,300 @@ fn open_url(app: tauri::AppHandle, url: String) -> Result<(), String> {
 }
 
 
+/// Compte ciblé par une commande (None => compte par défaut).
+fn resolve_account(account: Option<String>) -> Result<String, String> {
+  let account = accounts::normalize(account.as_deref());
+  if account == accounts::DEFAULT_ACCOUNT {
+    return Ok(account);
+  }
+  accounts::validate_name(&account)
+}
+
+/// Applique le store de cookies propre au compte (les comptes nommés ne partagent pas
+/// les cookies de la WebView par défaut).
+fn with_account_store<'a>(
+  builder: WebviewWindowBuilder<'a, tauri::Wry, tauri::AppHandle>,
+  app: &tauri::AppHandle,
+  account: &str,
+) -> Result<WebviewWindowBuilder<'a, tauri::Wry, tauri::AppHandle>, String> {
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  Ok(match accounts::webview_data_dir(&dir, account) {
+    Some(data_dir) => builder.data_directory(data_dir),
+    None => builder,
+  })
+}
+
+/// WebView invisible sur ffecompet (accès au store de cookies du compte sans fenêtre de login).
+fn open_probe_window(app: &tauri::AppHandle, account: &str) -> Result<tauri::WebviewWindow, String> {
+  let url = tauri::Url::parse("https://ffecompet.ffe.com/").map_err(|e| e.to_string())?;
+  let builder = WebviewWindowBuilder::new(app, accounts::probe_label(account), WebviewUrl::External(url))
+    .title("ffe-probe")
+    .visible(false)
+    .skip_taskbar(true)
+    .resizable(false)
+    .decorations(false);
+  with_account_store(builder, app, account)?
+    .build()
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
-fn save_ffe_session(app: tauri::AppHandle) -> Result<usize, String> {
-  // Récupère les cookies du webview "ffe-login" et les stocke en mémoire
+fn save_ffe_session(app: tauri::AppHandle, account: Option<String>) -> Result<usize, String> {
+  let account = resolve_account(account)?;
+  save_account_session(&app, &account)
+}
+
+fn save_account_session(app: &tauri::AppHandle, account: &str) -> Result<usize, String> {
+  // Récupère les cookies du webview de login du compte et les stocke en mémoire
+  let label = accounts::login_label(account);
   let window = app
-    .get_webview_window("ffe-login")
-    .ok_or("fenêtre login introuvable (label: ffe-login)")?;
+    .get_webview_window(&label)
+    .ok_or(format!("fenêtre login introuvable (label: {label})"))?;
 
-  let cookies = window.cookies().map_err(|e| e.to_string())?;
+  // On garde domaine / chemin / secure / expiration: chaque cookie ne sera renvoyé qu'aux URLs concernées
+  let cookies: Vec<cookie_jar::StoredCookie> = window
+    .cookies()
+    .map_err(|e| e.to_string())?
+    .iter()
+    .filter_map(cookie_jar::StoredCookie::from_browser)
+    .collect();
   let count = cookies.len();
 
-  // On construit un header Cookie simple (sans domaine/path), suffisant pour réutiliser la session
-  let cookie_header = cookies
-    .into_iter()
-    .map(|c| c.to_string())
-    .map(|s| s.trim().to_string())
-    .filter(|s| !s.is_empty())
-    // On ne garde que "name=value" (on coupe avant les attributs éventuels)
-    .map(|s| s.split(';').next().unwrap_or("").trim().to_string())
-    .filter(|s| !s.is_empty())
-    .collect::<Vec<_>>()
-    .join("; ");
-
-  let cell = FFE_COOKIES.get_or_init(|| Mutex::new(String::new()));
-  *cell.lock().unwrap() = cookie_header;
+  let jar = accounts::jar(account);
+  // Persistance chiffrée (restaurée au prochain démarrage)
+  if jar.replace_all(cookies) && !jar.is_empty() {
+    // Nouvelle session: l'état du keep-alive repart de zéro
+    keepalive::forget(account);
+    if let Err(e) = persist_ffe_session(app, account) {
+      set_login_debug_inner(format!("session non persistée ({account}): {e}"));
+    }
+  }
 
   // Notifie l'UI que des cookies ont été enregistrés (l'UI doit revalider la connexion via check_ffe_connected)
   let _ = app.emit(
     "ffe:status",
-    serde_json::json!({ "cookieCount": count }),
+    serde_json::json!({ "account": account, "cookieCount": count }),
   );
 
   Ok(count)
 }
 
-#[tauri::command]
-fn is_ffe_connected() -> bool {
-  FFE_COOKIES
-    .get()
-    .map(|m| !m.lock().unwrap().trim().is_empty())
-    .unwrap_or(false)
+fn persist_ffe_session(app: &tauri::AppHandle, account: &str) -> Result<(), String> {
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  session::save_jar(&dir, account, &accounts::jar(account)).map_err(|e| format!("{e:#}"))
 }
 
+/// Recharge la session chiffrée du compte depuis le disque dans son cookie jar, puis la valide
+/// par une requête HTTP authentifiée. Renvoie true si une session valide a été restaurée; le
+/// fichier n'est supprimé que si le serveur renvoie vers la page de connexion.
+async fn restore_ffe_session(app: &tauri::AppHandle, account: &str) -> Result<bool, String> {
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  let cookies = match session::load_jar(&dir, account).map_err(|e| format!("{e:#}"))? {
+    Some(c) if !c.is_empty() => c,
+    _ => return Ok(false),
+  };
+
+  let jar = accounts::jar(account);
+  jar.replace_all(cookies);
+  match keepalive::verify(account).await {
+    Ok(true) => {}
+    Ok(false) => {
+      set_login_debug_inner(format!("session restaurée mais plus valide pour {account} (reconnexion nécessaire)"));
+      jar.clear();
+      let _ = session::clear(&dir, account);
+      return Ok(false);
+    }
+    // Réseau indisponible, page ambiguë: les cookies restent chargés, le keep-alive tranchera
+    Err(e) => return Err(format!("session restaurée non vérifiée: {e}")),
+  }
 
+  let _ = app.emit("ffe:status", serde_json::json!({ "account": account, "connected": true, "restored": true }));
+  Ok(true)
+}
+
+/// Restaure toutes les sessions enregistrées. Renvoie les comptes restaurés.
+async fn restore_all_sessions(app: &tauri::AppHandle) -> Vec<String> {
+  let Ok(dir) = app.path().app_data_dir() else { return Vec::new(); };
+  let mut restored = Vec::new();
+  for account in session::stored_accounts(&dir) {
+    match restore_ffe_session(app, &account).await {
+      Ok(true) => restored.push(account),
+      Ok(false) => {}
+      Err(e) => set_login_debug_inner(format!("restauration session ({account}): {e}")),
+    }
+  }
+  restored
+}
+
+/// Appelé quand le watcher tombe sur la page de login SSO: si la WebView de login du compte est encore
+/// ouverte et connectée, on recopie ses cookies (session rafraîchie); sinon l'UI passe en "non connecté".
+async fn revalidate_ffe_session(app: tauri::AppHandle, account: String) {
+  let mut connected = false;
+  if app.get_webview_window(&accounts::login_label(&account)).is_some() {
+    connected = check_account_connected(&app, &account).await.unwrap_or(false);
+    if connected {
+      let _ = save_account_session(&app, &account);
+    }
+  }
+
+  if !connected {
+    set_login_debug_inner(format!("watcher: session FFE expirée pour {account} (redirection vers la page de connexion)"));
+  }
+  let _ = app.emit("ffe:status", serde_json::json!({ "account": account, "connected": connected, "expired": !connected }));
+}
 
 #[tauri::command]
-async fn check_ffe_connected(app: tauri::AppHandle) -> Result<bool, String> {
-  // 1) Si la fenêtre de login existe, on probe directement dedans (cookies garantis côté navigateur)
-  if let Some(w) = app.get_webview_window("ffe-login") {
-    // Après une connexion "réussie", il peut y avoir des redirections SSO pendant quelques secondes.
-    // On "probe" plusieurs fois avant de conclure à un échec.
-    for _ in 0..30 {
-      let js = r#"
-        try {
-          const txt = (document.body?.innerText || "").toLowerCase();
-          const hasLogout =
-            txt.includes("déconnexion") ||
-            !!document.querySelector("a[href*='logout'], a[href*='deconnexion'], button[aria-label*='déconnexion' i]");
-          window.__TAURI__.core.invoke('set_probe', {
-            href: window.location.href,
-            title: document.title + " | hasLogout=" + (hasLogout ? "true" : "false")
-          });
-        } catch (e) {}
-      "#;
-      let _ = w.eval(js);
+async fn set_session_passphrase(app: tauri::AppHandle, passphrase: Option<String>) -> Result<(), String> {
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  let removed = passphrase.as_deref().is_none_or(str::is_empty);
+  session::set_passphrase(passphrase);
+  // Re-chiffre les sessions courantes avec la nouvelle clé
+  for (account, jar) in accounts::all_jars() {
+    if !jar.is_empty() {
+      // Phrase de passe retirée: la session en mémoire remplace explicitement le fichier protégé
+      if removed {
+        session::clear(&dir, &account).map_err(|e| format!("{e:#}"))?;
+      }
+      persist_ffe_session(&app, &account)?;
+    }
+  }
+  Ok(())
+}
 
-      tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+#[tauri::command]
+async fn unlock_ffe_session(app: tauri::AppHandle, passphrase: String) -> Result<bool, String> {
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  // Vérifiée avant d'être gardée: une phrase fausse ne doit pas servir à re-chiffrer les sessions
+  if let Err(e) = session::verify_passphrase(&dir, &passphrase) {
+    session::set_passphrase(None);
+    return Err(format!("Phrase de passe incorrecte ({e:#})."));
+  }
+  session::set_passphrase(Some(passphrase));
+  Ok(!restore_all_sessions(&app).await.is_empty())
+}
 
-      if let Some((href, title)) = take_probe() {
-        if href.trim().is_empty() {
-          continue;
-        }
+/// Santé de la session (keep-alive): dernière réponse authentifiée, expiration estimée.
+#[tauri::command]
+fn get_session_health(account: Option<String>) -> Result<SessionHealth, String> {
+  Ok(keepalive::health(&resolve_account(account)?))
+}
 
-        let has_logout = title.contains("hasLogout=true");
-        let href_lc = href.to_lowercase();
-        let is_login = href_lc.contains("sso.ffe.com/login")
-          || href_lc.contains("/login")
-          || href_lc.contains("/identification");
+#[tauri::command]
+fn is_ffe_connected(account: Option<String>) -> Result<bool, String> {
+  Ok(!accounts::jar(&resolve_account(account)?).is_empty())
+}
 
-        set_login_debug_inner(format!("probe(retry): href={} | title={}", href, title));
+// ===================== COMPTES FFE =====================
 
-        // ✅ priorité au marqueur DOM "Déconnexion" (session OK), l'URL peut encore être en redirection
-        if has_logout {
-          return Ok(true);
-        }
+#[tauri::command]
+fn list_ffe_accounts(app: tauri::AppHandle, state: State<AppState>) -> Result<Vec<FfeAccount>, String> {
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  let mut names = vec![accounts::DEFAULT_ACCOUNT.to_string()];
+  names.extend(db::list_accounts(&conn).map_err(|e| e.to_string())?);
 
-        // fallback URL
-        if !is_login {
-          return Ok(true);
-        }
-      }
+  names
+    .into_iter()
+    .map(|name| {
+      let targets = db::count_targets_for_account(&conn, &name, name == accounts::DEFAULT_ACCOUNT)
+        .map_err(|e| e.to_string())?;
+      Ok(FfeAccount { has_session: !accounts::jar(&name).is_empty(), name, targets })
+    })
+    .collect()
+}
+
+#[tauri::command]
+fn add_ffe_account(app: tauri::AppHandle, state: State<AppState>, name: String) -> Result<String, String> {
+  let name = accounts::validate_name(&name)?;
+  if name == accounts::DEFAULT_ACCOUNT {
+    return Err("Le compte par défaut existe déjà.".into());
+  }
+  let db_path = get_db_path(&app,&state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::add_account(&conn, &name, chrono::Utc::now().timestamp()).map_err(|e| e.to_string())?;
+  Ok(name)
+}
+
+#[tauri::command]
+async fn delete_ffe_account(app: tauri::AppHandle, state: State<'_, AppState>, name: String) -> Result<(), String> {
+  let name = accounts::validate_name(&name)?;
+  if name == accounts::DEFAULT_ACCOUNT {
+    return Err("Le compte par défaut ne peut pas être supprimé.".into());
+  }
+  let db_path = get_db_path(&app,&state)?;
+  {
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    let n = db::count_targets_for_account(&conn, &name, false).map_err(|e| e.to_string())?;
+    if n > 0 {
+      return Err(format!("{n} cible(s) utilisent encore le compte {name}."));
     }
+  }
 
-    return Ok(false);
+  logout_account(&app, &name).await?;
+  accounts::forget_jar(&name);
+  http::forget(&name);
+  // Store de cookies WebView du compte
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  if let Some(data_dir) = accounts::webview_data_dir(&dir, &name) {
+    let _ = std::fs::remove_dir_all(data_dir);
   }
 
-  // 2) Sinon, on ouvre une WebView invisible de test sur ffecompet (même store navigateur)
-  let url = tauri::Url::parse("https://ffecompet.ffe.com/").map_err(|e| e.to_string())?;
-  let w = WebviewWindowBuilder::new(&app, "ffe-probe", WebviewUrl::External(url))
-    .title("ffe-probe")
-    .visible(false)
-    .skip_taskbar(true)
-    .resizable(false)
-    .decorations(false)
-    .build()
-    .map_err(|e| e.to_string())?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::delete_account(&conn, &name).map_err(|e| e.to_string())
+}
 
-  tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
+/// Vérifie qu'un compte nommé a bien été créé (évite les sessions orphelines).
+fn ensure_known_account(app: &tauri::AppHandle, account: &str) -> Result<(), String> {
+  if account == accounts::DEFAULT_ACCOUNT {
+    return Ok(());
+  }
+  let state = app.state::<AppState>();
+  let conn = Connection::open(get_db_path(app, &state)?).map_err(|e| e.to_string())?;
+  if db::list_accounts(&conn).map_err(|e| e.to_string())?.iter().any(|a| a == account) {
+    Ok(())
+  } else {
+    Err(format!("Compte FFE inconnu: {account}"))
+  }
+}
 
-  let js = r#"
-    try {
-      const txt = (document.body?.innerText || "").toLowerCase();
-      const hasLogout =
-        txt.includes("déconnexion") ||
-        !!document.querySelector("a[href*='logout'], a[href*='deconnexion'], button[aria-label*='déconnexion' i]");
-      window.__TAURI__.core.invoke('set_probe', {
-        href: window.location.href,
-        title: document.title + " | hasLogout=" + (hasLogout ? "true" : "false")
-      });
-    } catch (e) {}
-  "#;
-  let _ = w.eval(js);
-  tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+#[tauri::command]
+async fn check_ffe_connected(app: tauri::AppHandle, account: Option<String>) -> Result<bool, String> {
+  let account = resolve_account(account)?;
+  check_account_connected(&app, &account).await
+}
 
-  let (href, title) = take_probe().unwrap_or_else(|| ("".into(), "".into()));
-  let _ = w.close();
+async fn check_account_connected(app: &tauri::AppHandle, account: &str) -> Result<bool, String> {
+  // 1) Si la fenêtre de login du compte existe, on probe directement dedans (cookies garantis côté navigateur)
+  if let Some(w) = app.get_webview_window(&accounts::login_label(account)) {
+    // Après une connexion "réussie", il peut y avoir des redirections SSO pendant quelques secondes.
+    // On "probe" plusieurs fois avant de conclure à un échec.
+    for _ in 0..30 {
+      let Some(p) = probe::run(&w, std::time::Duration::from_millis(500)).await else { continue; };
+      set_login_debug_inner(format!("probe(retry:{account}): {}", p.summary()));
+      if p.is_connected() {
+        return Ok(true);
+      }
+      tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+    }
 
-  if href.trim().is_empty() {
-    set_login_debug_inner("probe(ffe-probe): no href returned".to_string());
     return Ok(false);
   }
 
-  let has_logout = title.contains("hasLogout=true");
-  let href_lc = href.to_lowercase();
+  // 2) Sinon, on ouvre une WebView invisible de test sur ffecompet (même store navigateur que le compte)
+  let w = open_probe_window(app, account)?;
 
-  let connected = if has_logout {
-    true
-  } else {
-    !(href_lc.contains("sso.ffe.com/login")
-      || href_lc.contains("/login")
-      || href_lc.contains("/identification"))
+  tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
+  let res = probe::run(&w, std::time::Duration::from_secs(3)).await;
+  let _ = w.close();
+
+  let Some(p) = res else {
+    set_login_debug_inner(format!("probe(ffe-probe:{account}): pas de réponse"));
+    return Ok(false);
   };
 
+  let connected = p.is_connected();
   if !connected {
-    set_login_debug_inner(format!("probe(ffe-probe): href={} | title={}", href, title));
+    set_login_debug_inner(format!("probe(ffe-probe:{account}): {}", p.summary()));
   }
 
   Ok(connected)
@@ -301,191 +696,195 @@ async fn check_ffe_connected(app: tauri::AppHandle) -> Result<bool, String> {
 
 
 #[tauri::command]
-async fn ffe_login_with_credentials(app: tauri::AppHandle, username: String, password: String) -> Result<(), String> {
+async fn ffe_login_with_credentials(
+  app: tauri::AppHandle,
+  username: String,
+  password: String,
+  account: Option<String>,
+) -> Result<(), String> {
   let username = username.trim().to_string();
   if username.is_empty() || password.is_empty() {
     return Err("Identifiant et mot de passe requis.".into());
   }
+  let account = resolve_account(account)?;
+  ensure_known_account(&app, &account)?;
+  let label = accounts::login_label(&account);
 
   // Ouvre (ou réutilise) une WebView FFE, car le login FFE dépend du navigateur (JS/SSO/protections).
   // Objectif: garder une UI "connexion par ID", mais exécuter la connexion dans un vrai moteur WebView.
   let login_url = "https://ffecompet.ffe.com/login";
   let parsed = tauri::Url::parse(login_url).map_err(|e| e.to_string())?;
 
-  let window = if let Some(w) = app.get_webview_window("ffe-login") {
+  let window = if let Some(w) = app.get_webview_window(&label) {
   // Réutilise la fenêtre existante, mais on s'assure qu'elle reste invisible et hors écran.
   let _ = w.hide();
   let _ = w.set_position(Position::Logical(LogicalPosition { x: -10_000.0, y: -10_000.0 }));
   w
 } else {
-  WebviewWindowBuilder::new(&app, "ffe-login", WebviewUrl::External(parsed))
-    .title("Connexion FFE")              // optionnel
+  let builder = WebviewWindowBuilder::new(&app, &label, WebviewUrl::External(parsed))
+    .title(format!("Connexion FFE ({account})"))
     .visible(false)                      // 👈 invisible
     .skip_taskbar(true)                  // 👈 pas dans la barre des tâches (Windows)
     .resizable(false)
     .decorations(true)
     .transparent(true)                   // optionnel
     .inner_size(980.0, 760.0)            // taille interne
-    .position(-10_000.0, -10_000.0) // 👈 hors écran (sécurité)
+    .position(-10_000.0, -10_000.0); // 👈 hors écran (sécurité)
+  with_account_store(builder, &app, &account)?
     .build()
     .map_err(|e| e.to_string())?
 };
 
 
-  // Injecte un script "best effort" qui:
-  // - tente d'accepter le bandeau cookies
-  // - trouve un éventuel iframe contenant le formulaire
-  // - remplit identifiant + mot de passe
-  // - clique "Se connecter" / "Connexion" ou submit
-  //
-  // NB: on n'essaie PAS de contourner des challenges; si captcha/2FA, l'utilisateur terminera à la main.
-  let js = format!(r#"
-    (function() {{
-      const USER = {user_json};
-      const PASS = {pass_json};
-
-      function qAll(root, sel) {{ try {{ return Array.from(root.querySelectorAll(sel)); }} catch(e) {{ return []; }} }}
-      function byText(root, tag, re) {{
-        return qAll(root, tag).find(el => re.test((el.innerText || el.value || el.getAttribute('aria-label') || '').trim()));
-      }}
-
-      function tryAcceptCookies(doc) {{
-        const patterns = [/tout accepter/i, /accepter/i, /^ok$/i, /d'accord/i, /fermer/i];
-        const btns = qAll(doc, 'button, a, input[type=button], input[type=submit]');
-        for (const b of btns) {{
-          const t = (b.innerText || b.value || b.getAttribute('aria-label') || '').trim();
-          if (patterns.some(p => p.test(t))) {{
-            try {{ b.click(); return true; }} catch(e) {{}}
-          }}
-        }}
-        // didomi fallback
-        const didomi = doc.querySelector('#didomi-notice-agree-button');
-        if (didomi) {{ try {{ didomi.click(); return true; }} catch(e) {{}} }}
-        return false;
-      }}
-
-      function findFormDoc() {{
-        // Cherche un iframe qui contient un password
-        const iframes = qAll(document, 'iframe');
-        for (const fr of iframes) {{
-          try {{
-            const d = fr.contentDocument;
-            if (d && d.querySelector('input[type=password]')) return d;
-          }} catch(e) {{}}
-        }}
-        return document;
-      }}
-
-      function fillAndSubmit(doc) {{
-        tryAcceptCookies(doc);
-
-        const userSelectors = [
-          "input[name='username']",
-          "input[name='email']",
-          "input[name='login']",
-          "input[type='email']",
-          "input[placeholder*='email' i]",
-          "input[placeholder*='identifiant' i]",
-          "input[placeholder*='licence' i]",
-        ];
-        const passSelectors = [
-          "input[name='password']",
-          "input[type='password']",
-          "input[placeholder*='mot de passe' i]",
-          "input[placeholder*='password' i]",
-          "input[placeholder*='code sif' i]",
-        ];
-
-        let u = null;
-        for (const s of userSelectors) {{ u = doc.querySelector(s); if (u) break; }}
-        let p = null;
-        for (const s of passSelectors) {{ p = doc.querySelector(s); if (p) break; }}
-
-        if (u) {{
-          u.focus();
-          u.value = USER;
-          u.dispatchEvent(new Event('input', {{ bubbles: true }}));
-          u.dispatchEvent(new Event('change', {{ bubbles: true }}));
-        }}
-        if (p) {{
-          p.focus();
-          p.value = PASS;
-          p.dispatchEvent(new Event('input', {{ bubbles: true }}));
-          p.dispatchEvent(new Event('change', {{ bubbles: true }}));
-        }}
-
-        const reBtn = /(se connecter|connexion|s'identifier)/i;
-        let btn = byText(doc, 'button', reBtn) || byText(doc, 'input[type=submit], input[type=button]', reBtn);
-        if (btn) {{
-          try {{ btn.click(); return true; }} catch(e) {{}}
-        }}
-
-        // fallback: submit du formulaire
-        if (p && p.form) {{
-          try {{ p.form.submit(); return true; }} catch(e) {{}}
-        }}
-        if (p) {{
-          try {{
-            const ke = new KeyboardEvent('keydown', {{ key: 'Enter', code: 'Enter', which: 13, keyCode: 13, bubbles: true }});
-            p.dispatchEvent(ke);
-          }} catch(e) {{}}
-        }}
-        return true;
-      }}
-
-      function attempt() {{
-        const doc = findFormDoc();
-        fillAndSubmit(doc);
-      }}
-
-      // tente plusieurs fois (la page peut charger du contenu dynamiquement)
-      let tries = 0;
-      const timer = setInterval(() => {{
-        tries++;
-        attempt();
-        if (tries >= 8) clearInterval(timer);
-      }}, 900);
-      setTimeout(attempt, 1200);
-      setTimeout(attempt, 2500);
-    }})();
-  "#, user_json = serde_json::to_string(&username).unwrap(), pass_json = serde_json::to_string(&password).unwrap());
-
-  window.eval(&js).map_err(|e| e.to_string())?;
-
-  // Poll: on essaye de récupérer des cookies de session et de valider côté serveur.
-  // Si captcha/2FA, l'utilisateur peut finir dans la fenêtre, puis relancer "Vérifier".
-  for _ in 0..30 {
-    tokio::time::sleep(std::time::Duration::from_millis(900)).await;
+  // Machine à états pilotée par sonde: bandeau cookies → formulaire → soumission → redirections SSO.
+  // Chaque étape est émise en `ffe:login` pour l'UI.
+  let mut flow = login::LoginFlow::new(app.clone(), &account);
+  flow.set(login::LoginState::Loading, None, Some(login_url));
+  tokio::time::sleep(std::time::Duration::from_millis(1200)).await;
 
-    // Tentative de capture des cookies depuis la webview
-    let _ = save_ffe_session(app.clone());
+  let deadline = std::time::Instant::now() + std::time::Duration::from_secs(45);
+  let mut last_href: Option<String> = None;
+  while std::time::Instant::now() < deadline {
+    let p = probe::run(&window, std::time::Duration::from_millis(1500)).await;
+    if let Some(p) = &p {
+      last_href = Some(p.href.clone());
+      set_login_debug_inner(format!("login({account}): {}", p.summary()));
+    }
 
-    if let Ok(true) = check_ffe_connected(app.clone()).await {
-      // On ferme la fenêtre de login pour revenir à l'app
-      if let Some(w) = app.get_webview_window("ffe-login") {
-        let _ = w.close();
+    match flow.next(p.as_ref()) {
+      login::Step::Done => {
+        let _ = save_account_session(&app, &account);
+        keepalive::mark_authenticated(&account);
+        // On ferme la fenêtre de login pour revenir à l'app
+        let _ = window.close();
+        return Ok(());
+      }
+      login::Step::AcceptCookies => {
+        let _ = window.eval(login::accept_cookies_script());
+      }
+      login::Step::FillAndSubmit => {
+        window.eval(login::fill_and_submit_script(&username, &password)).map_err(|e| e.to_string())?;
+        flow.set(login::LoginState::FormFilled, None, last_href.as_deref());
+        flow.submitted();
+        flow.set(login::LoginState::Submitted, None, last_href.as_deref());
       }
-      return Ok(());
+      login::Step::Manual => {
+        // Captcha / 2FA: l'utilisateur termine dans la fenêtre, on continue de surveiller en arrière-plan
+        show_login_window(&window);
+        tauri::async_runtime::spawn(await_manual_login(app.clone(), account.clone(), flow));
+        return Err("Action requise dans la fenêtre FFE (captcha ou code de vérification).".into());
+      }
+      login::Step::Fail(reason) => {
+        flow.fail(&reason, last_href.as_deref());
+        show_login_window(&window);
+        return Err(reason);
+      }
+      login::Step::Wait => {}
     }
+
+    tokio::time::sleep(std::time::Duration::from_millis(900)).await;
   }
 
-// Fallback: on affiche la fenêtre (au cas où un captcha/2FA/consentement bloque l'auth).
-let _ = window.set_position(Position::Logical(LogicalPosition { x: 80.0, y: 80.0 }));
-let _ = window.show();
-let _ = window.set_focus();
+  // Délai dépassé: on affiche la fenêtre (consentement / page inattendue qui bloque l'auth).
+  let reason = "délai de connexion dépassé".to_string();
+  flow.fail(&reason, last_href.as_deref());
+  show_login_window(&window);
+  Err(reason)
+}
 
-Err("ERREUR DE CONNEXION".into())
+fn show_login_window(window: &tauri::WebviewWindow) {
+  let _ = window.set_position(Position::Logical(LogicalPosition { x: 80.0, y: 80.0 }));
+  let _ = window.show();
+  let _ = window.set_focus();
 }
 
+/// Attend que l'utilisateur termine la connexion (captcha / 2FA) dans la fenêtre affichée.
+async fn await_manual_login(app: tauri::AppHandle, account: String, mut flow: login::LoginFlow) {
+  let label = accounts::login_label(&account);
+  let deadline = std::time::Instant::now() + std::time::Duration::from_secs(300);
+  while std::time::Instant::now() < deadline {
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    // Fenêtre fermée par l'utilisateur: abandon
+    let Some(window) = app.get_webview_window(&label) else {
+      flow.fail("fenêtre de connexion fermée", None);
+      return;
+    };
+    let Some(p) = probe::run(&window, std::time::Duration::from_millis(1500)).await else { continue; };
+    if p.is_connected() {
+      flow.set(login::LoginState::Connected, None, Some(&p.href));
+      let _ = save_account_session(&app, &account);
+      keepalive::mark_authenticated(&account);
+      let _ = window.close();
+      return;
+    }
+    // Reflète l'étape courante (ex: captcha résolu → code 2FA demandé)
+    if let login::Step::Fail(reason) = flow.next(Some(&p)) {
+      flow.fail(&reason, Some(&p.href));
+      return;
+    }
+  }
+  flow.fail("délai dépassé pour terminer la connexion", None);
+}
 
 
-// Utilisable depuis les autres modules (watcher/epreuves) pour injecter le header Cookie.
-pub(crate) fn ffe_cookie_header() -> Option<String> {
-  FFE_COOKIES
-    .get()
-    .map(|m| m.lock().unwrap().clone())
-    .map(|s| if s.trim().is_empty() { None } else { Some(s) })
-    .flatten()
+
+/// Supprime les cookies FFE du store de la WebView (partagé entre la fenêtre de login et la probe du compte).
+fn clear_webview_ffe_cookies(w: &tauri::WebviewWindow) -> usize {
+  let Ok(cookies) = w.cookies() else { return 0; };
+  let mut n = 0;
+  for c in cookies {
+    let is_ffe = c.domain().map(|d| d.trim_start_matches('.').ends_with("ffe.com")).unwrap_or(true);
+    if is_ffe && w.delete_cookie(c).is_ok() {
+      n += 1;
+    }
+  }
+  n
+}
+
+/// Déconnexion complète d'un compte. Renvoie le nombre de cookies WebView supprimés.
+async fn logout_account(app: &tauri::AppHandle, account: &str) -> Result<usize, String> {
+  // 1) Session HTTP (mémoire + disque)
+  accounts::jar(account).clear();
+  keepalive::forget(account);
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  session::clear(&dir, account).map_err(|e| format!("{e:#}"))?;
+
+  // 2) Cookies de la WebView: si aucune fenêtre FFE du compte n'est ouverte, on en ouvre une invisible
+  //    (le store de cookies est persistant, il faut une WebView pour y accéder)
+  let mut windows: Vec<tauri::WebviewWindow> = [accounts::login_label(account), accounts::probe_label(account)]
+    .iter()
+    .filter_map(|label| app.get_webview_window(label))
+    .collect();
+  if windows.is_empty() {
+    windows.push(open_probe_window(app, account)?);
+  }
+
+  let mut removed = 0;
+  for w in &windows {
+    removed += clear_webview_ffe_cookies(w);
+  }
+
+  // 3) Fermeture des fenêtres FFE du compte
+  for w in windows {
+    let _ = w.close();
+  }
+  Ok(removed)
+}
+
+#[tauri::command]
+async fn ffe_logout(app: tauri::AppHandle, account: Option<String>) -> Result<(), String> {
+  let account = resolve_account(account)?;
+  let removed = logout_account(&app, &account).await?;
+
+  // Debug + notification de toutes les fenêtres
+  let cell = LAST_LOGIN_DEBUG.get_or_init(|| Mutex::new(String::new()));
+  cell.lock().unwrap().clear();
+
+  let _ = app.emit(
+    "ffe:status",
+    serde_json::json!({ "account": account, "connected": false, "loggedOut": true, "cookiesRemoved": removed }),
+  );
+  Ok(())
 }
 
 
@@ -493,11 +892,11 @@ pub(crate) fn ffe_cookie_header() -> Option<String> {
 
 #[derive(Default)]
 struct ScrapeState {
-  last: Mutex<Option<Vec<epreuves::Epreuve>>>,
+  last: Mutex<Option<Vec<epreuves::EpreuveDetail>>>,
 }
 
 #[tauri::command]
-fn store_epreuves(state: State<ScrapeState>, epreuves: Vec<epreuves::Epreuve>) -> Result<(), String> {
+fn store_epreuves(state: State<ScrapeState>, epreuves: Vec<epreuves::EpreuveDetail>) -> Result<(), String> {
   let mut g = state.last.lock().map_err(|_| "lock".to_string())?;
   *g = Some(epreuves);
   Ok(())
@@ -522,486 +921,216 @@ fn store_links(state: State<ScrapeDebug>, links: Vec<epreuves::Epreuve>) -> Resu
 }
 
 
-#[tauri::command]
-#[allow(non_snake_case)]
-async fn list_epreuves(
-  _app: tauri::AppHandle,
-  _state: State<'_, ScrapeState>,
-  contestId: String,
-) -> Result<Vec<epreuves::Epreuve>, String> {
-  let contest_id = contestId.trim();
-  if contest_id.is_empty() {
-    return Err("N° concours manquant".into());
-  }
-  let base_url = format!("https://ffecompet.ffe.com/concours/{}", contest_id);
-
-  // ⚠️ IMPORTANT:
-  // Les pages externes (https://ffecompet.ffe.com/...) n'ont pas accès à `invoke` dans la WebView.
-  // Donc on scrape ici en Rust (HTTP), pas via JS dans une webview.
-  //
-  // Si plus tard tu veux parser aussi le "60 / 60" pour chaque épreuve, on pourra l'ajouter,
-  // mais ici on renvoie la liste des épreuves (1..N) + un URL "tagué" ?watch_epreuve=N.
-
-  let client = reqwest::Client::builder()
-    .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36")
-    .redirect(reqwest::redirect::Policy::limited(10))
-    .build()
-    .map_err(|e| format!("HTTP client error: {}", e))?;
-
-  let resp = client
-    .get(&base_url)
-    .send()
-    .await
-    .map_err(|e| format!("HTTP error: {}", e))?;
-
-  let status = resp.status();
-  let body = resp.text().await.map_err(|e| format!("HTTP read error: {}", e))?;
-
-  if !status.is_success() {
-    let snippet = body.chars().take(240).collect::<String>();
-    return Err(format!("HTTP {} sur {} — extrait: {}", status, base_url, snippet));
-  }
-
-  fn decode_entities(s: &str) -> String {
-    s.replace("&nbsp;", " ")
-      .replace("\u{00A0}", " ")
-      .replace("&amp;", "&")
-      .replace("&quot;", "\"")
-      .replace("&#39;", "'")
-      .replace("&lt;", "<")
-      .replace("&gt;", ">")
-  }
-
-  fn strip_html_to_text(html: &str) -> String {
-    // HTML -> texte avec conservation des sauts de ligne utiles (tableaux)
-    let mut out = String::with_capacity(html.len().min(200_000));
-    let mut in_tag = false;
-    let mut prev_was_space = false;
-
-    fn is_nl_trigger(tag: &str) -> bool {
-      matches!(
-        tag,
-        "br" | "/br" |
-        "p" | "/p" |
-        "tr" | "/tr" |
-        "td" | "/td" |
-        "th" | "/th" |
-        "li" | "/li" |
-        "div" | "/div" |
-        "section" | "/section" |
-        "article" | "/article" |
-        "h1" | "/h1" | "h2" | "/h2" | "h3" | "/h3"
-      )
-    }
-
-    let mut tag_buf = String::new();
-
-    for ch in html.chars() {
-      if ch == '<' {
-        in_tag = true;
-        tag_buf.clear();
-        continue;
-      }
-      if in_tag {
-        if ch == '>' {
-          in_tag = false;
-
-          // garde le "/" des tags fermants, et normalise en minuscule
-          let raw = tag_buf.trim().trim_start_matches('!').split_whitespace().next().unwrap_or("");
-          let t = raw.to_ascii_lowercase();
-
-          if is_nl_trigger(&t) {
-            if !out.ends_with('\n') {
-              out.push('\n');
-            }
-            prev_was_space = false;
-          }
-          continue;
-        } else {
-          tag_buf.push(ch);
-          continue;
-        }
-      }
-
-      // texte
-      if ch == '\n' || ch == '\r' {
-        if !out.ends_with('\n') {
-          out.push('\n');
-        }
-        prev_was_space = false;
-      } else if ch.is_whitespace() || ch == '\u{00A0}' {
-        if !prev_was_space && !out.ends_with('\n') {
-          out.push(' ');
-          prev_was_space = true;
-        }
-      } else {
-        out.push(ch);
-        prev_was_space = false;
-      }
-    }
-
-    decode_entities(&out)
-  }
-
-  // Fallback 1: parsing "texte" (si le tableau est dans le HTML)
-  let _text = strip_html_to_text(&body);
-
-  // Fallback 2 (prioritaire si le HTML est hydraté côté JS):
-  // on tente de trouver une URL d'API JSON dans le HTML, on la requête, et on extrait les épreuves.
-  fn extract_urls(html: &str) -> Vec<String> {
-    let mut out = Vec::new();
-    let bytes = html.as_bytes();
-    let mut i = 0usize;
-    while i + 8 < bytes.len() {
-      if &bytes[i..i+8] == b"https://" || (i + 7 < bytes.len() && &bytes[i..i+7] == b"http://") {
-        let start = i;
-        i += 7;
-        while i < bytes.len() {
-          let b = bytes[i];
-          if b == b'"' || b == b'\'' || b == b'<' || b == b'>' || b.is_ascii_whitespace() {
-            break;
-          }
-          i += 1;
-        }
-        let url = String::from_utf8_lossy(&bytes[start..i]).to_string();
-        if !out.contains(&url) {
-          out.push(url);
-        }
-      } else {
-        i += 1;
-      }
-    }
-    out
-  }
+/// Filtre discipline (codes ou noms, vide = toutes).
+fn parse_disciplines(disciplines: &[String]) -> Result<Vec<epreuves::Discipline>, String> {
+  disciplines
+    .iter()
+    .map(|d| epreuves::Discipline::parse(d.trim()).ok_or_else(|| format!("Discipline inconnue: {d}")))
+    .collect()
+}
 
-  fn parse_height_cm_from_label(label: &str) -> Option<u32> {
-    let start = label.find('(')?;
-    let end = label[start..].find('m')?;
-    let inside = &label[start+1..start+end];
-    let mut digits = String::new();
-    for ch in inside.chars() {
-      if ch.is_ascii_digit() || ch == ',' || ch == '.' {
-        digits.push(ch);
+/// Épreuves du concours depuis le cache (relues si absentes).
+async fn cached_epreuves(
+  app: &tauri::AppHandle,
+  db_path: &std::path::Path,
+  client: reqwest::Client,
+  contest_id: &str,
+) -> Result<Vec<epreuves::EpreuveDetail>, String> {
+  match contest_cache::load(db_path, contest_id)? {
+    Some(c) => {
+      if !c.is_fresh(chrono::Utc::now().timestamp()) {
+        revalidate_contest(app.clone(), db_path.to_path_buf(), client, contest_id.to_string());
       }
+      Ok(c.epreuves)
     }
-    if digits.is_empty() { return None; }
-    let val = digits.replace(',', ".");
-    let m: f32 = val.parse().ok()?;
-    Some((m * 100.0).round() as u32)
+    None => Ok(contest_cache::refresh(db_path, &client, contest_id).await?.0),
   }
+}
 
-  fn find_epreuves_in_json(v: &serde_json::Value) -> Vec<(u32, String, Option<u32>, Option<u32>, Option<u32>)> {
-    let mut out = Vec::new();
-
-    fn get_u32(obj: &serde_json::Map<String, serde_json::Value>, keys: &[&str]) -> Option<u32> {
-      for k in keys {
-        if let Some(val) = obj.get(*k) {
-          if let Some(n) = val.as_u64() { return Some(n as u32); }
-          if let Some(s) = val.as_str() {
-            if let Ok(n) = s.trim().parse::<u32>() { return Some(n); }
-          }
-        }
-      }
-      None
-    }
-
-    fn get_str(obj: &serde_json::Map<String, serde_json::Value>, keys: &[&str]) -> Option<String> {
-      for k in keys {
-        if let Some(val) = obj.get(*k) {
-          if let Some(s) = val.as_str() {
-            let s = s.trim();
-            if !s.is_empty() { return Some(s.to_string()); }
-          }
-        }
+/// Relit le concours en tâche de fond et prévient l'UI si les épreuves ont changé.
+fn revalidate_contest(app: tauri::AppHandle, db_path: std::path::PathBuf, client: reqwest::Client, contest_id: String) {
+  let Some(guard) = contest_cache::begin_refresh(&contest_id) else { return };
+  tauri::async_runtime::spawn(async move {
+    let _guard = guard;
+    match contest_cache::refresh(&db_path, &client, &contest_id).await {
+      Ok((list, true)) => {
+        let _ = app.emit("contest:updated", serde_json::json!({ "contestId": contest_id, "epreuves": list }));
       }
-      None
-    }
-
-    fn walk(v: &serde_json::Value, out: &mut Vec<(u32, String, Option<u32>, Option<u32>, Option<u32>)>) {
-      match v {
-        serde_json::Value::Object(obj) => {
-          if let Some(arr) = obj.get("epreuves").and_then(|x| x.as_array()) {
-            for it in arr { walk(it, out); }
-          }
-
-          let num = get_u32(obj, &["numEpreuve","numeroEpreuve","numero","num","ordre"]);
-          let label = get_str(obj, &["libelle","label","nom","intitule","designation"]);
-          if let (Some(n), Some(lab)) = (num, label) {
-            let disc = get_str(obj, &["discipline","codeDiscipline","disc"]);
-            if disc.as_deref().unwrap_or("SO") == "SO" || disc.is_none() {
-              let engaged = get_u32(obj, &["nbEngages","engages","nbEngage","nbPartants","participants"]);
-              let max = get_u32(obj, &["nbMax","maxEngages","nbMaxEngages","max","capacite"]);
-              let height = parse_height_cm_from_label(&lab);
-              out.push((n, lab, height, engaged, max));
-            }
-          }
-
-          for (_k, vv) in obj.iter() { walk(vv, out); }
-        }
-        serde_json::Value::Array(arr) => {
-          for it in arr { walk(it, out); }
-        }
-        _ => {}
+      Ok((_, false)) => {}
+      Err(e) => {
+        let _ = app.emit("contest:refresh_failed", serde_json::json!({ "contestId": contest_id, "error": e }));
       }
     }
-
-    walk(v, &mut out);
-    out
-  }
-
-  
-
-fn parse_ratio(line: &str) -> Option<(u32, u32)> {
-  // Cherche un motif "X / Y" n'importe où dans la chaîne
-  let mut nums: Vec<u32> = Vec::new();
-  let mut cur = String::new();
-  for ch in line.chars() {
-    if ch.is_ascii_digit() {
-      cur.push(ch);
-    } else {
-      if !cur.is_empty() {
-        if let Ok(v) = cur.parse::<u32>() {
-          nums.push(v);
-          if nums.len() >= 2 { break; }
-        }
-        cur.clear();
-      }
-    }
-  }
-  if !cur.is_empty() && nums.len() < 2 {
-    if let Ok(v) = cur.parse::<u32>() { nums.push(v); }
-  }
-  if nums.len() >= 2 { Some((nums[0], nums[1])) } else { None }
+  });
 }
 
-fn try_parse_json_epreuves(body_text: &str) -> Vec<(u32, String, Option<u32>, Option<u32>, Option<u32>)> {
-  // Retourne: (num, libellé, hauteur_cm, engagés, max)
-  let mut out: Vec<(u32, String, Option<u32>, Option<u32>, Option<u32>)> = Vec::new();
-
-  let v: serde_json::Value = match serde_json::from_str(body_text) {
-    Ok(v) => v,
-    Err(_) => return out,
+/// Épreuves du concours. La liste en cache est renvoyée immédiatement (relue en tâche de fond
+/// si elle a expiré, événement `contest:updated` si elle change); `refresh` force la relecture.
+#[tauri::command]
+#[allow(non_snake_case)]
+#[allow(clippy::too_many_arguments)]
+async fn list_epreuves(
+  app: tauri::AppHandle,
+  app_state: State<'_, AppState>,
+  state: State<'_, ScrapeState>,
+  contestId: String,
+  account: Option<String>,
+  disciplines: Option<Vec<String>>,
+  filter: Option<epreuves::EpreuveFilter>,
+  refresh: Option<bool>,
+) -> Result<Vec<epreuves::EpreuveDetail>, String> {
+  let account = resolve_account(account)?;
+  let wanted = parse_disciplines(&disciplines.unwrap_or_default())?;
+
+  let contest_id = contestId.trim().to_string();
+  let db_path = get_db_path(&app, &app_state)?;
+  let client = http::client(Some(&account)).map_err(|e| format!("HTTP client error: {e:#}"))?;
+  let all = if refresh.unwrap_or(false) {
+    contest_cache::refresh(&db_path, &client, &contest_id).await?.0
+  } else {
+    cached_epreuves(&app, &db_path, client, &contest_id).await?
   };
 
-  fn height_cm_from_label(label: &str) -> Option<u32> {
-    // Ex: "(1,10 m)" -> 110
-    let start = label.find('(')?;
-    let end_rel = label[start..].find('m')?;
-    let chunk = &label[start + 1..start + end_rel];
-    let chunk = chunk.trim().replace(',', ".");
-    let filtered: String = chunk.chars().filter(|c| c.is_ascii_digit() || *c == '.').collect();
-    let m = filtered.parse::<f32>().ok()?;
-    Some((m * 100.0).round() as u32)
-  }
-
-  fn walk(val: &serde_json::Value, out: &mut Vec<(u32, String, Option<u32>, Option<u32>, Option<u32>)>) {
-    match val {
-      serde_json::Value::Array(arr) => for x in arr { walk(x, out); },
-      serde_json::Value::Object(map) => {
-        let num = map.get("numero")
-          .or_else(|| map.get("num"))
-          .or_else(|| map.get("no"))
-          .and_then(|x| x.as_u64())
-          .map(|x| x as u32);
-
-        let label = map.get("libelle")
-          .or_else(|| map.get("label"))
-          .or_else(|| map.get("nom"))
-          .and_then(|x| x.as_str())
-          .map(|s| s.to_string());
-
-        let engages = map.get("engages")
-          .or_else(|| map.get("engaged"))
-          .or_else(|| map.get("nbEngages"))
-          .and_then(|x| x.as_u64())
-          .map(|x| x as u32);
-
-        let max = map.get("max")
-          .or_else(|| map.get("capacite"))
-          .or_else(|| map.get("nbMax"))
-          .and_then(|x| x.as_u64())
-          .map(|x| x as u32);
-
-        if let (Some(n), Some(lab)) = (num, label) {
-          let h = height_cm_from_label(&lab);
-          out.push((n, lab, h, engages, max));
-        }
-
-        for (_k, v) in map.iter() { walk(v, out); }
-      }
-      _ => {}
-    }
-  }
-
-  walk(&v, &mut out);
-  out.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
-  out.dedup_by(|a, b| a.0 == b.0 && a.1 == b.1);
-  out
-}
-
-async fn try_api_extract(
-  client: &reqwest::Client,
-  urls: Vec<String>,
-  contest_id: &str
-) -> Option<Vec<(u32, String, Option<u32>, Option<u32>, Option<u32>)>> {
-  for url in urls {
-    let lu = url.to_lowercase();
-    // heuristique: ne tester que les urls qui ressemblent à de l'API/JSON
-    if !(lu.contains("api") || lu.contains("json") || lu.contains("data")) { continue; }
-    // souvent l'id concours est présent dans l'URL d'API
-    if !lu.contains(contest_id) { continue; }
-
-    let resp = client.get(&url).send().await.ok()?;
-
-    // lire ce dont on a besoin AVANT de consommer resp
-    let ct: String = resp
-      .headers()
-      .get(reqwest::header::CONTENT_TYPE)
-      .and_then(|v| v.to_str().ok())
-      .unwrap_or("")
-      .to_string();
-
-    // consommer resp une seule fois
-    let body_text = resp.text().await.ok()?;
-
-    let looks_json =
-      ct.contains("json")
-      || body_text.trim_start().starts_with('{')
-      || body_text.trim_start().starts_with('[');
-
-    if !looks_json { continue; }
-
-    let items = try_parse_json_epreuves(&body_text);
-    if !items.is_empty() { return Some(items); }
-  }
-  None
+  let list = epreuves::keep_disciplines(all, &wanted);
+  // Critères sur le libellé décomposé (hauteur, série, niveau, barème) + tri
+  let list = filter.unwrap_or_default().apply(list);
+  *state.last.lock().map_err(|_| "lock".to_string())? = Some(list.clone());
+  Ok(list)
 }
 
-  // 1) Tentative via API JSON détectée dans le HTML (souvent le cas si le tableau est hydraté en JS)
-  let urls = extract_urls(&body);
-  let mut from_api: Option<Vec<(u32, String, Option<u32>, Option<u32>, Option<u32>)>> = None;
-  if !urls.is_empty() {
-    from_api = try_api_extract(&client, urls, contest_id).await;
+/// Crée en une fois les cibles des épreuves choisies (numéros, sinon disciplines + filtre),
+/// avec le même cavalier / cheval et les mêmes intervalles; les épreuves déjà surveillées sont ignorées.
+#[tauri::command]
+async fn watch_contest(
+  app: tauri::AppHandle,
+  state: State<'_, AppState>,
+  payload: WatchContestPayload,
+) -> Result<WatchContestResult, String> {
+  let contest_id = payload.contest_id.trim().to_string();
+  if contest_id.len() != 9 || !contest_id.chars().all(|c| c.is_ascii_digit()) {
+    return Err("N° concours invalide (9 chiffres).".into());
   }
-
-  let text = strip_html_to_text(&body);
-
-  // 2) Fallback via texte "visible" si le tableau est déjà dans le HTML
-  let mut found: std::collections::BTreeMap<u32, (String, Option<u32>, Option<u32>, Option<u32>)> = std::collections::BTreeMap::new();
-
-  if let Some(items) = from_api {
-    for (num, label, height_cm, engaged, max) in items {
-      found.entry(num).or_insert((label, height_cm, engaged, max));
+  let account = resolve_account(payload.account.clone())?;
+  ensure_known_account(&app, &account)?;
+  let wanted = parse_disciplines(&payload.disciplines)?;
+  if payload.check_engaged {
+    if let Some(e) = engages::check_error(payload.cavalier.as_deref(), payload.cheval.as_deref()) {
+      return Err(e);
     }
+  }
 
-} else {  // Parsing robuste par TOKENS (le tableau peut être éclaté en cellules: "1" puis "SO" sur la ligne suivante, etc.)
-  // On tokenise tout le texte et on reconstruit les blocs: [num][SO][label ... ,][... X / Y ...]
-  let tokens: Vec<&str> = text.split_whitespace().collect();
-  let mut i: usize = 0;
-
-  while i < tokens.len() {
-    // Détecter début d'épreuve: <num> SO
-    let num_opt = tokens[i].parse::<u32>().ok();
-    if let (Some(num), Some(next)) = (num_opt, tokens.get(i + 1).copied()) {
-      if next == "SO" {
-        i += 2;
-
-        // Collecter le label jusqu'à une virgule finale (souvent ",") ou jusqu'au prochain début d'épreuve
-        let mut label_parts: Vec<&str> = Vec::new();
-        while i < tokens.len() {
-          // Stop si prochain début d'épreuve
-          if tokens[i].parse::<u32>().is_ok() && tokens.get(i + 1).copied() == Some("SO") {
-            break;
-          }
-          let t = tokens[i];
-          // Virgule terminale: soit token == "," soit token finit par ","
-          if t == "," {
-            i += 1;
-            break;
-          }
-          if t.ends_with(',') {
-            label_parts.push(&t[..t.len() - 1]);
-            i += 1;
-            break;
-          }
-          label_parts.push(t);
-          i += 1;
-        }
+  let db_path = get_db_path(&app, &state)?;
+  let client = http::client(Some(&account)).map_err(|e| format!("HTTP client error: {e:#}"))?;
+  let all = cached_epreuves(&app, &db_path, client, &contest_id).await?;
 
-        let label = label_parts.join(" ").trim().to_string();
-        if !label.is_empty() {
-          let height_cm = parse_height_cm_from_label(&label);
-          found.entry(num).or_insert((label, height_cm, None, None));
-        }
+  let mut result = WatchContestResult::default();
+  let picked: Vec<epreuves::EpreuveDetail> = if payload.epreuves.is_empty() {
+    payload.filter.clone().unwrap_or_default().apply(epreuves::keep_disciplines(all, &wanted))
+  } else {
+    result.missing = payload.epreuves.iter().copied().filter(|n| !all.iter().any(|e| e.num == *n)).collect();
+    all.into_iter().filter(|e| payload.epreuves.contains(&e.num)).collect()
+  };
 
-        // Scanner ensuite pour un ratio X / Y avant la prochaine épreuve
-        while i + 2 < tokens.len() {
-          if tokens[i].parse::<u32>().is_ok() && tokens.get(i + 1).copied() == Some("SO") {
-            break; // prochaine épreuve
-          }
-          if let (Ok(e), Some("/"), Ok(mmax)) = (
-            tokens[i].parse::<u32>(),
-            tokens.get(i + 1).copied(),
-            tokens[i + 2].parse::<u32>()
-          ) {
-            if let Some(entry) = found.get_mut(&num) {
-              entry.2 = Some(e);
-              entry.3 = Some(mmax);
-            }
-            i += 3;
-            break;
-          }
-          i += 1;
-        }
+  let clean = |v: &Option<String>| v.as_deref().map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+  let targets: Vec<AddTargetPayload> = picked
+    .iter()
+    .map(|e| AddTargetPayload {
+      label: match &e.discipline {
+        Some(d) => format!("{contest_id} — Epreuve {} {d} — {}", e.num, e.label),
+        None => format!("{contest_id} — Epreuve {} — {}", e.num, e.label),
+      },
+      url: e.url.clone(),
+      cheval: clean(&payload.cheval),
+      cavalier: clean(&payload.cavalier),
+      interval_normal_sec: payload.interval_normal_sec,
+      interval_hot_sec: payload.interval_hot_sec,
+      hot_from: clean(&payload.hot_from),
+      hot_to: clean(&payload.hot_to),
+      // Compte par défaut stocké à NULL
+      account: (account != accounts::DEFAULT_ACCOUNT).then(|| account.clone()),
+      kind: WatchKind::Status,
+      check_engaged: payload.check_engaged,
+    })
+    .collect();
 
-        continue;
-      }
+  let mut conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  let created = db::add_targets(&mut conn, targets).map_err(|e| e.to_string())?;
+  for (e, was_created) in picked.iter().zip(created) {
+    if was_created {
+      result.created.push(e.num);
+    } else {
+      result.skipped.push(e.num);
     }
-
-    i += 1;
   }
+  Ok(result)
 }
 
+/// Liste des engagés d'une épreuve.
+#[tauri::command]
+#[allow(non_snake_case)]
+async fn list_engages(contestId: String, epreuve: u32, account: Option<String>) -> Result<Vec<engages::Engage>, String> {
+  let account = resolve_account(account)?;
+  let client = http::client(Some(&account)).map_err(|e| format!("HTTP client error: {e:#}"))?;
+  engages::fetch(&client, &contestId, epreuve).await
+}
 
-  let mut epreuves_out: Vec<epreuves::Epreuve> = Vec::new();
-  for (num, (label, height_cm, engaged, max)) in found {
-    let url = format!("{}?watch_epreuve={}", base_url, num);
+// ===================== RECHERCHE CONCOURS =====================
 
-    // Label demandé: "Epreuve 1 110 60/60" (si info dispo)
-    let mut parts = vec![format!("Epreuve {}", num)];
-    if let Some(h) = height_cm { parts.push(format!("{}", h)); }
-    if let (Some(e), Some(m)) = (engaged, max) { parts.push(format!("{}/{}", e, m)); }
+#[tauri::command]
+async fn search_contests(search: ContestSearch, account: Option<String>) -> Result<ContestSearchResult, String> {
+  let account = resolve_account(account)?;
+  let client = http::client(Some(&account)).map_err(|e| format!("HTTP client error: {e:#}"))?;
+  contests::search(&client, &search).await
+}
 
-    // fallback si pas de ratio/hauteur
-    if parts.len() == 1 {
-      parts.push(label.clone());
+/// Fiche du concours: version enregistrée, sinon (ou si `refresh`) relue sur ffecompet.
+#[tauri::command]
+#[allow(non_snake_case)]
+async fn get_contest_detail(
+  app: tauri::AppHandle,
+  state: State<'_, AppState>,
+  contestId: String,
+  account: Option<String>,
+  refresh: Option<bool>,
+) -> Result<ContestDetail, String> {
+  let db_path = get_db_path(&app, &state)?;
+  let id = contestId.trim().to_string();
+  let stored = {
+    let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+    db::get_contest(&conn, &id).map_err(|e| e.to_string())?
+  };
+  let now = chrono::Utc::now().timestamp();
+  if let Some(c) = &stored {
+    if !refresh.unwrap_or(false) && now - c.fetched_at < contests::DETAIL_TTL_SEC {
+      return Ok(c.clone());
     }
-
-    epreuves_out.push(epreuves::Epreuve { label: parts.join(" "), url });
   }
 
-  if epreuves_out.is_empty() {
-    // debug utile: on renvoie quelques lignes qui contiennent "SO" pour comprendre le format réel
-    let mut samples: Vec<String> = Vec::new();
-    // lignes texte
-    for raw in text.lines() {
-      let l = raw.trim();
-      if l.contains("SO") {
-        samples.push(l.chars().take(220).collect());
-      }
-      if samples.len() >= 8 { break; }
-    }
-    // si vide, renvoyer aussi quelques urls détectées
-    if samples.is_empty() {
-      samples.push(format!("urls_detected={}", extract_urls(&body).len()));
-    }
-    return Err(format!(
-      "0 épreuve détectée (scrape Rust) — url={} — samples={:?}",
-      base_url, samples
-    ));
-  }
+  let account = resolve_account(account)?;
+  let client = http::client(Some(&account)).map_err(|e| format!("HTTP client error: {e:#}"))?;
+  let detail = match contests::fetch_detail(&client, &id).await {
+    Ok(d) => d,
+    // Fiche expirée mais ffecompet injoignable: la dernière version connue plutôt qu'une erreur
+    Err(e) => return stored.filter(|_| !refresh.unwrap_or(false)).ok_or(e),
+  };
+  let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
+  db::save_contest(&conn, &detail).map_err(|e| e.to_string())?;
+  Ok(detail)
+}
 
-  Ok(epreuves_out)
+#[tauri::command]
+fn list_contest_details(app: tauri::AppHandle, state: State<AppState>) -> Result<Vec<ContestDetail>, String> {
+  let db_path = get_db_path(&app, &state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::list_contests(&conn).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[allow(non_snake_case)]
+fn delete_contest_detail(app: tauri::AppHandle, state: State<AppState>, contestId: String) -> Result<(), String> {
+  let db_path = get_db_path(&app, &state)?;
+  let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+  db::delete_contest(&conn, contestId.trim()).map_err(|e| e.to_string())
 }
 
 
@@ -1011,31 +1140,113 @@ async fn try_api_extract(
 fn main() {
   tauri::Builder::default()
     .plugin(tauri_plugin_opener::init())
+    .setup(|app| {
+      // Réglages du client HTTP partagé (avant toute requête)
+      if let Ok(path) = ensure_db(app.handle()) {
+        if let Ok(conn) = Connection::open(path) {
+          http::configure(http::load_settings(&conn));
+        }
+      }
+
+      // Restauration de la session FFE enregistrée (si elle n'est pas protégée par phrase de passe)
+      let handle = app.handle().clone();
+      tauri::async_runtime::spawn(async move {
+        let Ok(dir) = handle.path().app_data_dir() else { return; };
+        // Les Set-Cookie reçus par le watcher / le scraping sont réécrits sur disque
+        let error_handle = handle.clone();
+        let on_save_error: Arc<dyn Fn(String) + Send + Sync> = Arc::new(move |e| report_background_error(&error_handle, "session", e));
+        tauri::async_runtime::spawn(session::autosave_loop(dir.clone(), on_save_error));
+
+        let mut locked = Vec::new();
+        for account in session::stored_accounts(&dir) {
+          match session::stored_mode(&dir, &account) {
+            Some(session::KeyMode::Keyfile) => {
+              if let Err(e) = restore_ffe_session(&handle, &account).await {
+                set_login_debug_inner(format!("restauration session ({account}): {e}"));
+              }
+            }
+            Some(session::KeyMode::Passphrase) => locked.push(account),
+            None => {}
+          }
+        }
+        if !locked.is_empty() {
+          let _ = handle.emit("ffe:session_locked", serde_json::json!({ "accounts": locked }));
+        }
+
+        // Keep-alive des sessions connectées
+        let ka_handle = handle.clone();
+        let on_keepalive: Arc<dyn Fn(keepalive::KeepAliveEvent) + Send + Sync> = Arc::new(move |ev| match ev {
+          keepalive::KeepAliveEvent::Expired { account } => {
+            tauri::async_runtime::spawn(revalidate_ffe_session(ka_handle.clone(), account));
+          }
+          keepalive::KeepAliveEvent::Expiring { account, expires_at } => {
+            let _ = ka_handle.emit(
+              "ffe:session_expiring",
+              serde_json::json!({ "account": account, "expiresAt": expires_at }),
+            );
+          }
+        });
+        tauri::async_runtime::spawn(keepalive::keepalive_loop(on_keepalive));
+      });
+      Ok(())
+    })
     .manage(ScrapeState::default())
     .manage(ScrapeDebug::default())
     .manage(AppState {
       watcher: Arc::new(Watcher::new()),
       handle: Mutex::new(None),
+      background: Mutex::new(Vec::new()),
       db_path: Mutex::new(None),
     })
     .invoke_handler(tauri::generate_handler![
       list_targets,
       add_target,
       delete_target,
+      get_target_history,
       start_watcher,
       stop_watcher,
+      get_mute_state,
+      set_quiet_hours,
+      snooze_target,
+      unsnooze_target,
+      get_smtp_settings,
+      set_smtp_settings,
+      list_email_recipients,
+      add_email_recipient,
+      delete_email_recipient,
+      send_test_email,
+      send_digest_now,
+      list_push_channels,
+      add_push_channel,
+      delete_push_channel,
+      test_push_channel,
       open_url,
       store_epreuves,
       list_epreuves,
+      watch_contest,
+      list_engages,
+      search_contests,
+      get_contest_detail,
+      list_contest_details,
+      delete_contest_detail,
       store_debug,
       store_links,
       set_login_debug,
       get_login_debug,
       set_probe,
       save_ffe_session,
+      set_session_passphrase,
+      unlock_ffe_session,
       is_ffe_connected,
       check_ffe_connected,
-      ffe_login_with_credentials
+      ffe_login_with_credentials,
+      ffe_logout,
+      list_ffe_accounts,
+      add_ffe_account,
+      delete_ffe_account,
+      get_session_health,
+      get_http_settings,
+      set_http_settings
     ])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
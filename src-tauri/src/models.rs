@@ -1,4 +1,16 @@
 use serde::{Deserialize, Serialize};
+use crate::epreuves::EpreuveFilter;
+
+/// Ce que surveille une cible.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatchKind {
+  /// Statut d'engagement de la page (OPEN / FULL / CLOSED, places libérées)
+  #[default]
+  Status,
+  /// Programme du concours: épreuves ajoutées, supprimées ou modifiées
+  Contest,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Target {
@@ -15,6 +27,12 @@ pub struct Target {
   pub last_checked_at: Option<i64>,
   pub last_error: Option<String>,
   pub last_slots: Option<i32>,
+  /// Compte FFE utilisé pour interroger la cible (None => compte par défaut)
+  pub account: Option<String>,
+  pub kind: WatchKind,
+  /// Passer la cible en ENGAGED quand le couple cavalier / cheval (ou la licence du cavalier)
+  /// apparaît dans la liste des engagés
+  pub check_engaged: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,4 +45,406 @@ pub struct AddTargetPayload {
   pub interval_hot_sec: Option<i64>,
   pub hot_from: Option<String>,
   pub hot_to: Option<String>,
+  pub account: Option<String>,
+  #[serde(default)]
+  pub kind: WatchKind,
+  #[serde(default)]
+  pub check_engaged: bool,
+}
+
+/// Type d'alerte remontée par le watcher.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AlertKind {
+  /// Les engagements viennent de passer OPEN
+  Opened,
+  /// Une place s'est libérée (FULL → places > 0)
+  SlotFreed,
+  /// Le watcher est redirigé vers la page de connexion FFE
+  SessionExpired,
+  /// Programme du concours modifié (épreuves ajoutées / supprimées / modifiées)
+  ContestChanged,
+  /// Cible ENGAGED: le couple n'apparaît plus dans la liste des engagés
+  EngagementLost,
+}
+
+/// Gravité d'une alerte, utilisée pour la priorité des notifications push.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+  Info,
+  High,
+  Critical,
+}
+
+impl AlertKind {
+  pub fn severity(self) -> Severity {
+    match self {
+      AlertKind::Opened => Severity::Critical,
+      AlertKind::SlotFreed => Severity::High,
+      AlertKind::SessionExpired => Severity::High,
+      AlertKind::ContestChanged => Severity::Info,
+      AlertKind::EngagementLost => Severity::Critical,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+  pub target_id: i64,
+  pub kind: AlertKind,
+  pub label: String,
+  pub url: String,
+  pub cavalier: Option<String>,
+  pub cheval: Option<String>,
+  /// Compte FFE de la cible (absent des alertes mises en file avant les comptes multiples)
+  #[serde(default)]
+  pub account: Option<String>,
+  /// Détail affiché avec l'alerte (différences du programme)
+  #[serde(default)]
+  pub detail: Option<String>,
+}
+
+impl Alert {
+  pub fn title(&self) -> String {
+    match self.kind {
+      AlertKind::Opened => format!("Engagements ouverts — {}", self.label),
+      AlertKind::SlotFreed => format!("Place libérée — {}", self.label),
+      AlertKind::SessionExpired => format!("Session FFE expirée — {}", self.label),
+      AlertKind::ContestChanged => format!("Programme modifié — {}", self.label),
+      AlertKind::EngagementLost => format!("Engagement disparu — {}", self.label),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+  /// SMTP en clair (ex: MailHog / smtp4dev en local)
+  None,
+  StartTls,
+  /// TLS implicite (port 465)
+  Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpSettings {
+  pub enabled: bool,
+  pub host: String,
+  pub port: u16,
+  pub security: SmtpSecurity,
+  pub username: Option<String>,
+  pub password: Option<String>,
+  pub from: String,
+  /// Envoi d'un récapitulatif quotidien de la watchlist
+  pub digest_enabled: bool,
+  /// Heure locale (0-23) d'envoi du récapitulatif
+  pub digest_hour: u32,
+}
+
+impl Default for SmtpSettings {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      host: "localhost".into(),
+      port: 587,
+      security: SmtpSecurity::StartTls,
+      username: None,
+      password: None,
+      from: "FFE Pre-Engage <ffe-pre-engage@localhost>".into(),
+      digest_enabled: false,
+      digest_hour: 8,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailRecipient {
+  pub id: i64,
+  pub cavalier: String,
+  pub email: String,
+}
+
+/// Une ligne du récapitulatif quotidien (construite depuis `events`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestRow {
+  pub target_id: i64,
+  pub label: String,
+  pub url: String,
+  pub last_status: String,
+  pub last_checked_at: Option<i64>,
+  pub last_error: Option<String>,
+  pub checks: i64,
+  pub statuses: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushKind {
+  Ntfy,
+  Gotify,
+}
+
+/// Canal push (ntfy / Gotify), rattaché à un cavalier ou à une cible précise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushChannel {
+  pub id: i64,
+  pub kind: PushKind,
+  pub server_url: String,
+  /// Topic ntfy (ignoré pour Gotify)
+  pub topic: Option<String>,
+  /// Token d'accès ntfy ou token d'application Gotify
+  pub token: Option<String>,
+  pub cavalier: Option<String>,
+  pub target_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddPushChannelPayload {
+  pub kind: PushKind,
+  pub server_url: String,
+  pub topic: Option<String>,
+  pub token: Option<String>,
+  pub cavalier: Option<String>,
+  pub target_id: Option<i64>,
+}
+
+/// Compte FFE nommé et état de sa session en mémoire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FfeAccount {
+  pub name: String,
+  /// Cookies de session présents (à confirmer via `check_ffe_connected`)
+  pub has_session: bool,
+  /// Nombre de cibles interrogées avec ce compte
+  pub targets: i64,
+}
+
+/// Réglages du client HTTP partagé (watcher, scraping, keep-alive, push).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpSettings {
+  pub connect_timeout_sec: u64,
+  /// Délai max entre deux lectures sur la socket
+  pub read_timeout_sec: u64,
+  /// Durée max d'une requête complète (redirections comprises)
+  pub total_timeout_sec: u64,
+  /// Proxy optionnel: http://, https://, socks5:// ou socks5h://
+  pub proxy_url: Option<String>,
+  pub user_agent: String,
+  /// Taille max d'un corps de réponse lu (octets)
+  pub max_body_bytes: u64,
+  /// Nombre max de redirections suivies (0 = aucune)
+  pub max_redirects: usize,
+}
+
+impl Default for HttpSettings {
+  fn default() -> Self {
+    Self {
+      connect_timeout_sec: 10,
+      read_timeout_sec: 30,
+      total_timeout_sec: 60,
+      proxy_url: None,
+      user_agent: "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0 Safari/537.36".into(),
+      max_body_bytes: 5 * 1024 * 1024,
+      max_redirects: 10,
+    }
+  }
+}
+
+/// Santé de la session d'un compte FFE (tâche de keep-alive).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionHealth {
+  pub account: String,
+  /// Dernière réponse authentifiée (keep-alive ou watcher)
+  pub last_ok: Option<i64>,
+  /// Dernier passage du keep-alive
+  pub last_check: Option<i64>,
+  pub last_error: Option<String>,
+  /// Expiration estimée (Expires des cookies d'authentification, ou inactivité pour les cookies de session)
+  pub expires_at: Option<i64>,
+  /// true si le keep-alive est tombé sur la page de connexion
+  pub expired: bool,
+}
+
+/// Plage "ne pas déranger" globale (heures locales, peut passer minuit).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+  pub enabled: bool,
+  /// Début "HH:MM"
+  pub from: String,
+  /// Fin "HH:MM"
+  pub to: String,
+  /// Types d'alerte délivrés même pendant une pause (quiet hours ou snooze)
+  pub override_kinds: Vec<AlertKind>,
+}
+
+impl Default for QuietHours {
+  fn default() -> Self {
+    Self {
+      enabled: false,
+      from: "23:00".into(),
+      to: "07:00".into(),
+      override_kinds: vec![AlertKind::Opened],
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snooze {
+  pub target_id: i64,
+  pub label: String,
+  pub until: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MuteState {
+  pub quiet_hours: QuietHours,
+  /// true si on est actuellement dans la plage "ne pas déranger"
+  pub quiet_now: bool,
+  pub snoozes: Vec<Snooze>,
+  /// Alertes en attente de résumé
+  pub queued: i64,
+}
+
+/// Résumé des alertes mises en file pendant une pause, pour une cible.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedSummary {
+  pub target_id: i64,
+  pub label: String,
+  pub url: String,
+  pub cavalier: Option<String>,
+  pub opened: i64,
+  pub slot_freed: i64,
+  pub session_expired: i64,
+  #[serde(default)]
+  pub contest_changed: i64,
+  #[serde(default)]
+  pub engagement_lost: i64,
+  pub first_ts: i64,
+  pub last_ts: i64,
+}
+
+/// Critères de recherche de concours (tous optionnels).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContestSearch {
+  /// Début de période "YYYY-MM-DD"
+  pub date_from: Option<String>,
+  /// Fin de période "YYYY-MM-DD"
+  pub date_to: Option<String>,
+  /// N° de département ("41", "2A", "974")
+  pub departement: Option<String>,
+  pub region: Option<String>,
+  /// Code ou nom de discipline ("SO", "Dressage", ...)
+  pub discipline: Option<String>,
+  /// Niveau recherché ("Amateur", "Club 2", ...)
+  pub level: Option<String>,
+  /// Nom (ou partie du nom) du club organisateur
+  pub organizer: Option<String>,
+  /// Nombre max de pages de résultats parcourues (défaut 3)
+  pub max_pages: Option<u32>,
+}
+
+/// Concours trouvé dans la liste de résultats.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContestSummary {
+  /// N° de concours (9 chiffres)
+  pub id: String,
+  pub name: String,
+  /// "YYYY-MM-DD"
+  pub start_date: Option<String>,
+  pub end_date: Option<String>,
+  pub city: Option<String>,
+  pub departement: Option<String>,
+  /// Codes discipline FFE
+  pub disciplines: Vec<String>,
+  pub organizer: Option<String>,
+  pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContestSearchResult {
+  pub contests: Vec<ContestSummary>,
+  /// Pages de résultats lues
+  pub pages: u32,
+  /// D'autres pages existent au-delà de `max_pages`
+  pub has_more: bool,
+}
+
+/// Tarif affiché sur la page du concours (engagement, box, ...).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContestFee {
+  pub label: String,
+  /// Montant en euros
+  pub amount_eur: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContestContact {
+  pub name: Option<String>,
+  pub email: Option<String>,
+  pub phone: Option<String>,
+}
+
+/// Fiche d'un concours (page `/concours/{id}`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContestDetail {
+  /// N° de concours (9 chiffres)
+  pub id: String,
+  pub name: String,
+  /// Lieu / adresse tels qu'affichés
+  pub venue: Option<String>,
+  pub city: Option<String>,
+  pub departement: Option<String>,
+  /// "YYYY-MM-DD"
+  pub start_date: Option<String>,
+  pub end_date: Option<String>,
+  /// Club organisateur
+  pub organizer: Option<String>,
+  pub fees: Vec<ContestFee>,
+  /// Ouverture des engagements: "YYYY-MM-DD" ou "YYYY-MM-DDTHH:MM" (heure locale)
+  pub opening_date: Option<String>,
+  /// Clôture des engagements, même format
+  pub closing_date: Option<String>,
+  pub contact: ContestContact,
+  pub url: String,
+  /// Date de lecture de la page (timestamp secondes)
+  pub fetched_at: i64,
+}
+
+/// Création groupée des cibles d'un concours (`watch_contest`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchContestPayload {
+  pub contest_id: String,
+  /// Numéros d'épreuve choisis; vide => sélection par `disciplines` + `filter`
+  pub epreuves: Vec<u32>,
+  /// Codes ou noms de discipline (vide = toutes)
+  pub disciplines: Vec<String>,
+  pub filter: Option<EpreuveFilter>,
+  pub cheval: Option<String>,
+  pub cavalier: Option<String>,
+  /// Réglages communs à toutes les cibles créées
+  pub interval_normal_sec: Option<i64>,
+  pub interval_hot_sec: Option<i64>,
+  pub hot_from: Option<String>,
+  pub hot_to: Option<String>,
+  pub account: Option<String>,
+  /// Voir `Target::check_engaged`
+  pub check_engaged: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WatchContestResult {
+  /// Épreuves pour lesquelles une cible a été créée
+  pub created: Vec<u32>,
+  /// Épreuves déjà surveillées (même URL, même cavalier)
+  pub skipped: Vec<u32>,
+  /// Numéros demandés absents du concours
+  pub missing: Vec<u32>,
+}
+
+/// Ligne de l'historique d'une cible (table `events`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TargetEvent {
+  pub ts: i64,
+  pub status: String,
+  pub note: Option<String>,
 }
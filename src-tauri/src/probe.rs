@@ -0,0 +1,137 @@
+// Sonde de connexion exécutée dans une WebView FFE (fenêtre de login ou probe invisible).
+// Chaque sonde a un identifiant: le script renvoie son résultat typé via `set_probe`, qui réveille
+// uniquement l'appelant correspondant (plus de valeur globale partagée entre deux vérifications).
+
+use std::collections::HashMap;
+use std::sync::{
+  atomic::{AtomicU64, Ordering},
+  Mutex, OnceLock,
+};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginProbe {
+  pub request_id: u64,
+  pub href: String,
+  pub title: String,
+  /// Lien / bouton "Déconnexion" visible (session ouverte)
+  pub has_logout: bool,
+  /// Champ mot de passe présent (page ou iframe de login)
+  pub has_login_form: bool,
+  /// reCAPTCHA / hCaptcha / Turnstile affiché (les widgets invisibles sont ignorés)
+  pub has_captcha: bool,
+  /// Saisie d'un code de vérification (2FA / OTP)
+  #[serde(default)]
+  pub has_two_factor: bool,
+  /// Bandeau de consentement cookies visible
+  #[serde(default)]
+  pub has_cookie_banner: bool,
+  /// Message d'erreur affiché par le formulaire (ex: "Identifiant ou mot de passe invalide")
+  #[serde(default)]
+  pub error_text: Option<String>,
+}
+
+impl LoginProbe {
+  /// Session ouverte uniquement sur marqueur positif ("Déconnexion" visible): une page sans
+  /// formulaire de login (redirection SSO en cours, page d'erreur, bandeau cookies) ne prouve rien.
+  pub fn is_connected(&self) -> bool {
+    self.has_logout
+  }
+
+  pub fn summary(&self) -> String {
+    format!(
+      "href={} | title={} | logout={} form={} captcha={} 2fa={}",
+      self.href, self.title, self.has_logout, self.has_login_form, self.has_captcha, self.has_two_factor
+    )
+  }
+}
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static PENDING: OnceLock<Mutex<HashMap<u64, oneshot::Sender<LoginProbe>>>> = OnceLock::new();
+
+fn pending() -> &'static Mutex<HashMap<u64, oneshot::Sender<LoginProbe>>> {
+  PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Résultat renvoyé par le script (commande `set_probe`). Une réponse tardive ou inconnue est ignorée.
+pub fn resolve(probe: LoginProbe) {
+  if let Some(tx) = pending().lock().unwrap().remove(&probe.request_id) {
+    let _ = tx.send(probe);
+  }
+}
+
+fn script(request_id: u64) -> String {
+  format!(r##"
+    (function() {{
+      try {{
+        const invoke = window.__TAURI__?.core?.invoke || window.__TAURI_INTERNALS__?.invoke;
+        if (!invoke) return;
+
+        const docs = [document];
+        for (const fr of document.querySelectorAll('iframe')) {{
+          try {{ if (fr.contentDocument) docs.push(fr.contentDocument); }} catch (e) {{}}
+        }}
+        const any = (sel) => docs.some(d => {{ try {{ return !!d.querySelector(sel); }} catch (e) {{ return false; }} }});
+        // Widget affiché à l'écran: le reCAPTCHA invisible (badge, size=invisible) ne demande rien
+        const shown = (el) => {{
+          if (el.closest('.grecaptcha-badge, [data-size=invisible]')) return false;
+          if (/[?&]size=invisible/.test(el.getAttribute('src') || '')) return false;
+          const r = el.getBoundingClientRect();
+          const st = (el.ownerDocument.defaultView || window).getComputedStyle(el);
+          return r.width > 0 && r.height > 0 && st.visibility !== 'hidden';
+        }};
+        const anyShown = (sel) => docs.some(d => {{ try {{ return [...d.querySelectorAll(sel)].some(shown); }} catch (e) {{ return false; }} }});
+
+        const txt = (document.body?.innerText || "").toLowerCase();
+        const hasLogout =
+          txt.includes("déconnexion") ||
+          any("a[href*='logout'], a[href*='deconnexion'], button[aria-label*='déconnexion' i]");
+        const hasLoginForm = any("input[type=password]");
+        const hasCaptcha =
+          anyShown("iframe[src*='recaptcha'], iframe[src*='hcaptcha'], iframe[src*='turnstile'], .g-recaptcha, .h-captcha, .cf-turnstile, #captcha");
+        const hasTwoFactor =
+          any("input[autocomplete='one-time-code'], input[name='otp'], input[name='totp'], #kc-otp-login-form");
+        const banner = document.querySelector("#didomi-notice, #didomi-popup, #onetrust-banner-sdk, #tarteaucitronAlertBig, .cookie-banner");
+        const hasCookieBanner = !!banner && banner.offsetParent !== null;
+
+        let errorText = null;
+        for (const d of docs) {{
+          const el = d.querySelector("#input-error, .kc-feedback-text, .alert-error, .alert-danger, .error-message, [role=alert]");
+          const t = (el?.innerText || "").trim();
+          if (t) {{ errorText = t.slice(0, 200); break; }}
+        }}
+
+        invoke('set_probe', {{
+          probe: {{
+            requestId: {request_id},
+            href: window.location.href,
+            title: document.title || "",
+            hasLogout,
+            hasLoginForm,
+            hasCaptcha,
+            hasTwoFactor,
+            hasCookieBanner,
+            errorText
+          }}
+        }});
+      }} catch (e) {{}}
+    }})();
+  "##)
+}
+
+/// Lance une sonde dans la WebView et attend sa réponse (None si rien n'est revenu avant `timeout`).
+pub async fn run(w: &tauri::WebviewWindow, timeout: Duration) -> Option<LoginProbe> {
+  let id = NEXT_ID.fetch_add(1, Ordering::SeqCst);
+  let (tx, rx) = oneshot::channel();
+  pending().lock().unwrap().insert(id, tx);
+
+  let res = match w.eval(script(id)) {
+    Ok(()) => tokio::time::timeout(timeout, rx).await.ok().and_then(Result::ok),
+    Err(_) => None,
+  };
+  pending().lock().unwrap().remove(&id);
+  res
+}
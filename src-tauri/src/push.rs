@@ -0,0 +1,306 @@
+use anyhow::{bail, Context};
+use rusqlite::Connection;
+use crate::{db, http, models::{Alert, PushChannel, PushKind, Severity}};
+
+/// Message push générique, traduit ensuite vers ntfy ou Gotify.
+pub struct PushMessage {
+  pub title: String,
+  pub message: String,
+  pub severity: Severity,
+  /// URL ouverte au clic sur la notification (page de l'épreuve)
+  pub click_url: Option<String>,
+}
+
+impl PushMessage {
+  pub fn from_alert(alert: &Alert) -> Self {
+    let mut parts = Vec::new();
+    if let Some(d) = alert.detail.as_deref() { parts.push(d.to_string()); }
+    if let Some(c) = alert.cavalier.as_deref() { parts.push(c.to_string()); }
+    if let Some(h) = alert.cheval.as_deref() { parts.push(h.to_string()); }
+    parts.push(alert.url.clone());
+
+    Self {
+      title: alert.title(),
+      message: parts.join(" — "),
+      severity: alert.kind.severity(),
+      click_url: Some(alert.url.clone()),
+    }
+  }
+}
+
+/// Priorité ntfy: 1 (min) .. 5 (max)
+fn ntfy_priority(s: Severity) -> u8 {
+  match s {
+    Severity::Info => 3,
+    Severity::High => 4,
+    Severity::Critical => 5,
+  }
+}
+
+/// Priorité Gotify: 0 .. 10 (>= 8 => notification "forte" sur Android)
+fn gotify_priority(s: Severity) -> u8 {
+  match s {
+    Severity::Info => 4,
+    Severity::High => 8,
+    Severity::Critical => 10,
+  }
+}
+
+/// Publication ntfy au format JSON (POST sur la racine du serveur, topic dans le corps),
+/// ce qui évite les soucis d'encodage des headers `Title`/`Click` avec des accents.
+async fn send_ntfy(client: &reqwest::Client, ch: &PushChannel, msg: &PushMessage) -> anyhow::Result<()> {
+  let topic = ch.topic.as_deref().map(str::trim).filter(|t| !t.is_empty())
+    .context("topic ntfy manquant")?;
+
+  let mut body = serde_json::json!({
+    "topic": topic,
+    "title": msg.title,
+    "message": msg.message,
+    "priority": ntfy_priority(msg.severity),
+    "tags": if msg.severity == Severity::Critical { vec!["rotating_light"] } else { vec!["horse"] },
+  });
+  if let Some(url) = msg.click_url.as_deref() {
+    body["click"] = serde_json::json!(url);
+  }
+
+  let mut req = client.post(ch.server_url.trim_end_matches('/')).json(&body);
+  if let Some(token) = ch.token.as_deref().filter(|t| !t.trim().is_empty()) {
+    req = req.bearer_auth(token.trim());
+  }
+
+  let resp = req.send().await.context("ntfy")?;
+  if !resp.status().is_success() {
+    bail!("ntfy: HTTP {}", resp.status());
+  }
+  Ok(())
+}
+
+async fn send_gotify(client: &reqwest::Client, ch: &PushChannel, msg: &PushMessage) -> anyhow::Result<()> {
+  let token = ch.token.as_deref().map(str::trim).filter(|t| !t.is_empty())
+    .context("token d'application Gotify manquant")?;
+
+  let mut body = serde_json::json!({
+    "title": msg.title,
+    "message": msg.message,
+    "priority": gotify_priority(msg.severity),
+  });
+  if let Some(url) = msg.click_url.as_deref() {
+    body["extras"] = serde_json::json!({
+      "client::notification": { "click": { "url": url } }
+    });
+  }
+
+  let url = format!("{}/message", ch.server_url.trim_end_matches('/'));
+  let resp = client
+    .post(url)
+    .header("X-Gotify-Key", token)
+    .json(&body)
+    .send()
+    .await
+    .context("gotify")?;
+  if !resp.status().is_success() {
+    bail!("gotify: HTTP {}", resp.status());
+  }
+  Ok(())
+}
+
+/// Client partagé sans cookies (les services push ne doivent pas recevoir la session FFE).
+pub fn client() -> anyhow::Result<reqwest::Client> {
+  http::client(None)
+}
+
+pub async fn send(client: &reqwest::Client, ch: &PushChannel, msg: &PushMessage) -> anyhow::Result<()> {
+  match ch.kind {
+    PushKind::Ntfy => send_ntfy(client, ch, msg).await,
+    PushKind::Gotify => send_gotify(client, ch, msg).await,
+  }
+}
+
+/// Envoie une alerte sur tous les canaux de la cible et de son cavalier.
+/// Un canal en erreur n'empêche pas les autres d'être servis.
+pub async fn send_alert(client: &reqwest::Client, db_path: std::path::PathBuf, alert: Alert) -> anyhow::Result<()> {
+  let channels = {
+    let conn = Connection::open(&db_path)?;
+    db::push_channels_for(&conn, alert.target_id, alert.cavalier.as_deref())?
+  };
+
+  let msg = PushMessage::from_alert(&alert);
+  let mut errors = Vec::new();
+  for ch in &channels {
+    if let Err(e) = send(client, ch, &msg).await {
+      errors.push(format!("#{}: {e:#}", ch.id));
+    }
+  }
+
+  if !errors.is_empty() {
+    bail!(errors.join(" | "));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::collections::HashMap;
+  use std::sync::{Arc, Mutex};
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+  use tokio::net::TcpListener;
+  use crate::models::AlertKind;
+
+  #[derive(Debug)]
+  struct Captured {
+    method: String,
+    path: String,
+    /// Noms en minuscules
+    headers: HashMap<String, String>,
+    body: serde_json::Value,
+  }
+
+  /// Serveur HTTP local: garde chaque requête et répond `status`.
+  async fn push_server(status: u16) -> (String, Arc<Mutex<Vec<Captured>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let seen: Arc<Mutex<Vec<Captured>>> = Arc::default();
+    let store = seen.clone();
+    tokio::spawn(async move {
+      while let Ok((mut sock, _)) = listener.accept().await {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let head_end = loop {
+          let n = sock.read(&mut chunk).await.unwrap();
+          if n == 0 { return; }
+          buf.extend_from_slice(&chunk[..n]);
+          if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") { break i + 4; }
+        };
+        let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+        let mut lines = head.split("\r\n");
+        let mut request = lines.next().unwrap().split(' ');
+        let (method, path) = (request.next().unwrap().to_string(), request.next().unwrap().to_string());
+        let headers: HashMap<String, String> = lines
+          .filter_map(|l| l.split_once(':'))
+          .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim().to_string()))
+          .collect();
+        let len: usize = headers.get("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+        while buf.len() < head_end + len {
+          let n = sock.read(&mut chunk).await.unwrap();
+          buf.extend_from_slice(&chunk[..n]);
+        }
+        let body = serde_json::from_slice(&buf[head_end..head_end + len]).unwrap_or(serde_json::Value::Null);
+        store.lock().unwrap().push(Captured { method, path, headers, body });
+        let resp = format!("HTTP/1.1 {status} X\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}");
+        let _ = sock.write_all(resp.as_bytes()).await;
+      }
+    });
+    (base, seen)
+  }
+
+  fn channel(kind: PushKind, server_url: String, topic: Option<&str>, token: Option<&str>) -> PushChannel {
+    PushChannel { id: 1, kind, server_url, topic: topic.map(str::to_string), token: token.map(str::to_string), cavalier: None, target_id: None }
+  }
+
+  fn message(severity: Severity, click_url: Option<&str>) -> PushMessage {
+    PushMessage { title: "Engagements ouverts — Épreuve 2".into(), message: "Marie Dupont".into(), severity, click_url: click_url.map(str::to_string) }
+  }
+
+  #[test]
+  fn priorities() {
+    assert_eq!([Severity::Info, Severity::High, Severity::Critical].map(ntfy_priority), [3, 4, 5]);
+    assert_eq!([Severity::Info, Severity::High, Severity::Critical].map(gotify_priority), [4, 8, 10]);
+  }
+
+  #[test]
+  fn message_from_alert() {
+    let alert = Alert {
+      target_id: 3,
+      kind: AlertKind::Opened,
+      label: "Epreuve 2".into(),
+      url: "https://ffecompet.ffe.com/concours/202635080?watch_epreuve=2".into(),
+      cavalier: Some("Marie Dupont".into()),
+      cheval: Some("Quartz du Bois".into()),
+      account: None,
+      detail: None,
+    };
+    let msg = PushMessage::from_alert(&alert);
+    assert_eq!(msg.title, "Engagements ouverts — Epreuve 2");
+    assert_eq!(msg.message, "Marie Dupont — Quartz du Bois — https://ffecompet.ffe.com/concours/202635080?watch_epreuve=2");
+    assert_eq!(msg.severity, Severity::Critical);
+    assert_eq!(msg.click_url.as_deref(), Some(alert.url.as_str()));
+  }
+
+  #[tokio::test]
+  async fn ntfy_request() {
+    let (base, seen) = push_server(200).await;
+    let client = reqwest::Client::new();
+    let ch = channel(PushKind::Ntfy, format!("{base}/"), Some(" ffe-alertes "), Some(" tk_secret "));
+    send(&client, &ch, &message(Severity::Critical, Some("https://ffecompet.ffe.com/concours/202635080"))).await.unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 1);
+    let r = &seen[0];
+    assert_eq!((r.method.as_str(), r.path.as_str()), ("POST", "/"));
+    assert_eq!(r.headers["authorization"], "Bearer tk_secret");
+    assert_eq!(r.headers["content-type"], "application/json");
+    assert_eq!(r.body, serde_json::json!({
+      "topic": "ffe-alertes",
+      "title": "Engagements ouverts — Épreuve 2",
+      "message": "Marie Dupont",
+      "priority": 5,
+      "tags": ["rotating_light"],
+      "click": "https://ffecompet.ffe.com/concours/202635080",
+    }));
+  }
+
+  #[tokio::test]
+  async fn ntfy_without_token_or_click() {
+    let (base, seen) = push_server(200).await;
+    let ch = channel(PushKind::Ntfy, format!("{base}/ntfy/"), Some("ffe"), Some("  "));
+    send(&reqwest::Client::new(), &ch, &message(Severity::Info, None)).await.unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen[0].path, "/ntfy");
+    assert!(!seen[0].headers.contains_key("authorization"));
+    assert_eq!(seen[0].body["priority"], 3);
+    assert_eq!(seen[0].body["tags"], serde_json::json!(["horse"]));
+    assert!(seen[0].body.get("click").is_none());
+  }
+
+  #[tokio::test]
+  async fn gotify_request() {
+    let (base, seen) = push_server(200).await;
+    let ch = channel(PushKind::Gotify, format!("{base}/gotify/"), Some("ignoré"), Some(" AppToken1 "));
+    send(&reqwest::Client::new(), &ch, &message(Severity::High, Some("https://ffecompet.ffe.com/concours/202635080"))).await.unwrap();
+
+    let seen = seen.lock().unwrap();
+    let r = &seen[0];
+    assert_eq!((r.method.as_str(), r.path.as_str()), ("POST", "/gotify/message"));
+    assert_eq!(r.headers["x-gotify-key"], "AppToken1");
+    assert!(!r.headers.contains_key("authorization"));
+    assert_eq!(r.body, serde_json::json!({
+      "title": "Engagements ouverts — Épreuve 2",
+      "message": "Marie Dupont",
+      "priority": 8,
+      "extras": { "client::notification": { "click": { "url": "https://ffecompet.ffe.com/concours/202635080" } } },
+    }));
+  }
+
+  #[tokio::test]
+  async fn missing_topic_or_token() {
+    let (base, seen) = push_server(200).await;
+    let client = reqwest::Client::new();
+    let err = send(&client, &channel(PushKind::Ntfy, base.clone(), Some(" "), None), &message(Severity::Info, None)).await.unwrap_err();
+    assert_eq!(err.to_string(), "topic ntfy manquant");
+    let err = send(&client, &channel(PushKind::Gotify, base.clone(), None, None), &message(Severity::Info, None)).await.unwrap_err();
+    assert_eq!(err.to_string(), "token d'application Gotify manquant");
+    assert!(seen.lock().unwrap().is_empty());
+  }
+
+  #[tokio::test]
+  async fn http_errors() {
+    let (base, _) = push_server(403).await;
+    let client = reqwest::Client::new();
+    let err = send(&client, &channel(PushKind::Ntfy, base.clone(), Some("ffe"), None), &message(Severity::Info, None)).await.unwrap_err();
+    assert_eq!(err.to_string(), "ntfy: HTTP 403 Forbidden");
+    let err = send(&client, &channel(PushKind::Gotify, base, None, Some("t")), &message(Severity::Info, None)).await.unwrap_err();
+    assert_eq!(err.to_string(), "gotify: HTTP 403 Forbidden");
+  }
+}
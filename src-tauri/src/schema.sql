@@ -22,3 +22,76 @@ CREATE TABLE IF NOT EXISTS events (
   note TEXT,
   FOREIGN KEY(target_id) REFERENCES targets(id) ON DELETE CASCADE
 );
+
+CREATE TABLE IF NOT EXISTS settings (
+  key TEXT PRIMARY KEY,
+  value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS email_recipients (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  cavalier TEXT NOT NULL COLLATE NOCASE,
+  email TEXT NOT NULL COLLATE NOCASE,
+  UNIQUE(cavalier, email)
+);
+
+CREATE TABLE IF NOT EXISTS push_channels (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  kind TEXT NOT NULL,
+  server_url TEXT NOT NULL,
+  topic TEXT,
+  token TEXT,
+  cavalier TEXT COLLATE NOCASE,
+  target_id INTEGER,
+  FOREIGN KEY(target_id) REFERENCES targets(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS target_snoozes (
+  target_id INTEGER PRIMARY KEY,
+  until INTEGER NOT NULL,
+  FOREIGN KEY(target_id) REFERENCES targets(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS queued_alerts (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  target_id INTEGER NOT NULL,
+  ts INTEGER NOT NULL,
+  payload TEXT NOT NULL,
+  FOREIGN KEY(target_id) REFERENCES targets(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS ffe_accounts (
+  name TEXT PRIMARY KEY,
+  created_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS page_cache (
+  target_id INTEGER PRIMARY KEY,
+  etag TEXT,
+  last_modified TEXT,
+  body_hash TEXT,
+  checked_at INTEGER NOT NULL,
+  FOREIGN KEY(target_id) REFERENCES targets(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS contests (
+  id TEXT PRIMARY KEY,
+  start_date TEXT,
+  payload TEXT NOT NULL,
+  fetched_at INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contest_cache (
+  contest_id TEXT PRIMARY KEY,
+  payload TEXT NOT NULL,
+  payload_hash TEXT NOT NULL,
+  fetched_at INTEGER NOT NULL,
+  ttl_sec INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS contest_snapshots (
+  target_id INTEGER PRIMARY KEY,
+  payload TEXT NOT NULL,
+  checked_at INTEGER NOT NULL,
+  FOREIGN KEY(target_id) REFERENCES targets(id) ON DELETE CASCADE
+);
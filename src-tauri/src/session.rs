@@ -0,0 +1,443 @@
+// Persistance chiffrée de la session FFE (cookies) dans le dossier de données de l'app.
+//
+// Deux modes de clé:
+// - "keyfile": clé aléatoire de 32 octets dans `session.key` (restauration automatique au démarrage)
+// - "passphrase": clé dérivée (Argon2id) d'une phrase de passe saisie par l'utilisateur
+//   (la session reste verrouillée tant que la phrase n'est pas fournie)
+//
+// Une session par compte FFE: `ffe_session.bin` pour le compte par défaut,
+// `ffe_session-<compte>.bin` pour les comptes nommés (même clé / phrase de passe).
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::STANDARD as B64, Engine};
+use chacha20poly1305::{
+  aead::{Aead, KeyInit},
+  XChaCha20Poly1305, XNonce,
+};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use crate::{accounts::{self, DEFAULT_ACCOUNT}, cookie_jar::{CookieJar, StoredCookie}};
+
+const SESSION_FILE: &str = "ffe_session.bin";
+const SESSION_PREFIX: &str = "ffe_session-";
+const KEY_FILE: &str = "session.key";
+
+// Phrase de passe en mémoire uniquement (jamais écrite sur disque)
+static PASSPHRASE: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyMode {
+  Keyfile,
+  Passphrase,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+  v: u32,
+  mode: KeyMode,
+  /// Sel Argon2 (mode passphrase)
+  salt: Option<String>,
+  nonce: String,
+  data: String,
+}
+
+pub fn set_passphrase(passphrase: Option<String>) {
+  let cell = PASSPHRASE.get_or_init(|| Mutex::new(None));
+  *cell.lock().unwrap() = passphrase.filter(|p| !p.is_empty());
+}
+
+fn passphrase() -> Option<String> {
+  PASSPHRASE.get().and_then(|m| m.lock().unwrap().clone())
+}
+
+fn session_path(app_data_dir: &Path, account: &str) -> PathBuf {
+  if account == DEFAULT_ACCOUNT {
+    app_data_dir.join(SESSION_FILE)
+  } else {
+    app_data_dir.join(format!("{SESSION_PREFIX}{account}.bin"))
+  }
+}
+
+/// Comptes ayant une session enregistrée sur disque.
+pub fn stored_accounts(app_data_dir: &Path) -> Vec<String> {
+  let Ok(entries) = std::fs::read_dir(app_data_dir) else { return Vec::new(); };
+  let mut out: Vec<String> = entries
+    .filter_map(|e| e.ok()?.file_name().into_string().ok())
+    .filter_map(|f| {
+      if f == SESSION_FILE {
+        return Some(DEFAULT_ACCOUNT.to_string());
+      }
+      f.strip_prefix(SESSION_PREFIX)?.strip_suffix(".bin").map(str::to_string)
+    })
+    .collect();
+  out.sort();
+  out
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+  let mut b = [0u8; N];
+  rand::thread_rng().fill_bytes(&mut b);
+  b
+}
+
+/// Clé du mode "keyfile", créée au premier usage. Le fichier n'est lisible que par
+/// l'utilisateur (0600 sous Unix, droits resserrés s'il a été créé plus large).
+fn keyfile_key(app_data_dir: &Path) -> anyhow::Result<[u8; 32]> {
+  let path = app_data_dir.join(KEY_FILE);
+  if let Ok(raw) = std::fs::read(&path) {
+    restrict_permissions(&path)?;
+    return raw.as_slice().try_into().map_err(|_| anyhow!("fichier de clé invalide: {}", path.display()));
+  }
+
+  let key = random_bytes::<32>();
+  std::fs::create_dir_all(app_data_dir)?;
+  let mut opts = std::fs::OpenOptions::new();
+  opts.write(true).create_new(true);
+  #[cfg(unix)]
+  std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+  let mut file = match opts.open(&path) {
+    Ok(f) => f,
+    // Créé entre-temps (autre tâche): on relit celui-ci
+    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return keyfile_key(app_data_dir),
+    Err(e) => return Err(e).with_context(|| format!("création {}", path.display())),
+  };
+  std::io::Write::write_all(&mut file, &key).with_context(|| format!("écriture {}", path.display()))?;
+  Ok(key)
+}
+
+#[cfg(unix)]
+fn restrict_permissions(path: &Path) -> anyhow::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  let mode = std::fs::metadata(path)?.permissions().mode();
+  if mode & 0o077 != 0 {
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+      .with_context(|| format!("droits de {}", path.display()))?;
+  }
+  Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_path: &Path) -> anyhow::Result<()> {
+  Ok(())
+}
+
+fn passphrase_key(passphrase: &str, salt: &[u8]) -> anyhow::Result<[u8; 32]> {
+  let mut key = [0u8; 32];
+  argon2::Argon2::default()
+    .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+    .map_err(|e| anyhow!("dérivation de clé: {e}"))?;
+  Ok(key)
+}
+
+/// Session protégée par phrase de passe alors qu'aucune phrase n'est en mémoire.
+pub fn is_locked(app_data_dir: &Path, account: &str) -> bool {
+  passphrase().is_none() && stored_mode(app_data_dir, account) == Some(KeyMode::Passphrase)
+}
+
+/// Chiffre et écrit la session. Utilise la phrase de passe si elle est définie, sinon le fichier de clé.
+/// Une session protégée par phrase de passe n'est jamais ré-écrite avec le fichier de clé tant
+/// qu'elle est verrouillée (cf. `clear` pour abandonner explicitement la phrase de passe).
+fn save(app_data_dir: &Path, account: &str, plain: &str) -> anyhow::Result<()> {
+  if is_locked(app_data_dir, account) {
+    bail!("session verrouillée: saisis la phrase de passe avant d'enregistrer la session");
+  }
+  save_with(app_data_dir, account, plain, passphrase().as_deref())
+}
+
+fn save_with(app_data_dir: &Path, account: &str, plain: &str, passphrase: Option<&str>) -> anyhow::Result<()> {
+  let (mode, salt, key) = match passphrase {
+    Some(p) => {
+      let salt = random_bytes::<16>();
+      (KeyMode::Passphrase, Some(B64.encode(salt)), passphrase_key(p, &salt)?)
+    }
+    None => (KeyMode::Keyfile, None, keyfile_key(app_data_dir)?),
+  };
+
+  let nonce = random_bytes::<24>();
+  let cipher = XChaCha20Poly1305::new(&key.into());
+  let data = cipher
+    .encrypt(XNonce::from_slice(&nonce), plain.as_bytes())
+    .map_err(|_| anyhow!("chiffrement de la session impossible"))?;
+
+  let env = Envelope { v: 1, mode, salt, nonce: B64.encode(nonce), data: B64.encode(data) };
+  std::fs::create_dir_all(app_data_dir)?;
+  std::fs::write(session_path(app_data_dir, account), serde_json::to_vec(&env)?)?;
+  Ok(())
+}
+
+/// Mode de clé de la session enregistrée (None si aucune session sur disque).
+pub fn stored_mode(app_data_dir: &Path, account: &str) -> Option<KeyMode> {
+  let raw = std::fs::read(session_path(app_data_dir, account)).ok()?;
+  serde_json::from_slice::<Envelope>(&raw).ok().map(|e| e.mode)
+}
+
+/// Déchiffre la session enregistrée. `Ok(None)` si aucune session n'est enregistrée.
+fn load(app_data_dir: &Path, account: &str) -> anyhow::Result<Option<String>> {
+  load_with(app_data_dir, account, passphrase().as_deref())
+}
+
+fn load_with(app_data_dir: &Path, account: &str, passphrase: Option<&str>) -> anyhow::Result<Option<String>> {
+  let raw = match std::fs::read(session_path(app_data_dir, account)) {
+    Ok(r) => r,
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+    Err(e) => return Err(e.into()),
+  };
+  let env: Envelope = serde_json::from_slice(&raw).context("fichier de session illisible")?;
+  if env.v != 1 {
+    bail!("version de session inconnue: {}", env.v);
+  }
+
+  let key = match env.mode {
+    KeyMode::Keyfile => keyfile_key(app_data_dir)?,
+    KeyMode::Passphrase => {
+      let p = passphrase.context("session verrouillée: phrase de passe requise")?;
+      let salt = B64.decode(env.salt.as_deref().unwrap_or_default())?;
+      passphrase_key(p, &salt)?
+    }
+  };
+
+  let nonce = B64.decode(&env.nonce)?;
+  if nonce.len() != 24 {
+    bail!("nonce de session invalide");
+  }
+  let data = B64.decode(&env.data)?;
+  let plain = XChaCha20Poly1305::new(&key.into())
+    .decrypt(XNonce::from_slice(&nonce), data.as_slice())
+    .map_err(|_| anyhow!("déchiffrement impossible (clé ou phrase de passe incorrecte)"))?;
+
+  Ok(Some(String::from_utf8(plain)?))
+}
+
+/// Vérifie la phrase de passe sur toutes les sessions protégées enregistrées, sans la garder
+/// en mémoire. Err si l'une d'elles ne se déchiffre pas.
+pub fn verify_passphrase(app_data_dir: &Path, passphrase: &str) -> anyhow::Result<()> {
+  if passphrase.is_empty() {
+    bail!("phrase de passe vide");
+  }
+  for account in stored_accounts(app_data_dir) {
+    if stored_mode(app_data_dir, &account) == Some(KeyMode::Passphrase) {
+      load_with(app_data_dir, &account, Some(passphrase)).with_context(|| format!("session {account}"))?;
+    }
+  }
+  Ok(())
+}
+
+/// Persiste le contenu du cookie jar (JSON chiffré).
+pub fn save_jar(app_data_dir: &Path, account: &str, jar: &CookieJar) -> anyhow::Result<()> {
+  save(app_data_dir, account, &serde_json::to_string(&jar.snapshot())?)
+}
+
+/// Relit les cookies enregistrés (accepte aussi l'ancien format "a=b; c=d").
+pub fn load_jar(app_data_dir: &Path, account: &str) -> anyhow::Result<Option<Vec<StoredCookie>>> {
+  Ok(load(app_data_dir, account)?.map(|plain| {
+    serde_json::from_str::<Vec<StoredCookie>>(&plain)
+      .unwrap_or_else(|_| StoredCookie::from_legacy_header(&plain))
+  }))
+}
+
+/// Réécrit sur disque la session de chaque compte dont le jar a changé
+/// (ex: `Set-Cookie` reçu par le watcher). Une erreur d'écriture est remontée par `on_error`
+/// une seule fois tant qu'elle se répète.
+pub async fn autosave_loop(app_data_dir: PathBuf, on_error: Arc<dyn Fn(String) + Send + Sync>) {
+  let mut saved: HashMap<String, u64> = HashMap::new();
+  let mut failed: HashMap<String, String> = HashMap::new();
+  loop {
+    tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+    for (account, jar) in accounts::all_jars() {
+      let generation = jar.generation();
+      if saved.get(&account) == Some(&generation) {
+        continue;
+      }
+      // Session vidée (logout) : le fichier est supprimé par ffe_logout, rien à écrire.
+      // Session verrouillée: réécrite au déverrouillage.
+      if is_locked(&app_data_dir, &account) {
+        continue;
+      }
+      let res = if jar.is_empty() { Ok(()) } else { save_jar(&app_data_dir, &account, &jar) };
+      match res {
+        Ok(()) => {
+          failed.remove(&account);
+          saved.insert(account, generation);
+        }
+        Err(e) => {
+          let msg = format!("sauvegarde impossible ({account}): {e:#}");
+          if failed.get(&account) != Some(&msg) {
+            on_error(msg.clone());
+            failed.insert(account, msg);
+          }
+        }
+      }
+    }
+  }
+}
+
+pub fn clear(app_data_dir: &Path, account: &str) -> anyhow::Result<()> {
+  match std::fs::remove_file(session_path(app_data_dir, account)) {
+    Ok(()) => Ok(()),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+    Err(e) => Err(e.into()),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Dossier de données jetable (un par test: les tests tournent en parallèle).
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ffe_session_{name}_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn envelope(dir: &Path, account: &str) -> Envelope {
+    serde_json::from_slice(&std::fs::read(session_path(dir, account)).unwrap()).unwrap()
+  }
+
+  fn write_envelope(dir: &Path, account: &str, env: &Envelope) {
+    std::fs::write(session_path(dir, account), serde_json::to_vec(env).unwrap()).unwrap();
+  }
+
+  const PLAIN: &str = r#"[{"name":"SESSION","value":"abc"}]"#;
+
+  #[test]
+  fn keyfile_round_trip() {
+    let dir = temp_dir("keyfile");
+    save_with(&dir, DEFAULT_ACCOUNT, PLAIN, None).unwrap();
+
+    let env = envelope(&dir, DEFAULT_ACCOUNT);
+    assert_eq!((env.v, env.mode, env.salt), (1, KeyMode::Keyfile, None));
+    assert!(!std::fs::read_to_string(session_path(&dir, DEFAULT_ACCOUNT)).unwrap().contains("SESSION"));
+    assert_eq!(std::fs::read(dir.join(KEY_FILE)).unwrap().len(), 32);
+    assert_eq!(stored_mode(&dir, DEFAULT_ACCOUNT), Some(KeyMode::Keyfile));
+    assert_eq!(load_with(&dir, DEFAULT_ACCOUNT, None).unwrap().as_deref(), Some(PLAIN));
+    // Une phrase de passe en mémoire n'empêche pas de relire une session "keyfile"
+    assert_eq!(load_with(&dir, DEFAULT_ACCOUNT, Some("x")).unwrap().as_deref(), Some(PLAIN));
+
+    // Même clé pour un second compte; nonce différent à chaque écriture
+    save_with(&dir, "club", PLAIN, None).unwrap();
+    assert_ne!(envelope(&dir, "club").nonce, envelope(&dir, DEFAULT_ACCOUNT).nonce);
+    assert_eq!(load_with(&dir, "club", None).unwrap().as_deref(), Some(PLAIN));
+    assert_eq!(stored_accounts(&dir), vec!["club".to_string(), DEFAULT_ACCOUNT.to_string()]);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn keyfile_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = temp_dir("keyfile_mode");
+    let mode = |p: &Path| std::fs::metadata(p).unwrap().permissions().mode() & 0o777;
+    keyfile_key(&dir).unwrap();
+    assert_eq!(mode(&dir.join(KEY_FILE)), 0o600);
+
+    // Fichier existant trop ouvert: droits resserrés à la lecture, même clé
+    let key = keyfile_key(&dir).unwrap();
+    std::fs::set_permissions(dir.join(KEY_FILE), std::fs::Permissions::from_mode(0o644)).unwrap();
+    assert_eq!(keyfile_key(&dir).unwrap(), key);
+    assert_eq!(mode(&dir.join(KEY_FILE)), 0o600);
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn passphrase_round_trip_and_wrong_passphrase() {
+    let dir = temp_dir("passphrase");
+    save_with(&dir, DEFAULT_ACCOUNT, PLAIN, Some("cheval de course")).unwrap();
+
+    let env = envelope(&dir, DEFAULT_ACCOUNT);
+    assert_eq!(env.mode, KeyMode::Passphrase);
+    assert_eq!(B64.decode(env.salt.unwrap()).unwrap().len(), 16);
+    assert!(!dir.join(KEY_FILE).exists());
+    assert_eq!(load_with(&dir, DEFAULT_ACCOUNT, Some("cheval de course")).unwrap().as_deref(), Some(PLAIN));
+
+    let err = load_with(&dir, DEFAULT_ACCOUNT, Some("cheval de course!")).unwrap_err();
+    assert!(err.to_string().contains("phrase de passe incorrecte"), "{err}");
+    let err = load_with(&dir, DEFAULT_ACCOUNT, None).unwrap_err();
+    assert!(err.to_string().contains("verrouillée"), "{err}");
+
+    assert!(verify_passphrase(&dir, "cheval de course").is_ok());
+    assert!(verify_passphrase(&dir, "autre").is_err());
+    assert!(verify_passphrase(&dir, "").is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn verify_checks_every_protected_session() {
+    let dir = temp_dir("verify");
+    // Aucune session protégée: rien à vérifier
+    save_with(&dir, DEFAULT_ACCOUNT, PLAIN, None).unwrap();
+    assert!(verify_passphrase(&dir, "n'importe laquelle").is_ok());
+
+    save_with(&dir, "club", PLAIN, Some("p1")).unwrap();
+    save_with(&dir, "ecurie", PLAIN, Some("p2")).unwrap();
+    let err = verify_passphrase(&dir, "p1").unwrap_err();
+    assert!(format!("{err:#}").contains("session ecurie"), "{err:#}");
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn tampered_envelope_fails_authentication() {
+    let dir = temp_dir("tampered");
+    for (account, pass) in [(DEFAULT_ACCOUNT, None), ("club", Some("p1"))] {
+      save_with(&dir, account, PLAIN, pass).unwrap();
+      let original = envelope(&dir, account);
+
+      // Un bit du chiffré
+      let mut data = B64.decode(&original.data).unwrap();
+      data[0] ^= 1;
+      write_envelope(&dir, account, &Envelope { data: B64.encode(&data), ..envelope(&dir, account) });
+      assert!(load_with(&dir, account, pass).is_err(), "{account}: chiffré modifié");
+
+      // Tag d'authentification tronqué
+      let mut data = B64.decode(&original.data).unwrap();
+      data.pop();
+      write_envelope(&dir, account, &Envelope { data: B64.encode(&data), ..envelope(&dir, account) });
+      assert!(load_with(&dir, account, pass).is_err(), "{account}: tag tronqué");
+
+      // Autre nonce
+      let mut nonce = B64.decode(&original.nonce).unwrap();
+      nonce[23] ^= 0x80;
+      write_envelope(&dir, account, &Envelope { nonce: B64.encode(&nonce), data: original.data.clone(), ..envelope(&dir, account) });
+      assert!(load_with(&dir, account, pass).is_err(), "{account}: nonce modifié");
+    }
+
+    // Autre sel: autre clé dérivée
+    save_with(&dir, "club", PLAIN, Some("p1")).unwrap();
+    let env = envelope(&dir, "club");
+    write_envelope(&dir, "club", &Envelope { salt: Some(B64.encode([0u8; 16])), ..env });
+    assert!(load_with(&dir, "club", Some("p1")).is_err());
+
+    // Mode basculé sur "keyfile": la clé du fichier ne déchiffre pas
+    save_with(&dir, "club", PLAIN, Some("p1")).unwrap();
+    write_envelope(&dir, "club", &Envelope { mode: KeyMode::Keyfile, ..envelope(&dir, "club") });
+    assert!(load_with(&dir, "club", Some("p1")).is_err());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+
+  #[test]
+  fn missing_or_invalid_files() {
+    let dir = temp_dir("missing");
+    assert_eq!(load_with(&dir, DEFAULT_ACCOUNT, None).unwrap(), None);
+    assert_eq!(stored_mode(&dir, DEFAULT_ACCOUNT), None);
+
+    std::fs::write(session_path(&dir, DEFAULT_ACCOUNT), b"pas du json").unwrap();
+    assert!(load_with(&dir, DEFAULT_ACCOUNT, None).is_err());
+
+    save_with(&dir, DEFAULT_ACCOUNT, PLAIN, None).unwrap();
+    write_envelope(&dir, DEFAULT_ACCOUNT, &Envelope { v: 2, ..envelope(&dir, DEFAULT_ACCOUNT) });
+    assert!(load_with(&dir, DEFAULT_ACCOUNT, None).unwrap_err().to_string().contains("version"));
+
+    std::fs::write(dir.join(KEY_FILE), [0u8; 12]).unwrap();
+    assert!(keyfile_key(&dir).unwrap_err().to_string().contains("fichier de clé invalide"));
+
+    clear(&dir, DEFAULT_ACCOUNT).unwrap();
+    clear(&dir, DEFAULT_ACCOUNT).unwrap();
+    assert!(stored_accounts(&dir).is_empty());
+    let _ = std::fs::remove_dir_all(&dir);
+  }
+}
@@ -0,0 +1,293 @@
+// Texte des pages FFE: décodage du corps selon le charset annoncé (en-tête Content-Type,
+// BOM ou <meta>), décodage des entités HTML (nommées et numériques) et normalisation
+// insensible aux accents / à la casse utilisée par la détection de statut et le parsing des épreuves.
+
+use std::sync::OnceLock;
+use encoding_rs::{Encoding, UTF_8, WINDOWS_1252};
+use regex::Regex;
+use crate::html_entities::ENTITIES;
+
+/// Nombre d'octets examinés pour trouver un `<meta charset>`.
+const META_SNIFF_BYTES: usize = 4096;
+
+fn label_encoding(label: &str) -> Option<&'static Encoding> {
+  let enc = Encoding::for_label(label.trim().trim_matches(|c| c == '"' || c == '\'').as_bytes())?;
+  // Un <meta> UTF-16 ne peut pas être juste (on l'a lu en ASCII): traité comme UTF-8, comme les navigateurs
+  if enc == encoding_rs::UTF_16LE || enc == encoding_rs::UTF_16BE {
+    return Some(UTF_8);
+  }
+  Some(enc)
+}
+
+/// Charset de l'en-tête `Content-Type` (ex: `text/html; charset=ISO-8859-1`).
+pub fn charset_from_content_type(content_type: &str) -> Option<&'static Encoding> {
+  content_type.split(';').skip(1).find_map(|param| {
+    let (k, v) = param.split_once('=')?;
+    if k.trim().eq_ignore_ascii_case("charset") {
+      label_encoding(v)
+    } else {
+      None
+    }
+  })
+}
+
+fn meta_re() -> &'static Regex {
+  static RE: OnceLock<Regex> = OnceLock::new();
+  // <meta charset="..."> et <meta http-equiv="Content-Type" content="text/html; charset=...">
+  RE.get_or_init(|| Regex::new(r#"(?i)<meta\b[^>]*?charset\s*=\s*["']?\s*([a-z0-9_.:\-]+)"#).unwrap())
+}
+
+/// Charset déclaré par un `<meta>` en tête de document.
+pub fn charset_from_meta(head: &[u8]) -> Option<&'static Encoding> {
+  let head = String::from_utf8_lossy(&head[..head.len().min(META_SNIFF_BYTES)]);
+  let cap = meta_re().captures(&head)?;
+  label_encoding(&cap[1])
+}
+
+/// Décode un corps de réponse. Priorité: BOM, en-tête HTTP, `<meta>`, puis UTF-8
+/// (repli en Windows-1252 si les octets ne sont pas de l'UTF-8 valide).
+pub fn decode_body(bytes: &[u8], content_type: Option<&str>) -> String {
+  if let Some((enc, bom_len)) = Encoding::for_bom(bytes) {
+    return enc.decode_without_bom_handling(&bytes[bom_len..]).0.into_owned();
+  }
+
+  let declared = content_type
+    .and_then(charset_from_content_type)
+    .or_else(|| charset_from_meta(bytes));
+  if let Some(enc) = declared {
+    return enc.decode_without_bom_handling(bytes).0.into_owned();
+  }
+
+  match std::str::from_utf8(bytes) {
+    Ok(s) => s.to_string(),
+    Err(_) => WINDOWS_1252.decode_without_bom_handling(bytes).0.into_owned(),
+  }
+}
+
+/// Entité nommée HTML5 (table WHATWG complète, cf. html_entities.rs).
+fn named_entity(name: &str) -> Option<&'static str> {
+  ENTITIES.binary_search_by(|(n, _)| (*n).cmp(name)).ok().map(|i| ENTITIES[i].1)
+}
+
+/// Caractère d'une référence numérique. Les codes 0x80-0x9F sont lus en Windows-1252
+/// (`&#146;` = ’), les codes invalides deviennent U+FFFD, comme dans les navigateurs.
+fn numeric_char(code: u32) -> char {
+  if (0x80..=0x9f).contains(&code) {
+    let byte = [code as u8];
+    return WINDOWS_1252.decode_without_bom_handling(&byte).0.chars().next().unwrap_or('\u{fffd}');
+  }
+  match code {
+    0 => '\u{fffd}',
+    c => char::from_u32(c).unwrap_or('\u{fffd}'),
+  }
+}
+
+/// Décode les entités HTML (`&eacute;`, `&#233;`, `&#xE9;`). Une entité inconnue ou mal formée
+/// est laissée telle quelle.
+pub fn decode_entities(s: &str) -> String {
+  if !s.contains('&') {
+    return s.to_string();
+  }
+
+  let mut out = String::with_capacity(s.len());
+  let mut rest = s;
+  while let Some(pos) = rest.find('&') {
+    out.push_str(&rest[..pos]);
+    rest = &rest[pos..];
+
+    // Une référence tient en moins de 32 caractères et se termine par ';'
+    // (le nom le plus long de la table en fait 31)
+    let end = rest[1..].char_indices().take(32).find(|(_, c)| *c == ';' || *c == '&' || c.is_whitespace());
+    let decoded = match end {
+      Some((i, ';')) => {
+        let name = &rest[1..1 + i];
+        let value = if let Some(num) = name.strip_prefix('#') {
+          let code = match num.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => num.parse::<u32>().ok(),
+          };
+          code.map(|c| numeric_char(c).to_string())
+        } else {
+          named_entity(name).map(str::to_string)
+        };
+        value.map(|v| (v, i + 2))
+      }
+      _ => None,
+    };
+
+    match decoded {
+      Some((value, len)) => {
+        out.push_str(&value);
+        rest = &rest[len..];
+      }
+      None => {
+        out.push('&');
+        rest = &rest[1..];
+      }
+    }
+  }
+  out.push_str(rest);
+  out
+}
+
+/// Lettre de base d'un caractère accentué (ou ligature) courant en français.
+fn fold_char(c: char) -> Option<&'static str> {
+  Some(match c {
+    'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+    'ç' | 'ć' | 'č' => "c",
+    'ď' | 'đ' => "d",
+    'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ė' | 'ę' | 'ě' => "e",
+    'ì' | 'í' | 'î' | 'ï' | 'ī' | 'į' => "i",
+    'ł' | 'ľ' => "l",
+    'ñ' | 'ń' | 'ň' => "n",
+    'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ő' => "o",
+    'ř' => "r",
+    'ś' | 'š' | 'ş' => "s",
+    'ť' | 'ţ' => "t",
+    'ù' | 'ú' | 'û' | 'ü' | 'ū' | 'ů' | 'ű' => "u",
+    'ý' | 'ÿ' => "y",
+    'ź' | 'ż' | 'ž' => "z",
+    'æ' => "ae",
+    'œ' => "oe",
+    'ß' => "ss",
+    '’' | '‘' | '´' | '`' => "'",
+    '“' | '”' | '«' | '»' => "\"",
+    '‐' | '‑' | '–' | '—' => "-",
+    _ => return None,
+  })
+}
+
+/// Forme de comparaison: minuscules, sans accents, apostrophes / tirets typographiques ramenés
+/// à l'ASCII, espaces (y compris insécables) regroupés. `"Complète"` et `"COMPLETE"` donnent `"complete"`.
+pub fn normalize(s: &str) -> String {
+  let mut out = String::with_capacity(s.len());
+  let mut pending_space = false;
+  for c in s.chars().flat_map(char::to_lowercase) {
+    if c.is_whitespace() {
+      pending_space = !out.is_empty();
+      continue;
+    }
+    if c == '\u{ad}' || c == '\u{200b}' || c == '\u{200c}' || c == '\u{200d}' {
+      continue;
+    }
+    if pending_space {
+      out.push(' ');
+      pending_space = false;
+    }
+    match fold_char(c) {
+      Some(base) => out.push_str(base),
+      None => out.push(c),
+    }
+  }
+  out
+}
+
+/// Présence de `word` en mot entier dans un texte déjà normalisé
+/// (`"ferme"` ne correspond pas à `"fermer"` ni à `"fermeture"`).
+pub fn contains_word(haystack: &str, word: &str) -> bool {
+  let is_word = |c: char| c.is_alphanumeric();
+  haystack.match_indices(word).any(|(i, _)| {
+    let before = haystack[..i].chars().next_back().is_none_or(|c| !is_word(c));
+    let after = haystack[i + word.len()..].chars().next().is_none_or(|c| !is_word(c));
+    before && after
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn entity_table_is_sorted() {
+    assert!(ENTITIES.windows(2).all(|w| w[0].0 < w[1].0));
+  }
+
+  #[test]
+  fn body_charset_from_header() {
+    // "Engagé" en ISO-8859-1
+    let latin1 = b"Engag\xe9";
+    assert_eq!(decode_body(latin1, Some("text/html; charset=ISO-8859-1")), "Engagé");
+    assert_eq!(decode_body(latin1, Some("text/html;charset=\"windows-1252\"")), "Engagé");
+    // L'en-tête prime sur le <meta>
+    let body = b"<meta charset=\"utf-8\">Engag\xe9";
+    assert_eq!(decode_body(body, Some("text/html; charset=latin1")), "<meta charset=\"utf-8\">Engagé");
+  }
+
+  #[test]
+  fn body_charset_from_meta() {
+    let html5 = b"<html><head><meta charset=iso-8859-1></head><body>Cl\xf4tur\xe9</body>";
+    assert!(decode_body(html5, Some("text/html")).ends_with("Clôturé</body>"));
+    let legacy = b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=ISO-8859-15\">\xa4";
+    assert!(decode_body(legacy, None).ends_with('€'));
+    // <meta> UTF-16 lu en ASCII: traité comme UTF-8
+    assert_eq!(decode_body("<meta charset=utf-16>é".as_bytes(), None), "<meta charset=utf-16>é");
+  }
+
+  #[test]
+  fn body_bom_and_fallback() {
+    let mut utf8 = vec![0xef, 0xbb, 0xbf];
+    utf8.extend_from_slice("Épreuve".as_bytes());
+    // Le BOM prime sur l'en-tête
+    assert_eq!(decode_body(&utf8, Some("text/html; charset=ISO-8859-1")), "Épreuve");
+
+    let utf16le = [0xff, 0xfe, b'O', 0, b'K', 0];
+    assert_eq!(decode_body(&utf16le, None), "OK");
+
+    assert_eq!(decode_body("déjà".as_bytes(), None), "déjà");
+    // Octets non UTF-8 sans déclaration: Windows-1252
+    assert_eq!(decode_body(b"l\x92\xe9preuve", None), "l’épreuve");
+  }
+
+  #[test]
+  fn entities_named() {
+    assert_eq!(decode_entities("Cl&ocirc;ture &amp; d&eacute;part"), "Clôture & départ");
+    assert_eq!(decode_entities("&laquo;&nbsp;Pro&nbsp;1&nbsp;&raquo;"), "«\u{a0}Pro\u{a0}1\u{a0}»");
+    // Au-delà de Latin-1: table HTML5 complète
+    assert_eq!(decode_entities("&rightarrow; &NotEqualTilde; &bsol; &Afr;"), "→ \u{2242}\u{338} \\ \u{1d504}");
+    assert_eq!(decode_entities("&CounterClockwiseContourIntegral;"), "∳");
+  }
+
+  #[test]
+  fn entities_numeric() {
+    assert_eq!(decode_entities("&#233;t&#233;"), "été");
+    assert_eq!(decode_entities("&#xE9;&#XE8;&#x20AC;"), "éè€");
+    // 0x80-0x9F lus en Windows-1252
+    assert_eq!(decode_entities("l&#146;&#233;preuve"), "l’épreuve");
+    assert_eq!(decode_entities("&#0;&#x110000;&#xD800;"), "\u{fffd}\u{fffd}\u{fffd}");
+  }
+
+  #[test]
+  fn entities_invalid_left_as_is() {
+    assert_eq!(decode_entities("A & B"), "A & B");
+    assert_eq!(decode_entities("&inconnue; &eacute"), "&inconnue; &eacute");
+    assert_eq!(decode_entities("&#zz; &#x; &;"), "&#zz; &#x; &;");
+    assert_eq!(decode_entities("R&D&eacute;"), "R&Dé");
+    assert_eq!(decode_entities("fin &"), "fin &");
+    // Un seul niveau de décodage
+    assert_eq!(decode_entities("&amp;lt;"), "&lt;");
+    assert_eq!(decode_entities("&amp;amp;"), "&amp;");
+  }
+
+  #[test]
+  fn normalize_folds_case_accents_and_spaces() {
+    assert_eq!(normalize("Complète"), "complete");
+    assert_eq!(normalize("COMPLÈTE"), "complete");
+    assert_eq!(normalize("  Clôture\u{a0}des\t engagements \n"), "cloture des engagements");
+    assert_eq!(normalize("L’Œuf – «Pro»"), "l'oeuf - \"pro\"");
+    assert_eq!(normalize("en\u{ad}ga\u{200b}gé"), "engage");
+  }
+
+  #[test]
+  fn contains_word_on_boundaries() {
+    let text = normalize("Engagements fermés : épreuve fermée, ferme-t-on ?");
+    assert!(contains_word(&text, "engagements"));
+    assert!(contains_word(&text, "ferme"));
+    assert!(!contains_word(&text, "engagement"));
+    assert!(contains_word("ferme", "ferme"));
+    assert!(!contains_word("fermer", "ferme"));
+    assert!(!contains_word("fermeture", "ferme"));
+    assert!(!contains_word("refermee", "ferme"));
+    assert!(contains_word("complet.", "complet"));
+    assert!(contains_word("(complet)", "complet"));
+  }
+}
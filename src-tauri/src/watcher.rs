@@ -4,7 +4,7 @@ use std::sync::{
 };
 use tokio::time::{sleep, Duration};
 use rusqlite::Connection;
-use crate::{db, detect::{detect_status_from_html, detect_slots_from_html, Status}};
+use crate::{accounts, conditional::{fingerprint, CachedPage}, contest_cache, db, engages, epreuves, ffe_url, http, keepalive, detect::{detect_status, detect_slots_from_html, has_logout_marker, is_login_page, Status}, models::{Alert, AlertKind, Target, WatchKind}};
 
 pub struct Watcher {
   running: Arc<AtomicBool>,
@@ -26,17 +26,10 @@ impl Watcher {
   pub async fn run_loop(
     &self,
     db_path: std::path::PathBuf,
-    notify: Arc<dyn Fn(i64, String, String) + Send + Sync>,
+    notify: Arc<dyn Fn(Alert) + Send + Sync>,
   ) {
     self.running.store(true, Ordering::SeqCst);
 
-    let client = match reqwest::Client::builder()
-      .user_agent("FFE Watcher (Tauri) - pre-engagement assisted")
-      .build()
-    {
-      Ok(c) => c,
-      Err(_) => return,
-    };
 
     while self.running.load(Ordering::SeqCst) {
       let conn = match Connection::open(&db_path) {
@@ -44,6 +37,7 @@ impl Watcher {
         Err(_) => { sleep(Duration::from_secs(2)).await; continue; }
       };
 
+      // Les cibles ENGAGED restent surveillées: un engagement retiré ou refusé doit être signalé
       let targets = match db::list_targets(&conn) {
         Ok(t) => t,
         Err(_) => { sleep(Duration::from_secs(2)).await; continue; }
@@ -58,30 +52,71 @@ impl Watcher {
       for t in targets {
         if !self.running.load(Ordering::SeqCst) { break; }
 
+        let account = accounts::normalize(t.account.as_deref());
+        // Client partagé du compte (cookie jar du compte + timeouts / proxy configurés)
+        let Ok(client) = http::client(Some(&account)) else { continue; };
+
         let interval = t.interval_normal_sec.max(15);
         let now = chrono::Utc::now().timestamp();
 
-        let mut status;
+        // Cible concours: comparaison du programme, pas de détection de statut
+        if t.kind == WatchKind::Contest {
+          check_contest(&db_path, &client, &t, now, &notify).await;
+          sleep(Duration::from_millis(250)).await;
+          sleep(Duration::from_secs(interval as u64)).await;
+          continue;
+        }
+
+        let mut status = Status::Unknown;
         let mut err: Option<String> = None;
+        // Épreuve surveillée (`?watch_epreuve=N`): seul son statut compte dans l'état JSON
+        let epreuve = ffe_url::normalize(&t.url).ok().and_then(|u| u.epreuve);
 
         // ✅ On garde le HTML pour extraire ensuite "52 / 60" → places restantes
         let mut html_opt: Option<String> = None;
 
-        let mut req = client.get(&t.url);
-        if let Some(c) = crate::ffe_cookie_header() {
-          req = req.header("Cookie", c);
-        }
+        // Requête conditionnelle: validateurs + empreinte du dernier contenu vu.
+        // Après une erreur / session expirée, on refait toujours une détection complète.
+        let cached = Connection::open(&db_path)
+          .ok()
+          .and_then(|c| db::get_cached_page(&c, t.id).ok().flatten())
+          .unwrap_or_default();
+        let can_skip = !matches!(t.last_status.as_str(), "ERROR" | "SESSION_EXPIRED");
+        let req = if can_skip { cached.apply(client.get(&t.url)) } else { client.get(&t.url) };
+
+        // Validateurs / empreinte de la réponse courante (None si pas de contenu exploitable)
+        let mut fresh: Option<CachedPage> = None;
+        let mut unchanged = false;
 
         match req.send().await {
+          Ok(resp) if resp.status() == reqwest::StatusCode::NOT_MODIFIED => {
+            unchanged = true;
+          }
           Ok(resp) => {
             if !resp.status().is_success() {
               status = Status::Error;
               err = Some(format!("HTTP {}", resp.status()));
             } else {
-              match resp.text().await {
+              let mut page = CachedPage::from_response(&resp);
+              // URL finale (après redirections) pour repérer un renvoi vers le SSO
+              let final_url = resp.url().to_string();
+              match http::read_text(resp).await {
+                Ok(html) if is_login_page(&final_url, &html) => {
+                  status = Status::SessionExpired;
+                  err = Some(format!("session FFE expirée (redirigé vers {final_url})"));
+                }
                 Ok(html) => {
-                  status = detect_status_from_html(&html);
-                  html_opt = Some(html);
+                  if has_logout_marker(&html) {
+                    keepalive::mark_authenticated(&account);
+                  }
+                  page.body_hash = Some(fingerprint(&html));
+                  if can_skip && cached.body_hash.is_some() && page.body_hash == cached.body_hash {
+                    unchanged = true;
+                  } else {
+                    status = detect_status(&html, epreuve);
+                    html_opt = Some(html);
+                  }
+                  fresh = Some(page);
                 }
                 Err(e) => { status = Status::Error; err = Some(format!("read body: {e}")); }
               }
@@ -90,35 +125,80 @@ impl Watcher {
           Err(e) => { status = Status::Error; err = Some(format!("http: {e}")); }
         }
 
-        // double-confirm if OPEN
-        if status == Status::Open {
-          sleep(Duration::from_secs(1)).await;
-          let mut req2 = client.get(&t.url);
-          if let Some(c) = crate::ffe_cookie_header() {
-            req2 = req2.header("Cookie", c);
+        // Contenu identique (304 ou même empreinte): ni détection ni event
+        if unchanged {
+          if let Ok(conn2) = Connection::open(&db_path) {
+            let _ = db::touch_checked(&conn2, t.id, now);
+            if let Some(page) = &fresh {
+              let _ = db::set_cached_page(&conn2, t.id, page, now);
+            }
           }
-          if let Ok(resp2) = req2.send().await {
-            if let Ok(html2) = resp2.text().await {
-              let s2 = detect_status_from_html(&html2);
-              if s2 != Status::Open { status = s2; }
-              // on remplace par le HTML le plus récent
+          sleep(Duration::from_millis(250)).await;
+          sleep(Duration::from_secs(interval as u64)).await;
+          continue;
+        }
+
+        // double-confirm si OPEN ou si le contenu a changé depuis le dernier passage
+        let content_changed = cached.body_hash.is_some()
+          && fresh.as_ref().is_some_and(|p| p.body_hash != cached.body_hash);
+        let mut inconclusive = false;
+        if html_opt.is_some() && (status == Status::Open || content_changed) {
+          sleep(Duration::from_secs(1)).await;
+          let second = match client.get(&t.url).send().await {
+            Ok(resp2) => {
+              let page2 = CachedPage::from_response(&resp2);
+              let final_url2 = resp2.url().to_string();
+              http::read_text(resp2).await.ok().map(|html2| (final_url2, page2, html2))
+            }
+            Err(_) => None,
+          };
+          match second {
+            Some((final_url2, _, html2)) if is_login_page(&final_url2, &html2) => {
+              status = Status::SessionExpired;
+              fresh = None;
+              html_opt = None;
+            }
+            // Les deux lectures concordent: on garde la plus récente (HTML, empreinte)
+            Some((_, mut page2, html2)) if detect_status(&html2, epreuve) == status => {
+              page2.body_hash = Some(fingerprint(&html2));
+              fresh = Some(page2);
               html_opt = Some(html2);
             }
+            // Lectures divergentes (ou seconde lecture impossible): statut précédent conservé,
+            // empreinte non enregistrée pour refaire une détection complète au prochain passage
+            _ => {
+              inconclusive = true;
+              fresh = None;
+              html_opt = None;
+            }
           }
         }
 
         let status_str = match status {
-          Status::Unknown => "UNKNOWN",
-          Status::Closed => "CLOSED",
-          Status::Open => "OPEN",
-          Status::Full => "FULL",
-          Status::Error => "ERROR",
-        }.to_string();
+          _ if inconclusive => t.last_status.clone(),
+          Status::Unknown => "UNKNOWN".to_string(),
+          Status::Closed => "CLOSED".to_string(),
+          Status::Open => "OPEN".to_string(),
+          Status::Full => "FULL".to_string(),
+          Status::Error => "ERROR".to_string(),
+          Status::SessionExpired => "SESSION_EXPIRED".to_string(),
+        };
 
         let old = t.last_status.clone();
 
+        // Engagements ouverts (ou clos après notre engagement): notre couple est-il dans la liste ?
+        let check = matches!(status, Status::Open | Status::Full) || (status == Status::Closed && old == "ENGAGED");
+        let engaged = if t.check_engaged && !inconclusive && check { Some(is_engaged(&client, &t).await) } else { None };
+        if let Some(Err(e)) = &engaged {
+          err.get_or_insert_with(|| format!("liste des engagés: {e}"));
+        }
+        let (status_str, lost) = engaged_outcome(&old, status_str, engaged);
+
         if let Ok(conn2) = Connection::open(&db_path) {
           let _ = db::update_status(&conn2, t.id, &status_str, now, err.as_deref());
+          if let Some(page) = &fresh {
+            let _ = db::set_cached_page(&conn2, t.id, page, now);
+          }
 
           // ✅ Alerte "place libérée" : FULL (0) → >0
           if let Some(html) = html_opt.as_deref() {
@@ -126,7 +206,7 @@ impl Watcher {
               let last_slots = t.last_slots.unwrap_or(-1);
 
               if last_slots == 0 && slots_now > 0 {
-                notify(t.id, t.label.clone(), t.url.clone());
+                notify(alert_for(&t, AlertKind::SlotFreed));
               }
 
               let _ = db::set_last_slots(&conn2, t.id, slots_now);
@@ -134,9 +214,20 @@ impl Watcher {
           }
         }
 
+        // 🔒 Session expirée: une seule alerte à la transition
+        if old != "SESSION_EXPIRED" && status_str == "SESSION_EXPIRED" {
+          notify(alert_for(&t, AlertKind::SessionExpired));
+        }
+
+        // Engagement disparu de la liste, quel que soit le statut de l'épreuve
+        if lost {
+          let detail = format!("Le couple n'apparaît plus dans la liste des engagés (épreuve {status_str}).");
+          notify(Alert { detail: Some(detail), ..alert_for(&t, AlertKind::EngagementLost) });
+        }
+
         // 🚨 Alerte "ouverture engagements"
-        if old != "OPEN" && status_str == "OPEN" {
-          notify(t.id, t.label.clone(), t.url.clone());
+        if old != "OPEN" && status_str == "OPEN" && !lost {
+          notify(alert_for(&t, AlertKind::Opened));
         }
 
         sleep(Duration::from_millis(250)).await;
@@ -145,3 +236,110 @@ impl Watcher {
     }
   }
 }
+
+/// Relit les épreuves du concours et les compare à la dernière liste vue par la cible.
+/// Le premier contrôle enregistre la liste sans alerter.
+async fn check_contest(
+  db_path: &std::path::Path,
+  client: &reqwest::Client,
+  t: &Target,
+  now: i64,
+  notify: &Arc<dyn Fn(Alert) + Send + Sync>,
+) {
+  let fetched = match ffe_url::normalize(&t.url) {
+    Ok(u) => contest_cache::refresh(db_path, client, &u.contest_id).await.map(|(list, _)| list),
+    Err(e) => Err(e),
+  };
+  let Ok(conn) = Connection::open(db_path) else { return };
+  let list = match fetched {
+    Ok(list) => list,
+    Err(e) => {
+      let _ = db::update_status(&conn, t.id, "ERROR", now, Some(&e));
+      return;
+    }
+  };
+
+  let changes = match db::get_contest_snapshot(&conn, t.id) {
+    Ok(Some(previous)) => epreuves::diff(&previous, &list),
+    _ => Vec::new(),
+  };
+  let _ = db::set_contest_snapshot(&conn, t.id, &list, now);
+
+  if changes.is_empty() {
+    let _ = db::record_contest_check(&conn, t.id, "UNCHANGED", now, None);
+    return;
+  }
+  let detail = changes.iter().map(|c| c.describe()).collect::<Vec<_>>().join("\n");
+  let _ = db::record_contest_check(&conn, t.id, "CHANGED", now, Some(&detail));
+  notify(Alert { detail: Some(detail), ..alert_for(t, AlertKind::ContestChanged) });
+}
+
+/// Le couple de la cible figure parmi les engagés de l'épreuve (voir `Engage::is`: cheval ou
+/// licence requis en plus du nom). Err si la liste n'a pas pu être lue.
+async fn is_engaged(client: &reqwest::Client, t: &Target) -> Result<bool, String> {
+  let cavalier = t.cavalier.as_deref().filter(|c| !c.trim().is_empty()).ok_or("cible sans cavalier")?;
+  let u = ffe_url::normalize(&t.url)?;
+  let num = u.epreuve.ok_or("cible sans n° d'épreuve")?;
+  let list = engages::fetch(client, &u.contest_id, num).await?;
+  Ok(list.iter().any(|e| e.is(cavalier, t.cheval.as_deref())))
+}
+
+/// Statut enregistré après le contrôle des engagés (`None`: pas de contrôle à ce passage), et
+/// `true` si notre engagement a disparu de la liste. Un contrôle en échec (réseau, HTTP, liste
+/// introuvable) ne fait pas sortir une cible de l'état ENGAGED.
+fn engaged_outcome(old: &str, status_str: String, engaged: Option<Result<bool, String>>) -> (String, bool) {
+  match engaged {
+    Some(Ok(true)) => ("ENGAGED".to_string(), false),
+    Some(Ok(false)) => (status_str, old == "ENGAGED"),
+    Some(Err(_)) if old == "ENGAGED" => (old.to_string(), false),
+    _ => (status_str, false),
+  }
+}
+
+fn alert_for(t: &Target, kind: AlertKind) -> Alert {
+  Alert {
+    target_id: t.id,
+    kind,
+    label: t.label.clone(),
+    url: t.url.clone(),
+    cavalier: t.cavalier.clone(),
+    cheval: t.cheval.clone(),
+    account: t.account.clone(),
+    detail: None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn engaged_found() {
+    assert_eq!(engaged_outcome("OPEN", "OPEN".into(), Some(Ok(true))), ("ENGAGED".into(), false));
+    assert_eq!(engaged_outcome("ENGAGED", "CLOSED".into(), Some(Ok(true))), ("ENGAGED".into(), false));
+  }
+
+  #[test]
+  fn engaged_check_failure_keeps_engaged() {
+    let failed = || Some(Err("HTTP 503".to_string()));
+    assert_eq!(engaged_outcome("ENGAGED", "OPEN".into(), failed()), ("ENGAGED".into(), false));
+    assert_eq!(engaged_outcome("ENGAGED", "FULL".into(), failed()), ("ENGAGED".into(), false));
+    // Pas encore engagé: le statut de la page s'applique
+    assert_eq!(engaged_outcome("CLOSED", "OPEN".into(), failed()), ("OPEN".into(), false));
+  }
+
+  #[test]
+  fn engagement_lost_whatever_the_status() {
+    for status in ["OPEN", "FULL", "CLOSED"] {
+      assert_eq!(engaged_outcome("ENGAGED", status.into(), Some(Ok(false))), (status.into(), true), "{status}");
+    }
+    // Jamais engagé: rien n'a disparu
+    assert_eq!(engaged_outcome("OPEN", "FULL".into(), Some(Ok(false))), ("FULL".into(), false));
+  }
+
+  #[test]
+  fn no_check_keeps_page_status() {
+    assert_eq!(engaged_outcome("ENGAGED", "ERROR".into(), None), ("ERROR".into(), false));
+    assert_eq!(engaged_outcome("CLOSED", "OPEN".into(), None), ("OPEN".into(), false));
+  }
+}
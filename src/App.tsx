@@ -1,10 +1,22 @@
 import React, { useEffect, useMemo, useRef, useState } from "react";
 import "./styles.css";
-import { addTarget, deleteTarget, listTargets, startWatcher, stopWatcher, type Target } from "./api";
+import { addTarget, deleteTarget, DISCIPLINES, epreuveTitle, getHttpSettings, listTargets, setHttpSettings, startWatcher, stopWatcher, watchContest, type ContestSearchResult, type Epreuve, type EpreuveFilter, type HttpSettings, type LoginEvent, type LoginState, type Target } from "./api";
 import invoke from "./lib/invoke";
 
 const isTauriRuntime = () => typeof (window as any).__TAURI_INTERNALS__ !== "undefined";
 
+const LOGIN_STEP_LABELS: Record<LoginState, string> = {
+  Loading: "chargement de la page FFE…",
+  CookieBanner: "bandeau cookies…",
+  FormFilled: "formulaire rempli…",
+  Submitted: "identifiants envoyés…",
+  Redirecting: "redirection SSO…",
+  CaptchaRequired: "captcha à résoudre dans la fenêtre FFE.",
+  TwoFactorRequired: "code de vérification à saisir dans la fenêtre FFE.",
+  Connected: "connecté.",
+  Failed: "échec.",
+};
+
 export default function App() {
   const [targets, setTargets] = useState<Target[]>([]);
   // Anti-spam / anti-race pour éviter plusieurs fenêtres d'alerte simultanées
@@ -22,24 +34,70 @@ export default function App() {
   const [ffePass, setFfePass] = useState("");
   const [ffeLoginError, setFfeLoginError] = useState<string | null>(null);
   const [ffeLoginLoading, setFfeLoginLoading] = useState(false);
+  const [ffeLoginStep, setFfeLoginStep] = useState<LoginEvent | null>(null);
+
+  // Réglages réseau (client HTTP partagé)
+  const [httpOpen, setHttpOpen] = useState(false);
+  const [httpSettings, setHttpSettingsState] = useState<HttpSettings | null>(null);
+  const [httpMsg, setHttpMsg] = useState<string | null>(null);
 
 
   // ✅ États indépendants par bloc
   // --- Alerte ouverture concours
   const [contestIdConcours, setContestIdConcours] = useState("");
   const [concoursError, setConcoursError] = useState<string | null>(null);
+  // Surveillance du programme (épreuves ajoutées / supprimées / modifiées) plutôt que de l'ouverture
+  const [watchProgramme, setWatchProgramme] = useState(false);
 
   // --- Alerte place épreuve
   const [contestIdEpreuve, setContestIdEpreuve] = useState("");
+  // Filtre discipline ("" = toutes)
+  const [disciplineEpreuve, setDisciplineEpreuve] = useState("SO");
+  // Filtre hauteur (cm, vide = sans borne) + tri
+  const [minHeightEpreuve, setMinHeightEpreuve] = useState("");
+  const [maxHeightEpreuve, setMaxHeightEpreuve] = useState("");
+  const [sortEpreuve, setSortEpreuve] = useState<"num" | "height" | "level">("num");
 
 
-  type Epreuve = { label: string; url: string };
 
   const [epreuves, setEpreuves] = useState<Epreuve[]>([]);
   const [selected, setSelected] = useState<Record<string, boolean>>({});
   const [loadingEpreuves, setLoadingEpreuves] = useState(false);
   const [epreuvesError, setEpreuvesError] = useState<string | null>(null);
   const [epreuvesOpen, setEpreuvesOpen] = useState(true);
+  // Dernier concours relu en tâche de fond avec des épreuves modifiées (event "contest:updated")
+  const [contestUpdated, setContestUpdated] = useState<string | null>(null);
+
+  // Recherche de concours (ffecompet)
+  const [searchFrom, setSearchFrom] = useState("");
+  const [searchTo, setSearchTo] = useState("");
+  const [searchDept, setSearchDept] = useState("");
+  const [searchResult, setSearchResult] = useState<ContestSearchResult | null>(null);
+  const [searchLoading, setSearchLoading] = useState(false);
+  const [searchError, setSearchError] = useState<string | null>(null);
+
+  async function toggleHttpSettings() {
+    const open = !httpOpen;
+    setHttpOpen(open);
+    setHttpMsg(null);
+    if (open && !httpSettings) {
+      try {
+        setHttpSettingsState(await getHttpSettings());
+      } catch (e) {
+        setHttpMsg(String(e));
+      }
+    }
+  }
+
+  async function saveHttpSettings() {
+    if (!httpSettings) return;
+    try {
+      await setHttpSettings({ ...httpSettings, proxy_url: httpSettings.proxy_url?.trim() || null });
+      setHttpMsg("Réglages enregistrés.");
+    } catch (e) {
+      setHttpMsg("Erreur: " + String(e));
+    }
+  }
 
   async function checkFFEConnection() {
     try {
@@ -87,7 +145,7 @@ export default function App() {
   setFfeLoginError(null);
   setFfeLoginLoading(true);
   try {
-    await invoke("ffe_logout");          // <-- commande backend à créer
+    await invoke("ffe_logout");
     setFfeServerOk(false);              // UI: repasse en "non connecté"
   } catch (e) {
     setFfeLoginError("Déconnexion impossible: " + String(e));
@@ -118,6 +176,34 @@ useEffect(() => {
   checkFFEConnection();
 }, []);
 
+useEffect(() => {
+  // Étapes de la connexion (ffe_login_with_credentials)
+  let unlisten: undefined | (() => void);
+
+  if (isTauriRuntime()) {
+    (async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      const offLogin = await listen<LoginEvent>("ffe:login", (e) => {
+        setFfeLoginStep(e.payload);
+        if (e.payload.state === "Connected") {
+          setFfeServerOk(true);
+          setFfeLoginError(null);
+        }
+      });
+      // Keep-alive: expiration probable des cookies d'authentification
+      const offExpiring = await listen<{ account: string; expiresAt: number }>("ffe:session_expiring", (e) => {
+        const at = new Date(e.payload.expiresAt * 1000).toLocaleTimeString();
+        setFfeLoginError(`Session FFE (${e.payload.account}) bientôt expirée (vers ${at}) : reconnecte-toi si besoin.`);
+      });
+      unlisten = () => { offLogin(); offExpiring(); };
+    })().catch(() => {});
+  }
+
+  return () => {
+    if (unlisten) unlisten();
+  };
+}, []);
+
 useEffect(() => {
   // Le backend émet "ffe:status" après save_ffe_session()
   // En mode Web (iPhone), il n'y a pas d'events Tauri -> on ignore.
@@ -126,7 +212,15 @@ useEffect(() => {
   if (isTauriRuntime()) {
     (async () => {
       const { listen } = await import("@tauri-apps/api/event");
-      unlisten = await listen<{ connected?: boolean; cookieCount?: number }>("ffe:status", async () => {
+      unlisten = await listen<{ connected?: boolean; cookieCount?: number; loggedOut?: boolean }>("ffe:status", async (e) => {
+      // Déconnexion (ffe_logout): rien à revalider
+      if (e.payload?.loggedOut) {
+        setFfeServerOk(false);
+        setFfeLoginError(null);
+        setFfeLoginLoading(false);
+        return;
+      }
+
       const wait = (ms: number) => new Promise((res) => setTimeout(res, ms));
 
       let ok = false;
@@ -148,6 +242,28 @@ useEffect(() => {
   };
 }, []);
 
+useEffect(() => {
+  // Cache concours: liste relue en tâche de fond et différente de celle affichée
+  let unlisten: undefined | (() => void);
+
+  if (isTauriRuntime()) {
+    (async () => {
+      const { listen } = await import("@tauri-apps/api/event");
+      unlisten = await listen<{ contestId: string }>("contest:updated", (e) => setContestUpdated(e.payload.contestId));
+    })().catch(() => {});
+  }
+
+  return () => {
+    if (unlisten) unlisten();
+  };
+}, []);
+
+useEffect(() => {
+  // Le cache est à jour: on recharge (filtres compris) sans nouvelle requête FFE
+  if (contestUpdated && contestUpdated === contestIdEpreuve.trim() && epreuves.length > 0) loadEpreuves();
+  setContestUpdated(null);
+}, [contestUpdated]);
+
 useEffect(() => {
     const it = setInterval(() => { refresh(); }, 2000);
     return () => clearInterval(it);
@@ -174,7 +290,13 @@ useEffect(() => {
     setLoadingEpreuves(true);
     setEpreuvesError(null);
     try {
-      const eps = await invoke<{ label: string; url: string }[]>("list_epreuves", { contestId: id });
+      const disciplines = disciplineEpreuve ? [disciplineEpreuve] : [];
+      const filter: EpreuveFilter = {
+        min_height_cm: minHeightEpreuve ? Number(minHeightEpreuve) : null,
+        max_height_cm: maxHeightEpreuve ? Number(maxHeightEpreuve) : null,
+        sort: sortEpreuve
+      };
+      const eps = await invoke<Epreuve[]>("list_epreuves", { contestId: id, disciplines, filter });
       setEpreuves(eps);
       if (eps.length > 0) setEpreuvesOpen(true);
 
@@ -195,19 +317,44 @@ useEffect(() => {
     }
   }
 
+  async function searchContests() {
+    setSearchLoading(true);
+    setSearchError(null);
+    try {
+      const search = {
+        date_from: searchFrom || null,
+        date_to: searchTo || null,
+        departement: searchDept.trim() || null,
+        discipline: disciplineEpreuve || null
+      };
+      const res = await invoke<ContestSearchResult>("search_contests", { search });
+      setSearchResult(res);
+      if (res.contests.length === 0) setSearchError("Aucun concours trouvé.");
+    } catch (e) {
+      console.error("search_contests error", e);
+      setSearchResult(null);
+      setSearchError(String(e));
+    } finally {
+      setSearchLoading(false);
+    }
+  }
+
   async function addSelectedEpreuves() {
     const id = contestIdEpreuve.trim();
     const picked = epreuves.filter(e => selected[e.url]);
 
-    for (const e of picked) {
-      await addTarget({
-        label: `${id} — ${e.label}`,
-        url: e.url,
-        cheval: null,
-        cavalier: null,
+    if (picked.length === 0) return;
+
+    try {
+      const res = await watchContest({
+        contest_id: id,
+        epreuves: picked.map(e => e.num),
         interval_normal_sec: intervalEpreuveNormal,
         interval_hot_sec: intervalEpreuveHot
       });
+      setEpreuvesError(res.skipped.length > 0 ? `Déjà surveillées : épreuves ${res.skipped.join(", ")}.` : null);
+    } catch (e) {
+      setEpreuvesError(String(e));
     }
     await refresh();
   }
@@ -293,7 +440,13 @@ useEffect(() => {
   if (isTauriRuntime()) {
     (async () => {
       const { listen } = await import("@tauri-apps/api/event");
-      unlisten = await listen<{ id: number; label: string; url: string }>("target_open", (e) => {
+      unlisten = await listen<{ id: number; label: string; url: string; kind?: string }>("target_open", (e) => {
+    // Session expirée: pas de fenêtre d'alarme, on repasse l'UI en "non connecté"
+    if (e.payload?.kind === "SessionExpired") {
+      setFfeServerOk(false);
+      setFfeLoginError("Session FFE expirée : reconnecte-toi pour reprendre la surveillance.");
+      return;
+    }
     const contest = (e.payload?.label || "").trim();
     if (!contest) return;
     const targetUrl = (e.payload?.url || "").trim();
@@ -330,16 +483,22 @@ useEffect(() => {
       return;
     }
 
-    await addTarget({
-      // label = n° concours
-      label: id,
-      // l\'URL est construite en interne (pas affichée)
-      url: buildContestUrl(id),
-      cheval: null,
-      cavalier: null,
-      interval_normal_sec: intervalConcoursNormal,
-      interval_hot_sec: intervalConcoursHot
-    });
+    try {
+      await addTarget({
+        // label = n° concours
+        label: watchProgramme ? `${id} — programme` : id,
+        // l\'URL est construite en interne (pas affichée)
+        url: buildContestUrl(id),
+        cheval: null,
+        cavalier: null,
+        interval_normal_sec: intervalConcoursNormal,
+        interval_hot_sec: intervalConcoursHot,
+        kind: watchProgramme ? "contest" : "status"
+      });
+    } catch (e) {
+      setConcoursError(String(e));
+      return;
+    }
 
     setContestIdConcours("");
     await refresh();
@@ -403,7 +562,12 @@ useEffect(() => {
 
 
         {ffeLoginLoading ? (
-          <div className="small" style={{ marginTop: 8, opacity: 0.9 }}>Connexion en cours… merci de patienter.</div>
+          <div className="small" style={{ marginTop: 8, opacity: 0.9 }}>
+            Connexion en cours… {ffeLoginStep ? LOGIN_STEP_LABELS[ffeLoginStep.state] : "merci de patienter."}
+          </div>
+        ) : null}
+        {!ffeLoginLoading && ffeLoginStep && (ffeLoginStep.state === "CaptchaRequired" || ffeLoginStep.state === "TwoFactorRequired") ? (
+          <div className="small" style={{ marginTop: 8 }}>{LOGIN_STEP_LABELS[ffeLoginStep.state]}</div>
         ) : null}
         {ffeLoginError ? <div className="small" style={{ marginTop: 8 }}>Erreur: {ffeLoginError}</div> : null}
       </div>
@@ -435,6 +599,11 @@ useEffect(() => {
               </div>
             </div>
 
+            <label className="small row" style={{ gap: 6, alignItems: "center" }}>
+              <input type="checkbox" checked={watchProgramme} onChange={e => setWatchProgramme(e.target.checked)}/>
+              Surveiller le programme (épreuves ajoutées, supprimées ou modifiées)
+            </label>
+
             <button className="row" style={{ gap: 10, flexWrap: "wrap" }} onClick={onAddConcours}>Charger concours</button>
             {concoursError ? <div className="small" style={{ marginTop: 8 }}>Erreur: {concoursError}</div> : null}
 
@@ -465,12 +634,74 @@ useEffect(() => {
         <div className="card">
           <div style={{ fontWeight: 700, marginBottom: 8 }}>ALERTE PLACE EPREUVE</div>
 
+          <div className="row" style={{ gap: 6, flexWrap: "wrap", alignItems: "flex-end", marginBottom: 10 }}>
+            <div>
+              <div className="small">Du / au</div>
+              <div className="row" style={{ gap: 6 }}>
+                <input type="date" value={searchFrom} onChange={e => setSearchFrom(e.target.value)} className="input-s"/>
+                <input type="date" value={searchTo} onChange={e => setSearchTo(e.target.value)} className="input-s"/>
+              </div>
+            </div>
+            <div>
+              <div className="small">Département</div>
+              <input value={searchDept} onChange={e => setSearchDept(e.target.value)} placeholder="41" className="input-xs"/>
+            </div>
+            <button className="secondary" onClick={searchContests} disabled={searchLoading}>
+              {searchLoading ? "Recherche…" : "Rechercher concours"}
+            </button>
+          </div>
+
+          {searchError ? <div className="small" style={{ marginBottom: 8 }}>{searchError}</div> : null}
+          {searchResult && searchResult.contests.length > 0 ? (
+            <div className="card" style={{ padding: 10, marginBottom: 10, maxHeight: 220, overflowY: "auto" }}>
+              {searchResult.contests.map(c => (
+                <div key={c.id} className="row" style={{ justifyContent: "space-between", gap: 8, marginBottom: 4 }}>
+                  <div className="small">
+                    {c.start_date ?? "?"} — {c.name}
+                    {c.city ? ` — ${c.city}` : ""}{c.departement ? ` (${c.departement})` : ""}
+                    {c.disciplines.length > 0 ? ` — ${c.disciplines.join(", ")}` : ""}
+                  </div>
+                  <button className="secondary" style={{ padding: "4px 8px" }} onClick={() => setContestIdEpreuve(c.id)}>
+                    {c.id}
+                  </button>
+                </div>
+              ))}
+              {searchResult.has_more ? <div className="small" style={{ opacity: 0.8 }}>D'autres concours existent : affine les filtres.</div> : null}
+            </div>
+          ) : null}
+
           <div className="grid" style={{ marginBottom: 10 }}>
             <div>
               <div className="small">N° concours (9 chiffres)</div>
               <input value={contestIdEpreuve} onChange={e => setContestIdEpreuve(e.target.value)} placeholder="202635080"  className="input-s"/>
             </div>
 
+            <div>
+              <div className="small">Discipline</div>
+              <select value={disciplineEpreuve} onChange={e => setDisciplineEpreuve(e.target.value)} className="input-s">
+                <option value="">Toutes</option>
+                {DISCIPLINES.map(d => <option key={d.code} value={d.code}>{d.name} ({d.code})</option>)}
+              </select>
+            </div>
+
+            <div className="grid grid-2">
+              <div>
+                <div className="small">Hauteur min / max (cm)</div>
+                <div className="row" style={{ gap: 6 }}>
+                  <input type="number" value={minHeightEpreuve} onChange={e => setMinHeightEpreuve(e.target.value)} placeholder="100" className="input-xs"/>
+                  <input type="number" value={maxHeightEpreuve} onChange={e => setMaxHeightEpreuve(e.target.value)} placeholder="120" className="input-xs"/>
+                </div>
+              </div>
+              <div>
+                <div className="small">Tri</div>
+                <select value={sortEpreuve} onChange={e => setSortEpreuve(e.target.value as "num" | "height" | "level")} className="input-s">
+                  <option value="num">N° d'épreuve</option>
+                  <option value="height">Hauteur</option>
+                  <option value="level">Niveau</option>
+                </select>
+              </div>
+            </div>
+
             <div className="grid grid-2">
               <div>
                 <div className="small">Intervalle normal (sec)</div>
@@ -543,7 +774,7 @@ useEffect(() => {
                           style={{ margin: 0, flex: "0 0 auto" }}
                         />
                         <span style={{ fontWeight: 600, lineHeight: 1.2, flex: "1 1 auto" }}>
-                          {e.label}
+                          {epreuveTitle(e)}
                         </span>
                       </div>
                     );
@@ -597,6 +828,52 @@ useEffect(() => {
           {targets.length === 0 ? <div className="small">Aucune alerte. Ajoute un N° concours.</div> : null}
         </div>
       </div>
+
+      <div className="card" style={{ marginTop: 12 }}>
+        <div className="row" style={{ justifyContent: "space-between" }}>
+          <div style={{ fontWeight: 700 }}>Réglages réseau</div>
+          <button onClick={toggleHttpSettings}>{httpOpen ? "Masquer" : "Afficher"}</button>
+        </div>
+
+        {httpOpen && httpSettings ? (
+          <div style={{ marginTop: 10 }}>
+            <div className="grid grid-2">
+              <label className="small">Délai de connexion (s)
+                <input className="input-s" type="number" min={1} value={httpSettings.connect_timeout_sec}
+                  onChange={e => setHttpSettingsState({ ...httpSettings, connect_timeout_sec: Number(e.target.value) })} />
+              </label>
+              <label className="small">Délai de lecture (s)
+                <input className="input-s" type="number" min={1} value={httpSettings.read_timeout_sec}
+                  onChange={e => setHttpSettingsState({ ...httpSettings, read_timeout_sec: Number(e.target.value) })} />
+              </label>
+              <label className="small">Délai total par requête (s)
+                <input className="input-s" type="number" min={1} value={httpSettings.total_timeout_sec}
+                  onChange={e => setHttpSettingsState({ ...httpSettings, total_timeout_sec: Number(e.target.value) })} />
+              </label>
+              <label className="small">Redirections max (0 = aucune)
+                <input className="input-s" type="number" min={0} value={httpSettings.max_redirects}
+                  onChange={e => setHttpSettingsState({ ...httpSettings, max_redirects: Number(e.target.value) })} />
+              </label>
+              <label className="small">Taille max de réponse (Ko)
+                <input className="input-s" type="number" min={64} value={Math.round(httpSettings.max_body_bytes / 1024)}
+                  onChange={e => setHttpSettingsState({ ...httpSettings, max_body_bytes: Number(e.target.value) * 1024 })} />
+              </label>
+              <label className="small">Proxy (http://, https://, socks5://)
+                <input className="input-s" value={httpSettings.proxy_url ?? ""} placeholder="aucun"
+                  onChange={e => setHttpSettingsState({ ...httpSettings, proxy_url: e.target.value })} />
+              </label>
+            </div>
+            <label className="small" style={{ display: "block", marginTop: 8 }}>User agent
+              <input className="input-s" value={httpSettings.user_agent}
+                onChange={e => setHttpSettingsState({ ...httpSettings, user_agent: e.target.value })} />
+            </label>
+            <div className="row" style={{ marginTop: 10, gap: 8 }}>
+              <button className="primary" onClick={saveHttpSettings}>Enregistrer</button>
+            </div>
+          </div>
+        ) : null}
+        {httpMsg ? <div className="small" style={{ marginTop: 8 }}>{httpMsg}</div> : null}
+      </div>
     </div>
   );
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
 import { invoke } from "@tauri-apps/api/core";
 
+/** "status" = statut d'engagement de la page, "contest" = programme du concours */
+export type WatchKind = "status" | "contest";
+
 export type Target = {
   id: number;
   label: string;
@@ -13,12 +16,171 @@ export type Target = {
   last_status: string;
   last_checked_at?: number | null;
   last_error?: string | null;
+  account?: string | null;
+  kind: WatchKind;
+  /** Passe en ENGAGED quand le couple cavalier / cheval (ou la licence du cavalier) apparaît dans la liste des engagés */
+  check_engaged: boolean;
+};
+
+export type Series = "club" | "poney" | "amateur" | "pro";
+export type Level = "elite" | "1" | "2" | "3" | "4";
+export type Table =
+  | "a" | "c" | "deux_phases" | "vitesse" | "preparatoire"
+  | "speciale" | "grand_prix" | "derby" | "puissance" | "six_barres";
+
+/** Libellé décomposé: "Amateur 2 Grand Prix (1,15 m)" → amateur / 2 / grand_prix / 115 */
+export type EpreuveLabel = {
+  series?: Series | null;
+  level?: Level | null;
+  table?: Table | null;
+  height_cm?: number | null;
+};
+
+export type EpreuveFilter = {
+  min_height_cm?: number | null;
+  max_height_cm?: number | null;
+  series?: Series[];
+  levels?: Level[];
+  tables?: Table[];
+  sort?: "num" | "height" | "level";
+};
+
+export type Epreuve = {
+  num: number;
+  discipline?: string | null;
+  label: string;
+  height_cm?: number | null;
+  category?: string | null;
+  /** Reprise de dressage, format de complet… */
+  level?: string | null;
+  engaged?: number | null;
+  max?: number | null;
+  url: string;
+  parsed: EpreuveLabel;
+};
+
+/** Codes FFE: CSO, dressage, complet, hunter, pony-games */
+export const DISCIPLINES: { code: string; name: string }[] = [
+  { code: "SO", name: "CSO" },
+  { code: "DR", name: "Dressage" },
+  { code: "CC", name: "Complet" },
+  { code: "HU", name: "Hunter" },
+  { code: "PG", name: "Pony-games" },
+];
+
+/** "Epreuve 2 SO — Amateur 2 Grand Prix (1,15 m) — 60/60" */
+export function epreuveTitle(e: Epreuve): string {
+  const parts = [e.discipline ? `Epreuve ${e.num} ${e.discipline}` : `Epreuve ${e.num}`, e.label];
+  if (e.engaged != null && e.max != null) parts.push(`${e.engaged}/${e.max}`);
+  return parts.join(" — ");
+}
+
+export async function listEpreuves(
+  contestId: string,
+  account?: string | null,
+  disciplines?: string[],
+  filter?: EpreuveFilter,
+  refresh = false
+): Promise<Epreuve[]> {
+  return await invoke("list_epreuves", { contestId, account, disciplines, filter, refresh });
+}
+
+export type ContestSearch = {
+  /** "YYYY-MM-DD" */
+  date_from?: string | null;
+  date_to?: string | null;
+  departement?: string | null;
+  region?: string | null;
+  /** Code ("SO") ou nom ("Dressage") */
+  discipline?: string | null;
+  level?: string | null;
+  organizer?: string | null;
+  max_pages?: number | null;
+};
+
+export type ContestSummary = {
+  id: string;
+  name: string;
+  start_date?: string | null;
+  end_date?: string | null;
+  city?: string | null;
+  departement?: string | null;
+  disciplines: string[];
+  organizer?: string | null;
+  url: string;
 };
 
-export type Epreuve = { label: string; url: string };
+export type ContestSearchResult = { contests: ContestSummary[]; pages: number; has_more: boolean };
+
+export async function searchContests(search: ContestSearch, account?: string | null): Promise<ContestSearchResult> {
+  return await invoke("search_contests", { search, account });
+}
+
+export type ContestFee = { label: string; amount_eur: number };
+export type ContestContact = { name?: string | null; email?: string | null; phone?: string | null };
+
+export type ContestDetail = {
+  id: string;
+  name: string;
+  venue?: string | null;
+  city?: string | null;
+  departement?: string | null;
+  start_date?: string | null;
+  end_date?: string | null;
+  organizer?: string | null;
+  fees: ContestFee[];
+  /** "YYYY-MM-DD" ou "YYYY-MM-DDTHH:MM" */
+  opening_date?: string | null;
+  closing_date?: string | null;
+  contact: ContestContact;
+  url: string;
+  fetched_at: number;
+};
+
+export async function getContestDetail(contestId: string, account?: string | null, refresh = false): Promise<ContestDetail> {
+  return await invoke("get_contest_detail", { contestId, account, refresh });
+}
+
+export async function listContestDetails(): Promise<ContestDetail[]> {
+  return await invoke("list_contest_details");
+}
 
-export async function listEpreuves(contestId: string): Promise<Epreuve[]> {
-  return await invoke("list_epreuves", { contestId });
+export async function deleteContestDetail(contestId: string): Promise<void> {
+  await invoke("delete_contest_detail", { contestId });
+}
+
+export type WatchContestPayload = {
+  contest_id: string;
+  /** Numéros d'épreuve; vide => sélection par disciplines + filtre */
+  epreuves?: number[];
+  disciplines?: string[];
+  filter?: EpreuveFilter | null;
+  cheval?: string | null;
+  cavalier?: string | null;
+  interval_normal_sec?: number;
+  interval_hot_sec?: number;
+  hot_from?: string | null;
+  hot_to?: string | null;
+  account?: string | null;
+  check_engaged?: boolean;
+};
+
+export type WatchContestResult = { created: number[]; skipped: number[]; missing: number[] };
+
+export async function watchContest(payload: WatchContestPayload): Promise<WatchContestResult> {
+  return await invoke("watch_contest", { payload });
+}
+
+export type TargetEvent = { ts: number; status: string; note?: string | null };
+
+export async function getTargetHistory(id: number, limit?: number): Promise<TargetEvent[]> {
+  return await invoke("get_target_history", { id, limit });
+}
+
+export type Engage = { rider: string; licence?: string | null; horse: string; club?: string | null };
+
+export async function listEngages(contestId: string, epreuve: number, account?: string | null): Promise<Engage[]> {
+  return await invoke("list_engages", { contestId, epreuve, account });
 }
 
 export async function listTargets(): Promise<Target[]> {
@@ -34,6 +196,9 @@ export async function addTarget(payload: {
   interval_hot_sec?: number;
   hot_from?: string | null;
   hot_to?: string | null;
+  account?: string | null;
+  kind?: WatchKind;
+  check_engaged?: boolean;
 }): Promise<void> {
   await invoke("add_target", { payload });
 }
@@ -53,3 +218,179 @@ export async function stopWatcher(): Promise<void> {
 export async function openUrl(url: string): Promise<void> {
   await invoke("open_url", { url });
 }
+
+export type SmtpSettings = {
+  enabled: boolean;
+  host: string;
+  port: number;
+  security: "none" | "starttls" | "tls";
+  username?: string | null;
+  password?: string | null;
+  from: string;
+  digest_enabled: boolean;
+  digest_hour: number;
+};
+
+export type EmailRecipient = { id: number; cavalier: string; email: string };
+
+export async function getSmtpSettings(): Promise<SmtpSettings> {
+  return await invoke("get_smtp_settings");
+}
+
+export async function setSmtpSettings(settings: SmtpSettings): Promise<void> {
+  await invoke("set_smtp_settings", { settings });
+}
+
+export async function listEmailRecipients(): Promise<EmailRecipient[]> {
+  return await invoke("list_email_recipients");
+}
+
+export async function addEmailRecipient(cavalier: string, email: string): Promise<void> {
+  await invoke("add_email_recipient", { cavalier, email });
+}
+
+export async function deleteEmailRecipient(id: number): Promise<void> {
+  await invoke("delete_email_recipient", { id });
+}
+
+export async function sendTestEmail(to: string): Promise<void> {
+  await invoke("send_test_email", { to });
+}
+
+export type PushChannel = {
+  id: number;
+  kind: "ntfy" | "gotify";
+  server_url: string;
+  topic?: string | null;
+  token?: string | null;
+  cavalier?: string | null;
+  target_id?: number | null;
+};
+
+export async function listPushChannels(): Promise<PushChannel[]> {
+  return await invoke("list_push_channels");
+}
+
+export async function addPushChannel(payload: Omit<PushChannel, "id">): Promise<number> {
+  return await invoke("add_push_channel", { payload });
+}
+
+export async function deletePushChannel(id: number): Promise<void> {
+  await invoke("delete_push_channel", { id });
+}
+
+export async function testPushChannel(id: number): Promise<void> {
+  await invoke("test_push_channel", { id });
+}
+
+export type AlertKind = "Opened" | "SlotFreed";
+
+export type QuietHours = { enabled: boolean; from: string; to: string; override_kinds: AlertKind[] };
+
+export type MuteState = {
+  quiet_hours: QuietHours;
+  quiet_now: boolean;
+  snoozes: { target_id: number; label: string; until: number }[];
+  queued: number;
+};
+
+export async function getMuteState(): Promise<MuteState> {
+  return await invoke("get_mute_state");
+}
+
+export async function setQuietHours(settings: QuietHours): Promise<void> {
+  await invoke("set_quiet_hours", { settings });
+}
+
+export async function snoozeTarget(id: number, minutes: number): Promise<number> {
+  return await invoke("snooze_target", { id, minutes });
+}
+
+export async function unsnoozeTarget(id: number): Promise<void> {
+  await invoke("unsnooze_target", { id });
+}
+
+export async function setSessionPassphrase(passphrase: string | null): Promise<void> {
+  await invoke("set_session_passphrase", { passphrase });
+}
+
+export async function unlockFfeSession(passphrase: string): Promise<boolean> {
+  return await invoke("unlock_ffe_session", { passphrase });
+}
+
+export type FfeAccount = { name: string; has_session: boolean; targets: number };
+
+export async function listFfeAccounts(): Promise<FfeAccount[]> {
+  return await invoke("list_ffe_accounts");
+}
+
+export async function addFfeAccount(name: string): Promise<string> {
+  return await invoke("add_ffe_account", { name });
+}
+
+export async function deleteFfeAccount(name: string): Promise<void> {
+  await invoke("delete_ffe_account", { name });
+}
+
+export async function checkFfeConnected(account?: string | null): Promise<boolean> {
+  return await invoke("check_ffe_connected", { account });
+}
+
+export async function ffeLogin(username: string, password: string, account?: string | null): Promise<void> {
+  await invoke("ffe_login_with_credentials", { username, password, account });
+}
+
+export async function ffeLogout(account?: string | null): Promise<void> {
+  await invoke("ffe_logout", { account });
+}
+
+export type LoginState =
+  | "Loading"
+  | "CookieBanner"
+  | "FormFilled"
+  | "Submitted"
+  | "Redirecting"
+  | "CaptchaRequired"
+  | "TwoFactorRequired"
+  | "Connected"
+  | "Failed";
+
+/** Payload de l'évènement `ffe:login` (une étape de la connexion). */
+export type LoginEvent = {
+  account: string;
+  state: LoginState;
+  detail?: string | null;
+  href?: string | null;
+  ts: number;
+};
+
+export type SessionHealth = {
+  account: string;
+  last_ok?: number | null;
+  last_check?: number | null;
+  last_error?: string | null;
+  expires_at?: number | null;
+  expired: boolean;
+};
+
+export async function getSessionHealth(account?: string | null): Promise<SessionHealth> {
+  return await invoke("get_session_health", { account });
+}
+
+export type HttpSettings = {
+  connect_timeout_sec: number;
+  read_timeout_sec: number;
+  total_timeout_sec: number;
+  proxy_url?: string | null;
+  user_agent: string;
+  max_body_bytes: number;
+  max_redirects: number;
+};
+
+export async function getHttpSettings(): Promise<HttpSettings> {
+  return await invoke("get_http_settings");
+}
+
+export async function setHttpSettings(settings: HttpSettings): Promise<void> {
+  await invoke("set_http_settings", { settings });
+}